@@ -0,0 +1,392 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+
+use crate::game::automaton::AutomatonKind;
+use crate::game::cursor::CursorConfig;
+use crate::game::ecosystem::SpeciesConfig;
+use crate::game::neighborhood::NeighborhoodKind;
+use crate::game::packets::NetworkConfig;
+use crate::game::palette::{DayNightConfig, Theme};
+use crate::game::world::{BoundaryMode, SimulationConfig};
+use crate::renderer::camera::CameraConfig;
+use crate::renderer::particles::ParticleConfig;
+use crate::renderer::postprocess::PostEffectConfig;
+use crate::renderer::screen::MonitorLayout;
+use crate::renderer::shape::CellShape;
+
+// Settings for the simulation on one monitor, used instead of the ones for every
+// monitor when they are set
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct MonitorConfig {
+    pub automaton: Option<AutomatonKind>,
+    pub rule: Option<String>,
+    pub seed_text: Option<String>,
+}
+
+// Another automaton drawn over the top of the main one, blended with it
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct LayerConfig {
+    pub automaton: AutomatonKind,
+    pub rule: Option<String>,
+    pub ticks_per_second: f64,
+    // Theme for its colors, the same as the main simulation's when left out
+    pub theme: Option<String>,
+    // How much its cells hide what's underneath, from 0 to 1
+    pub opacity: f32,
+}
+
+impl Default for LayerConfig {
+    fn default() -> Self {
+        Self {
+            automaton: AutomatonKind::PreditorPrey,
+            rule: None,
+            ticks_per_second: 60.0,
+            theme: None,
+            opacity: 0.5,
+        }
+    }
+}
+
+// How the music playing drives the simulation
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    // How much faster the simulation runs at full volume, 1 doubling its speed
+    pub speed: f32,
+    // How much the colors dim while it's quiet, from 0 to 1
+    pub brightness: f32,
+    // Cells dropped into each simulation on every beat of the bass
+    pub spawn: usize,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            brightness: 0.5,
+            spawn: 20,
+        }
+    }
+}
+
+// How the system's load feeds the simulation
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct UsageConfig {
+    // Predators dropped in each second while the CPU is fully busy, fewer the less
+    // busy it is
+    pub predators: f32,
+    // Prey dropped in for each percent of memory that is freed
+    pub prey: f32,
+}
+
+impl Default for UsageConfig {
+    fn default() -> Self {
+        Self {
+            predators: 20.0,
+            prey: 50.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub automaton: AutomatonKind,
+    // Simulation speed, independent of the frame rate
+    pub ticks_per_second: f64,
+    // Run as fast as possible, ignoring ticks_per_second
+    pub turbo: bool,
+    // Slow down while the CPU is busier than busy_cpu_percent, to leave it for
+    // whatever else is running
+    pub throttle: bool,
+    pub busy_cpu_percent: f64,
+    // Stop while every monitor is covered by a maximized or fullscreen window
+    pub pause_when_covered: bool,
+    // Show Windows notifications when a species dies out, the population beats its
+    // record or the simulation reseeds itself
+    pub notifications: bool,
+    // Memory in megabytes used to keep recent generations for rewinding, 0 to turn it off
+    pub rewind_memory_mb: usize,
+    // Save the world on exit and carry on from it next time
+    pub autosave: bool,
+    // Seed for the random number generator, or a new random one each run
+    pub seed: Option<u64>,
+    // Overrides the neighborhood every automaton's rules look at
+    pub neighborhood: Option<NeighborhoodKind>,
+    pub neighborhood_radius: usize,
+    // What happens at the edges of the world
+    pub boundary: BoundaryMode,
+    // Generations remembered to notice the simulation repeating itself, 0 to never check
+    pub cycle_window: usize,
+    // Prey in preditor_prey need grass to reproduce
+    pub grass: bool,
+    // Creatures in preditor_prey pass mutated traits on to their children
+    pub evolution: bool,
+    // B/S rulestring used by the life automaton
+    pub rule: String,
+    // Plaintext .cells pattern the life automaton starts from
+    pub life_pattern: Option<PathBuf>,
+    // Wolfram rule number (0-255) used by the elementary automaton
+    pub elementary_rule: u8,
+    // Run a separate simulation on each monitor instead of one across all of them
+    pub per_monitor: bool,
+    // Where the monitors are, "actual" follows their real positions so cells cross
+    // between them where their edges meet
+    pub monitor_layout: MonitorLayout,
+    // Monitors to leave alone, counting from 1 on the left
+    pub exclude_monitors: Vec<usize>,
+    // Simulate a world bigger than the window and slide across it, only when there's
+    // one simulation across every monitor
+    pub camera: Option<CameraConfig>,
+    // Overrides for each monitor from left to right, when running one per monitor
+    pub monitors: Vec<MonitorConfig>,
+    // Automata drawn over the top of the main one, in order from the bottom up
+    pub layers: Vec<LayerConfig>,
+    // Ctrl+Alt+P pauses, Ctrl+Alt+R reseeds, Ctrl+Alt+N switches to the next
+    // automaton and Ctrl+Alt+S takes a screenshot from anywhere
+    pub hotkeys: bool,
+    // Take commands from scripts over the \\.\pipe\automata-background named pipe
+    pub control_pipe: bool,
+    // Serve the HTTP API on localhost at this port, when built with the http feature
+    pub http_port: Option<u16>,
+    // Where screenshots are saved, the screenshots folder next to the config file
+    // when left out
+    pub screenshot_folder: Option<PathBuf>,
+    // Cells dropped in at random for every key pressed, 0 to ignore typing
+    pub typing: usize,
+    // Wall off the desktop icons so the cells flow around them
+    pub avoid_icons: bool,
+    // Draw the desktop wallpaper under the cells instead of black
+    pub wallpaper: bool,
+    // Effects applied to everything drawn, in order, before it reaches the screen.
+    // Only drawn with a graphics card.
+    pub post_processing: Vec<PostEffectConfig>,
+    // Graphics adapter to draw with, "integrated", "discrete" or part of its name.
+    // Left out, wgpu picks one
+    pub gpu: Option<String>,
+    // Width and height of each cell in pixels
+    pub cell_size: u32,
+    // Shape every cell is drawn as
+    pub cell_shape: CellShape,
+    // Pixels left empty between neighboring cells, for a grid over the simulation
+    pub cell_gap: u32,
+    // How opaque the cells and agents are drawn, from 0 to 1, to dim them down
+    pub opacity: f32,
+    // Seconds everything takes to fade in at startup and out when closed from the
+    // tray, 0 to appear and disappear straight away
+    pub fade_seconds: f32,
+    // How many times bigger the cells are drawn than cell_size, around the middle of
+    // the window
+    pub zoom: f32,
+    // Mirror the picture 2, 4 or 8 ways around the middle of the window, 1 for not
+    // at all
+    pub kaleidoscope: u32,
+    // Image drawn for each cell when the shape is a sprite
+    pub cell_sprite: Option<PathBuf>,
+    // Shader from the shaders folder next to this file to draw the cells with
+    // instead of the built in one
+    pub shader: Option<String>,
+    // Frames a dead cell takes to fade out, 0 to remove it straight away
+    pub fade_frames: u32,
+    // Color the background by how often each cell changes
+    pub heatmap: bool,
+    // Fly a flock of boids over the top of the automaton
+    pub boids: bool,
+    // Message written into the world whenever it's seeded
+    pub seed_text: Option<String>,
+    // Write the time into the world in live cells every minute
+    pub clock: bool,
+    // Show the clock's hours from 0 to 23 instead of 1 to 12
+    pub clock_24_hour: bool,
+    // Image or text file of walls that every automaton has to work around
+    pub obstacle_map: Option<PathBuf>,
+    // File every tick is recorded to
+    pub record: Option<PathBuf>,
+    // Recording played back instead of running the automaton
+    pub replay: Option<PathBuf>,
+    // Circuit file loaded by the wireworld automaton
+    pub wireworld_pattern: Option<PathBuf>,
+    // Rule parameters for preditor_prey
+    pub simulation: SimulationConfig,
+    // Species and food chain used by the ecosystem automaton
+    pub species: Vec<SpeciesConfig>,
+    // How predators and prey react to the mouse coming near them
+    pub cursor: CursorConfig,
+    // Name of the theme to start with, which can be changed from the tray menu
+    pub theme: String,
+    // Themes added to the built in ones
+    pub themes: Vec<Theme>,
+    // Change theme with the time of day instead of using `theme`
+    pub day_night: Option<DayNightConfig>,
+    // Speed up, brighten and spawn cells to whatever is playing on the speakers
+    pub audio: Option<AudioConfig>,
+    // Drop in predators as the CPU gets busy and prey as memory is freed
+    pub system_monitor: Option<UsageConfig>,
+    // Fly packets over the simulation as fast as data goes in and out over the network
+    pub network: Option<NetworkConfig>,
+    // Throw out sparks wherever a creature dies or is eaten
+    pub particles: Option<ParticleConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            automaton: AutomatonKind::PreditorPrey,
+            ticks_per_second: 60.0,
+            turbo: false,
+            throttle: true,
+            busy_cpu_percent: 75.0,
+            pause_when_covered: true,
+            notifications: true,
+            rewind_memory_mb: 64,
+            autosave: true,
+            seed: None,
+            neighborhood: None,
+            neighborhood_radius: 1,
+            boundary: BoundaryMode::Toroidal,
+            cycle_window: 60,
+            grass: false,
+            evolution: false,
+            rule: "B3/S23".to_string(),
+            life_pattern: None,
+            elementary_rule: 30,
+            per_monitor: false,
+            monitor_layout: MonitorLayout::default(),
+            exclude_monitors: Vec::new(),
+            camera: None,
+            monitors: Vec::new(),
+            layers: Vec::new(),
+            hotkeys: true,
+            control_pipe: true,
+            http_port: None,
+            screenshot_folder: None,
+            typing: 0,
+            avoid_icons: false,
+            wallpaper: true,
+            post_processing: Vec::new(),
+            gpu: None,
+            cell_size: 6,
+            cell_shape: CellShape::Square,
+            cell_gap: 0,
+            opacity: 1.0,
+            fade_seconds: 2.0,
+            zoom: 1.0,
+            kaleidoscope: 1,
+            cell_sprite: None,
+            shader: None,
+            fade_frames: 8,
+            heatmap: false,
+            boids: false,
+            seed_text: None,
+            clock: false,
+            clock_24_hour: true,
+            obstacle_map: None,
+            record: None,
+            replay: None,
+            wireworld_pattern: None,
+            simulation: SimulationConfig::default(),
+            species: SpeciesConfig::defaults(),
+            cursor: CursorConfig::default(),
+            theme: "classic".to_string(),
+            themes: Vec::new(),
+            day_night: None,
+            audio: None,
+            system_monitor: None,
+            network: None,
+            particles: None,
+        }
+    }
+}
+
+impl Config {
+    // The config for the simulation on monitor `index`, with that monitor's overrides
+    // applied. Only the first monitor records or plays back a recording.
+    pub fn for_monitor(&self, index: usize) -> Config {
+        let mut config = self.clone();
+        if let Some(monitor) = self.monitors.get(index) {
+            if let Some(automaton) = monitor.automaton {
+                config.automaton = automaton;
+            }
+            if let Some(rule) = &monitor.rule {
+                config.rule = rule.clone();
+            }
+            if let Some(text) = &monitor.seed_text {
+                config.seed_text = Some(text.clone());
+            }
+        }
+        if index > 0 {
+            config.record = None;
+            config.replay = None;
+        }
+        config
+    }
+
+    // The config for layer `index`, which doesn't save, record or write any text
+    pub fn for_layer(&self, index: usize) -> Config {
+        let mut config = self.clone();
+        if let Some(layer) = self.layers.get(index) {
+            config.automaton = layer.automaton;
+            if let Some(rule) = &layer.rule {
+                config.rule = rule.clone();
+            }
+            config.ticks_per_second = layer.ticks_per_second;
+        }
+        config.autosave = false;
+        config.rewind_memory_mb = 0;
+        config.seed_text = None;
+        config.record = None;
+        config.replay = None;
+        config
+    }
+
+    // Everything the app writes lives in %APPDATA%\automata-background
+    pub fn directory() -> PathBuf {
+        std::env::var_os("APPDATA")
+            .map(PathBuf::from)
+            .unwrap_or_default()
+            .join("automata-background")
+    }
+
+    pub fn path() -> PathBuf {
+        Self::directory().join("config.toml")
+    }
+
+    // When the config file was last changed, if it exists
+    pub fn modified() -> Option<SystemTime> {
+        std::fs::metadata(Self::path()).ok()?.modified().ok()
+    }
+
+    // Write a single top level setting into the config file, leaving everything else
+    // in it (comments included) as it was
+    pub fn save_setting(key: &str, value: impl Into<toml_edit::Value>) -> Result<(), String> {
+        let path = Self::path();
+        let contents = std::fs::read_to_string(&path).unwrap_or_default();
+        let mut document = contents
+            .parse::<toml_edit::DocumentMut>()
+            .map_err(|e| e.to_string())?;
+        document[key] = toml_edit::value(value);
+
+        std::fs::create_dir_all(Self::directory()).map_err(|e| e.to_string())?;
+        std::fs::write(&path, document.to_string()).map_err(|e| e.to_string())
+    }
+
+    pub fn load() -> Self {
+        let path = Self::path();
+        let Ok(contents) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        match toml::from_str(&contents) {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::warn!("Failed to parse {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use winapi::um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+use crate::renderer::control::{json_string, send};
+
+const USAGE: &str = "Usage: automata-background ctl <command>
+
+Commands:
+    pause
+    resume
+    reseed
+    set_rule <rule>       switch to life with a rule like B36/S23
+    set_speed <percent>   run at a percentage of ticks_per_second
+    set_automaton <name>  switch to an automaton like falling_sand
+    set_cell_size <size>  draw cells this many pixels wide
+    stats
+    settings";
+
+// The JSON line for a command given on the command line
+fn command_line(args: &[String]) -> Result<String, String> {
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args.as_slice() {
+        [name @ ("pause" | "resume" | "reseed" | "stats" | "settings")] => {
+            Ok(format!("{{\"command\":{}}}", json_string(name)))
+        }
+        ["set_rule", rule] => Ok(format!(
+            "{{\"command\":\"set_rule\",\"rule\":{}}}",
+            json_string(rule)
+        )),
+        ["set_speed", percent] => match percent.parse::<u32>() {
+            Ok(percent) => Ok(format!(
+                "{{\"command\":\"set_speed\",\"percent\":{}}}",
+                percent
+            )),
+            Err(_) => Err(format!("'{}' isn't a percentage", percent)),
+        },
+        ["set_automaton", name] => Ok(format!(
+            "{{\"command\":\"set_automaton\",\"automaton\":{}}}",
+            json_string(name)
+        )),
+        ["set_cell_size", size] => match size.parse::<u32>() {
+            Ok(size) => Ok(format!(
+                "{{\"command\":\"set_cell_size\",\"size\":{}}}",
+                size
+            )),
+            Err(_) => Err(format!("'{}' isn't a cell size", size)),
+        },
+        [] => Err(USAGE.to_string()),
+        _ => Err(format!("Unknown command: {}\n\n{}", args.join(" "), USAGE)),
+    }
+}
+
+// Send a command to the running background and print its response, returning the
+// exit code
+pub fn run(args: &[String]) -> i32 {
+    // The app has no console of its own, so print to the one it was started from
+    unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+
+    let response = command_line(args).and_then(|line| send(&line));
+    match response {
+        // Responses that worked always start with "ok": true
+        Ok(response) if response.starts_with("{\"ok\":true") => {
+            println!("{}", response);
+            0
+        }
+        Ok(response) => {
+            println!("{}", response);
+            1
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            2
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::game::boids::Boids;
+use crate::game::ecosystem::Ecosystem;
+use crate::game::elementary::Elementary;
+use crate::game::falling_sand::FallingSand;
+use crate::game::life::{LifeLike, Rule};
+use crate::game::neighborhood::Neighborhood;
+use crate::game::palette::{Blend, Theme};
+use crate::game::physarum::Physarum;
+use crate::game::plaintext::CellsPattern;
+use crate::game::preditor_prey::PreditorPrey;
+use crate::game::wator::WaTor;
+use crate::game::wireworld::{Pattern, WireWorld};
+use crate::game::world::{Cell, CellType, SimulationConfig, World};
+
+/// A free-floating agent drawn on top of the grid, measured in cells
+#[derive(Clone, Copy, Debug)]
+pub struct Agent {
+    pub position: [f32; 2],
+    pub velocity: [f32; 2],
+    pub color: [f32; 3],
+}
+
+/// A creature that was eaten or died during a step, and where it was
+#[derive(Clone, Copy)]
+pub struct Death {
+    pub row: usize,
+    pub col: usize,
+    pub cell: Cell,
+}
+
+/// The rules of a cellular automaton, stepping one world into the next
+pub trait Automaton {
+    /// Place the starting population into a cleared world
+    fn seed(&mut self, world: &mut World, ticks: u32, rng: &mut StdRng);
+
+    /// Compute the next generation of `active` into the cleared `inactive` world
+    fn step(&mut self, active: &World, inactive: &mut World, ticks: u32, rng: &mut StdRng);
+
+    /// The color to draw a cell with at tick `ticks`, or None if the cell isn't drawn
+    fn color(&self, cell: &Cell, ticks: u32) -> Option<[f32; 3]>;
+
+    /// How see-through a cell is and how it's blended with what's under it
+    fn blend(&self, _cell: &Cell) -> Blend {
+        Blend::default()
+    }
+
+    /// Switch to a different set of colors
+    fn set_theme(&mut self, _theme: &Theme) {}
+
+    /// The kind of cell text and other stamped seeds are drawn with, or None if the
+    /// automaton has no use for them
+    fn seed_cell_type(&self) -> Option<CellType> {
+        None
+    }
+
+    /// The color of the world's trail layer at full strength, or None if the
+    /// automaton doesn't use it
+    fn trail_color(&self) -> Option<[f32; 3]> {
+        None
+    }
+
+    /// Agents that move freely instead of living in the grid
+    fn agents(&self) -> &[Agent] {
+        &[]
+    }
+
+    /// Creatures that died since this was last called
+    fn take_deaths(&mut self) -> Vec<Death> {
+        Vec::new()
+    }
+
+    /// Catch up with the world changing size to `size`, moving anything kept by
+    /// position back inside it
+    fn resize(&mut self, _size: (usize, usize), _rng: &mut StdRng) {}
+
+    /// Pick up parameters changed while the simulation is running
+    fn configure(&mut self, _config: &SimulationConfig) {}
+
+    /// Where the mouse is in the world, in cells, for creatures that react to it
+    fn set_cursor(&mut self, _position: Option<(usize, usize)>) {}
+
+    /// Whether the world has died out and needs to be reseeded
+    fn is_extinct(&self, world: &World) -> bool;
+
+    /// Whether settling into a repeating loop means the world needs to be reseeded
+    fn reseed_on_cycle(&self) -> bool {
+        true
+    }
+}
+
+/// Every automaton the background can run
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutomatonKind {
+    PreditorPrey,
+    Life,
+    Wireworld,
+    Wator,
+    FallingSand,
+    Elementary,
+    Boids,
+    Physarum,
+    Ecosystem,
+}
+
+impl AutomatonKind {
+    /// Every kind, in the order the tray lists them
+    pub const ALL: [AutomatonKind; 9] = [
+        AutomatonKind::PreditorPrey,
+        AutomatonKind::Life,
+        AutomatonKind::Wireworld,
+        AutomatonKind::Wator,
+        AutomatonKind::FallingSand,
+        AutomatonKind::Elementary,
+        AutomatonKind::Boids,
+        AutomatonKind::Physarum,
+        AutomatonKind::Ecosystem,
+    ];
+
+    /// Name shown in the tray menu
+    pub fn name(self) -> &'static str {
+        match self {
+            AutomatonKind::PreditorPrey => "Preditor and prey",
+            AutomatonKind::Life => "Life",
+            AutomatonKind::Wireworld => "Wireworld",
+            AutomatonKind::Wator => "Wa-Tor",
+            AutomatonKind::FallingSand => "Falling sand",
+            AutomatonKind::Elementary => "Elementary",
+            AutomatonKind::Boids => "Boids",
+            AutomatonKind::Physarum => "Physarum",
+            AutomatonKind::Ecosystem => "Ecosystem",
+        }
+    }
+
+    /// Name used for it in the config file, like "falling_sand"
+    pub fn key(self) -> String {
+        toml::Value::try_from(self)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    /// The kind named `key` in the config file, the reverse of `key`
+    pub fn from_key(key: &str) -> Option<AutomatonKind> {
+        AutomatonKind::deserialize(toml::Value::String(key.to_string())).ok()
+    }
+
+    /// A new automaton of this kind, set up from the config
+    pub fn create(self, config: &Config) -> Box<dyn Automaton> {
+        // Each automaton has its own usual neighborhood unless the config picks one
+        let neighborhood = |default: Neighborhood| match config.neighborhood {
+            Some(kind) => Neighborhood::new(kind, config.neighborhood_radius),
+            None => default,
+        };
+
+        match self {
+            AutomatonKind::PreditorPrey => Box::new(PreditorPrey::new(
+                config.grass,
+                config.evolution,
+                config.simulation.clone(),
+                neighborhood(Neighborhood::moore()),
+                config.cursor.clone(),
+            )),
+            AutomatonKind::Life => {
+                let rule = Rule::parse(&config.rule).unwrap_or_else(|e| {
+                    tracing::warn!("Invalid rule {}, using B3/S23: {}", config.rule, e);
+                    Rule::CONWAY
+                });
+                let pattern = config.life_pattern.as_ref().and_then(|path| {
+                    CellsPattern::load(path)
+                        .map_err(|e| tracing::warn!("Failed to load {}: {}", path.display(), e))
+                        .ok()
+                });
+                Box::new(LifeLike::new(
+                    rule,
+                    neighborhood(Neighborhood::moore()),
+                    pattern,
+                ))
+            }
+            AutomatonKind::Wireworld => {
+                let pattern = config.wireworld_pattern.as_ref().and_then(|path| {
+                    Pattern::load(path)
+                        .map_err(|e| tracing::warn!("Failed to load {}: {}", path.display(), e))
+                        .ok()
+                });
+                Box::new(WireWorld::new(pattern))
+            }
+            AutomatonKind::Wator => Box::new(WaTor::new(
+                neighborhood(Neighborhood::von_neumann()),
+                config.cursor.clone(),
+            )),
+            AutomatonKind::FallingSand => Box::new(FallingSand::new()),
+            AutomatonKind::Elementary => Box::new(Elementary::new(config.elementary_rule)),
+            AutomatonKind::Boids => Box::new(Boids::new()),
+            AutomatonKind::Physarum => Box::new(Physarum::new()),
+            AutomatonKind::Ecosystem => Box::new(Ecosystem::new(
+                config.species.clone(),
+                neighborhood(Neighborhood::moore()),
+            )),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::game::automaton::{Agent, Automaton};
+use crate::game::world::{BoundaryMode, Cell, World};
+
+const BOID_COLOR: [f32; 3] = [0.8, 0.9, 1.0];
+
+// Distances are measured in cells, speeds in cells per tick
+const VIEW_RADIUS: f32 = 10.0;
+const SEPARATION_RADIUS: f32 = 3.0;
+const SEPARATION_WEIGHT: f32 = 0.05;
+const ALIGNMENT_WEIGHT: f32 = 0.05;
+const COHESION_WEIGHT: f32 = 0.005;
+const MIN_SPEED: f32 = 0.3;
+const MAX_SPEED: f32 = 1.0;
+const MAX_BOIDS: usize = 1000;
+
+// A flock of free-floating agents following the separation, alignment and
+// cohesion rules. Boids that fly off an absorbing edge are gone for good.
+pub struct Boids {
+    pub agents: Vec<Agent>,
+}
+
+impl Default for Boids {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Boids {
+    pub fn new() -> Self {
+        Self { agents: Vec::new() }
+    }
+
+    pub fn spawn(&mut self, size: (usize, usize), rng: &mut StdRng) {
+        if size.0 == 0 || size.1 == 0 {
+            self.agents.clear();
+            return;
+        }
+        let (width, height) = (size.0 as f32, size.1 as f32);
+        let count = (size.0 * size.1 / 300).clamp(1, MAX_BOIDS);
+
+        self.agents = (0..count)
+            .map(|_| {
+                let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+                let speed = rng.gen_range(MIN_SPEED..MAX_SPEED);
+                Agent {
+                    position: [rng.gen_range(0.0..width), rng.gen_range(0.0..height)],
+                    velocity: [angle.cos() * speed, angle.sin() * speed],
+                    color: BOID_COLOR,
+                }
+            })
+            .collect();
+    }
+
+    // The shortest offset from `from` to `to` on an axis of length `size`, which
+    // can cross the edge when the world wraps
+    fn wrapped_offset(boundary: BoundaryMode, from: f32, to: f32, size: f32) -> f32 {
+        let offset = to - from;
+        if boundary != BoundaryMode::Toroidal {
+            offset
+        } else if offset > size / 2.0 {
+            offset - size
+        } else if offset < -size / 2.0 {
+            offset + size
+        } else {
+            offset
+        }
+    }
+
+    pub fn update(&mut self, world: &World) {
+        let (width, height) = (world.size.0 as f32, world.size.1 as f32);
+        let boundary = world.boundary;
+        let previous = self.agents.clone();
+
+        self.agents.retain_mut(|agent| {
+            let mut separation = [0.0, 0.0];
+            let mut average_velocity = [0.0, 0.0];
+            let mut center_offset = [0.0, 0.0];
+            let mut neighbors = 0;
+
+            for other in &previous {
+                let dx =
+                    Self::wrapped_offset(boundary, agent.position[0], other.position[0], width);
+                let dy =
+                    Self::wrapped_offset(boundary, agent.position[1], other.position[1], height);
+                let distance = (dx * dx + dy * dy).sqrt();
+                if distance == 0.0 || distance > VIEW_RADIUS {
+                    continue;
+                }
+
+                // Steer away from boids that are too close
+                if distance < SEPARATION_RADIUS {
+                    separation[0] -= dx / distance;
+                    separation[1] -= dy / distance;
+                }
+                average_velocity[0] += other.velocity[0];
+                average_velocity[1] += other.velocity[1];
+                center_offset[0] += dx;
+                center_offset[1] += dy;
+                neighbors += 1;
+            }
+
+            if neighbors > 0 {
+                let n = neighbors as f32;
+                for i in 0..2 {
+                    // Match the heading of the neighbors and move toward their center
+                    agent.velocity[i] += separation[i] * SEPARATION_WEIGHT
+                        + (average_velocity[i] / n - agent.velocity[i]) * ALIGNMENT_WEIGHT
+                        + center_offset[i] / n * COHESION_WEIGHT;
+                }
+            }
+
+            let speed = (agent.velocity[0].powi(2) + agent.velocity[1].powi(2)).sqrt();
+            if speed > 0.0 {
+                let clamped = speed.clamp(MIN_SPEED, MAX_SPEED);
+                agent.velocity[0] *= clamped / speed;
+                agent.velocity[1] *= clamped / speed;
+            }
+
+            let x = boundary.resolve_f32(
+                agent.position[0] + agent.velocity[0],
+                &mut agent.velocity[0],
+                width,
+            );
+            let y = boundary.resolve_f32(
+                agent.position[1] + agent.velocity[1],
+                &mut agent.velocity[1],
+                height,
+            );
+            match (x, y) {
+                (Some(x), Some(y)) => {
+                    agent.position = [x, y];
+                    true
+                }
+                _ => false,
+            }
+        });
+    }
+}
+
+impl Automaton for Boids {
+    fn seed(&mut self, world: &mut World, _ticks: u32, rng: &mut StdRng) {
+        self.spawn(world.size, rng);
+    }
+
+    fn resize(&mut self, size: (usize, usize), _rng: &mut StdRng) {
+        if size.0 == 0 || size.1 == 0 {
+            return;
+        }
+        let size = [size.0, size.1].map(|size| size as f32);
+        for agent in &mut self.agents {
+            for (position, size) in agent.position.iter_mut().zip(size) {
+                *position = position.rem_euclid(size);
+            }
+        }
+    }
+
+    fn step(&mut self, active: &World, _inactive: &mut World, _ticks: u32, _rng: &mut StdRng) {
+        if active.size.0 == 0 || active.size.1 == 0 {
+            return;
+        }
+        self.update(active);
+    }
+
+    fn color(&self, _cell: &Cell, _ticks: u32) -> Option<[f32; 3]> {
+        None
+    }
+
+    fn agents(&self) -> &[Agent] {
+        &self.agents
+    }
+
+    fn is_extinct(&self, _world: &World) -> bool {
+        self.agents.is_empty()
+    }
+}
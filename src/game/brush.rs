@@ -0,0 +1,39 @@
+use crate::game::automaton::Automaton;
+use crate::game::world::CellType;
+
+// What dragging on the desktop paints, picked from the tray menu
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Brush {
+    #[default]
+    Prey,
+    Predators,
+    Walls,
+    Eraser,
+}
+
+impl Brush {
+    pub const ALL: [Brush; 4] = [Brush::Prey, Brush::Predators, Brush::Walls, Brush::Eraser];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Brush::Prey => "Prey",
+            Brush::Predators => "Predators",
+            Brush::Walls => "Walls",
+            Brush::Eraser => "Eraser",
+        }
+    }
+
+    // The cell painted into a world run by `automaton`, or None if it has no such
+    // cell. Prey stands for whatever the automaton is seeded with, like live cells
+    // in life, and predators only live alongside prey.
+    pub fn cell_type(self, automaton: &dyn Automaton) -> Option<CellType> {
+        match self {
+            Brush::Prey => automaton.seed_cell_type(),
+            Brush::Predators => {
+                (automaton.seed_cell_type() == Some(CellType::Prey)).then_some(CellType::Preditor)
+            }
+            Brush::Walls => Some(CellType::Wall),
+            Brush::Eraser => Some(CellType::Empty),
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use serde::Deserialize;
+
+use crate::game::world::CellType;
+
+// What a creature does when the mouse comes near it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorReaction {
+    #[default]
+    Ignore,
+    Attract,
+    Flee,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct CursorConfig {
+    pub predators: CursorReaction,
+    pub prey: CursorReaction,
+    // How close the mouse has to be, in cells, for anything to notice it
+    pub radius: usize,
+}
+
+impl Default for CursorConfig {
+    fn default() -> Self {
+        Self {
+            predators: CursorReaction::Ignore,
+            prey: CursorReaction::Ignore,
+            radius: 20,
+        }
+    }
+}
+
+// Where the mouse is in a world, for the automata whose creatures react to it
+#[derive(Clone, Debug, Default)]
+pub struct Cursor {
+    pub config: CursorConfig,
+    // Measured in cells, or None when the mouse is over another monitor
+    pub position: Option<(usize, usize)>,
+}
+
+impl Cursor {
+    pub fn new(config: CursorConfig) -> Self {
+        Self {
+            config,
+            position: None,
+        }
+    }
+
+    fn distance(position: (usize, usize), to: (usize, usize)) -> usize {
+        let dx = position.0.abs_diff(to.0);
+        let dy = position.1.abs_diff(to.1);
+        dx * dx + dy * dy
+    }
+
+    // How a `cell_type` creature at `from` reacts, ignoring the mouse when it's too
+    // far away to notice
+    pub fn reaction(&self, cell_type: CellType, from: (usize, usize)) -> CursorReaction {
+        let Some(position) = self.position else {
+            return CursorReaction::Ignore;
+        };
+        if Self::distance(position, from) > self.config.radius * self.config.radius {
+            return CursorReaction::Ignore;
+        }
+        match cell_type {
+            CellType::Preditor => self.config.predators,
+            CellType::Prey => self.config.prey,
+            _ => CursorReaction::Ignore,
+        }
+    }
+
+    // The place to move to out of `candidates`: the closest to the mouse or the
+    // furthest from it. Creatures that ignore it get None and move however they
+    // usually would.
+    pub fn steer(
+        &self,
+        reaction: CursorReaction,
+        candidates: impl Iterator<Item = (usize, usize)>,
+    ) -> Option<(usize, usize)> {
+        let position = self.position?;
+        match reaction {
+            CursorReaction::Ignore => None,
+            CursorReaction::Attract => {
+                candidates.min_by_key(|&candidate| Self::distance(position, candidate))
+            }
+            CursorReaction::Flee => {
+                candidates.max_by_key(|&candidate| Self::distance(position, candidate))
+            }
+        }
+    }
+}
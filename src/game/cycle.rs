@@ -0,0 +1,44 @@
+use std::collections::VecDeque;
+
+// Notices when the simulation has settled into a short repeating loop by remembering
+// a hash of each of the last few generations
+pub struct CycleDetector {
+    // How many generations are remembered, 0 turns detection off
+    pub window: usize,
+    hashes: VecDeque<u64>,
+}
+
+impl CycleDetector {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            hashes: VecDeque::with_capacity(window),
+        }
+    }
+
+    // Add the hash of the newest generation. Returns the length of the cycle if the
+    // same hash was seen within the window.
+    pub fn check(&mut self, hash: u64) -> Option<usize> {
+        if self.window == 0 {
+            return None;
+        }
+
+        let period = self
+            .hashes
+            .iter()
+            .rev()
+            .position(|&previous| previous == hash)
+            .map(|index| index + 1);
+
+        if self.hashes.len() == self.window {
+            self.hashes.pop_front();
+        }
+        self.hashes.push_back(hash);
+
+        period
+    }
+
+    pub fn clear(&mut self) {
+        self.hashes.clear();
+    }
+}
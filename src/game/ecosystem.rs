@@ -0,0 +1,273 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::game::automaton::{Automaton, Death};
+use crate::game::neighborhood::Neighborhood;
+use crate::game::palette::{Blend, BlendMode};
+use crate::game::seeding::{seed_count, SeedLayout, Seeder};
+use crate::game::world::{Cell, CellType, World};
+
+// One species of an ecosystem as written in the config file
+#[derive(Clone, Debug, Deserialize)]
+pub struct SpeciesConfig {
+    pub name: String,
+    pub color: [f32; 3],
+    // How opaque the species is drawn, from 0 to 1
+    #[serde(default = "SpeciesConfig::default_alpha")]
+    pub alpha: f32,
+    // "additive" makes the species glow over whatever is under it
+    #[serde(default)]
+    pub blend: BlendMode,
+    // Ticks between attempts to reproduce into an empty neighbor
+    pub breed_interval: u32,
+    // Chance each tick of reproducing, used instead of the breed interval when set
+    #[serde(default)]
+    pub breed_chance: Option<f64>,
+    // Chance of catching a neighbor it tries to eat
+    #[serde(default = "SpeciesConfig::default_catch_chance")]
+    pub catch_chance: f64,
+    // Ticks before dying of old age, or forever if missing
+    pub lifespan: Option<u32>,
+    // Names of the species this one converts into its own kind
+    #[serde(default)]
+    pub eats: Vec<String>,
+    // Percentage of cells placed when the world is seeded
+    #[serde(default = "SpeciesConfig::default_seed_density")]
+    pub seed_density: f64,
+    // Exact number to place instead, whatever the size of the world
+    #[serde(default)]
+    pub seed_count: Option<usize>,
+}
+
+impl SpeciesConfig {
+    fn default_catch_chance() -> f64 {
+        1.0
+    }
+
+    fn default_seed_density() -> f64 {
+        0.2
+    }
+
+    fn default_alpha() -> f32 {
+        1.0
+    }
+
+    // Three species chasing each other in a circle, like rock paper scissors
+    pub fn defaults() -> Vec<SpeciesConfig> {
+        vec![
+            SpeciesConfig {
+                name: "red".to_string(),
+                color: [1.0, 0.2, 0.2],
+                alpha: 1.0,
+                blend: BlendMode::Normal,
+                breed_interval: 20,
+                breed_chance: None,
+                catch_chance: 1.0,
+                lifespan: Some(80),
+                eats: vec!["green".to_string()],
+                seed_density: 0.2,
+                seed_count: None,
+            },
+            SpeciesConfig {
+                name: "green".to_string(),
+                color: [0.2, 1.0, 0.2],
+                alpha: 1.0,
+                blend: BlendMode::Normal,
+                breed_interval: 20,
+                breed_chance: None,
+                catch_chance: 1.0,
+                lifespan: Some(80),
+                eats: vec!["blue".to_string()],
+                seed_density: 0.2,
+                seed_count: None,
+            },
+            SpeciesConfig {
+                name: "blue".to_string(),
+                color: [0.2, 0.4, 1.0],
+                alpha: 1.0,
+                blend: BlendMode::Normal,
+                breed_interval: 20,
+                breed_chance: None,
+                catch_chance: 1.0,
+                lifespan: Some(80),
+                eats: vec!["red".to_string()],
+                seed_density: 0.2,
+                seed_count: None,
+            },
+        ]
+    }
+}
+
+// Any number of species living in CellType::Species cells. Every creature moves,
+// breeds into empty space and eats whichever neighbors the food chain allows.
+pub struct Ecosystem {
+    pub species: Vec<SpeciesConfig>,
+    // food_chain[a][b] is true when species a eats species b
+    pub food_chain: Vec<Vec<bool>>,
+    pub neighborhood: Neighborhood,
+    // Creatures that died in the last step
+    deaths: Vec<Death>,
+}
+
+impl Ecosystem {
+    pub fn new(species: Vec<SpeciesConfig>, neighborhood: Neighborhood) -> Self {
+        let species = species
+            .into_iter()
+            .take(u8::MAX as usize)
+            .collect::<Vec<_>>();
+
+        let food_chain = species
+            .iter()
+            .map(|hunter| {
+                for name in &hunter.eats {
+                    if !species.iter().any(|prey| &prey.name == name) {
+                        tracing::warn!("{} eats unknown species {}", hunter.name, name);
+                    }
+                }
+                species
+                    .iter()
+                    .map(|prey| hunter.eats.contains(&prey.name))
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            species,
+            food_chain,
+            neighborhood,
+            deaths: Vec::new(),
+        }
+    }
+
+    // Whether a creature of `species` can move into a cell of this type
+    fn can_enter(&self, species: u8, cell_type: CellType) -> bool {
+        match cell_type {
+            CellType::Empty => true,
+            CellType::Species(other) => self.food_chain[species as usize][other as usize],
+            _ => false,
+        }
+    }
+}
+
+impl Automaton for Ecosystem {
+    fn seed(&mut self, world: &mut World, ticks: u32, rng: &mut StdRng) {
+        let seeder = Seeder::new(SeedLayout::Uniform, world, rng);
+        for (index, species) in self.species.iter().enumerate() {
+            let count = seed_count(world, species.seed_density, species.seed_count);
+            let cell = Cell::new(CellType::Species(index as u8), ticks);
+            seeder.place(world, count, cell, rng);
+        }
+    }
+
+    fn step(&mut self, active: &World, inactive: &mut World, ticks: u32, rng: &mut StdRng) {
+        let (rows, cols) = active.size;
+        self.deaths.clear();
+
+        for row in 0..rows {
+            for col in 0..cols {
+                let cell = active.get_cell(row, col);
+                let CellType::Species(species) = cell.cell_type else {
+                    continue;
+                };
+                let config = &self.species[species as usize];
+                let age = ticks - cell.created_at;
+
+                if config.lifespan.is_some_and(|lifespan| age > lifespan) {
+                    self.deaths.push(Death { row, col, cell });
+                    continue;
+                }
+
+                // Look around for somewhere to go, dying of overcrowding if there's nowhere.
+                // Wandering off an absorbing edge is fatal too.
+                let mut target = None;
+                for _ in 0..self.neighborhood.len() {
+                    let Some((neighbor_row, neighbor_col)) =
+                        self.neighborhood.random_neighbor(active, row, col, rng)
+                    else {
+                        break;
+                    };
+                    let neighbor_type = inactive.cell_type(neighbor_row, neighbor_col);
+                    if self.can_enter(species, neighbor_type) {
+                        target = Some((neighbor_row, neighbor_col, neighbor_type));
+                        break;
+                    }
+                }
+                let Some((neighbor_row, neighbor_col, neighbor_type)) = target else {
+                    self.deaths.push(Death { row, col, cell });
+                    continue;
+                };
+
+                let breeds = match neighbor_type {
+                    // Eating a neighbor turns it into a newborn of this species
+                    CellType::Species(_) => true,
+                    _ => match config.breed_chance {
+                        Some(chance) => rng.gen_bool(chance.clamp(0.0, 1.0)),
+                        None => age.is_multiple_of(config.breed_interval.max(1)),
+                    },
+                };
+
+                let escaped = matches!(neighbor_type, CellType::Species(_))
+                    && !rng.gen_bool(config.catch_chance.clamp(0.0, 1.0));
+
+                if let (CellType::Species(_), false) = (neighbor_type, escaped) {
+                    self.deaths.push(Death {
+                        row: neighbor_row,
+                        col: neighbor_col,
+                        cell: inactive.get_cell(neighbor_row, neighbor_col),
+                    });
+                }
+
+                if escaped {
+                    // The neighbor got away, so stay put
+                    inactive.set_cell(row, col, cell);
+                } else if breeds {
+                    let child = Cell::new(cell.cell_type, ticks);
+                    inactive.set_cell(neighbor_row, neighbor_col, child);
+                    inactive.set_cell(row, col, cell);
+                } else {
+                    inactive.set_cell(neighbor_row, neighbor_col, cell);
+                }
+            }
+        }
+    }
+
+    fn seed_cell_type(&self) -> Option<CellType> {
+        Some(CellType::Species(0))
+    }
+
+    fn color(&self, cell: &Cell, _ticks: u32) -> Option<[f32; 3]> {
+        match cell.cell_type {
+            CellType::Species(species) => Some(self.species[species as usize].color),
+            _ => None,
+        }
+    }
+
+    fn blend(&self, cell: &Cell) -> Blend {
+        match cell.cell_type {
+            CellType::Species(species) => {
+                let species = &self.species[species as usize];
+                Blend {
+                    alpha: species.alpha.clamp(0.0, 1.0),
+                    mode: species.blend,
+                }
+            }
+            _ => Blend::default(),
+        }
+    }
+
+    fn take_deaths(&mut self) -> Vec<Death> {
+        std::mem::take(&mut self.deaths)
+    }
+
+    fn is_extinct(&self, world: &World) -> bool {
+        // Reseed once fewer than two species are left
+        let mut alive = vec![false; self.species.len()];
+        for cell_type in world.types() {
+            if let CellType::Species(species) = cell_type {
+                alive[*species as usize] = true;
+            }
+        }
+        alive.iter().filter(|&&alive| alive).count() < 2.min(self.species.len())
+    }
+}
@@ -0,0 +1,90 @@
+use rand::rngs::StdRng;
+
+use crate::game::automaton::Automaton;
+use crate::game::world::{Cell, CellType, World};
+
+const ALIVE_COLOR: [f32; 3] = [0.9, 0.9, 1.0];
+
+// A one dimensional elementary cellular automaton. Each tick a new generation is
+// computed along the top of the screen and the older generations scroll down.
+pub struct Elementary {
+    // Wolfram rule number, each bit is the next state for one 3 cell neighborhood
+    pub rule: u8,
+}
+
+impl Elementary {
+    pub fn new(rule: u8) -> Self {
+        Self { rule }
+    }
+
+    fn is_alive(world: &World, x: usize, y: usize) -> bool {
+        world.cell_type(x, y) == CellType::Alive
+    }
+
+    // Past an absorbing edge every cell is dead
+    fn is_neighbor_alive(world: &World, x: usize, y: usize, dx: isize) -> bool {
+        world
+            .offset((x, y), (dx, 0))
+            .is_some_and(|(x, y)| Self::is_alive(world, x, y))
+    }
+}
+
+impl Automaton for Elementary {
+    fn seed(&mut self, world: &mut World, ticks: u32, _rng: &mut StdRng) {
+        // A single cell in the middle of the top line
+        let (width, height) = world.size;
+        if width == 0 || height == 0 {
+            return;
+        }
+        world.set_cell(width / 2, height - 1, Cell::new(CellType::Alive, ticks));
+    }
+
+    fn step(&mut self, active: &World, inactive: &mut World, ticks: u32, _rng: &mut StdRng) {
+        let (width, height) = active.size;
+        // A window shorter than one cell has no top line
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        // Scroll every generation down by one line. Walls stay where they are and
+        // anything scrolling into one is lost.
+        for x in 0..width {
+            for y in 0..height - 1 {
+                let above = active.get_cell(x, y + 1);
+                let blocked = inactive.cell_type(x, y) == CellType::Wall;
+                if above.cell_type != CellType::Wall && !blocked {
+                    inactive.set_cell(x, y, above);
+                }
+            }
+        }
+
+        // Compute the next generation along the top
+        let top = height - 1;
+        for x in 0..width {
+            let left = Self::is_neighbor_alive(active, x, top, -1) as u8;
+            let center = Self::is_alive(active, x, top) as u8;
+            let right = Self::is_neighbor_alive(active, x, top, 1) as u8;
+            let neighborhood = left << 2 | center << 1 | right;
+
+            if self.rule >> neighborhood & 1 == 1 && active.cell_type(x, top) != CellType::Wall {
+                inactive.set_cell(x, top, Cell::new(CellType::Alive, ticks));
+            }
+        }
+    }
+
+    fn color(&self, cell: &Cell, _ticks: u32) -> Option<[f32; 3]> {
+        match cell.cell_type {
+            CellType::Alive => Some(ALIVE_COLOR),
+            _ => None,
+        }
+    }
+
+    fn is_extinct(&self, world: &World) -> bool {
+        // Reseed once the newest generation has died out
+        let (width, height) = world.size;
+        if width == 0 || height == 0 {
+            return false;
+        }
+        !(0..width).any(|x| Self::is_alive(world, x, height - 1))
+    }
+}
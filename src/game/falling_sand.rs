@@ -0,0 +1,244 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::game::automaton::Automaton;
+use crate::game::world::{Cell, CellType, World};
+
+const SAND_COLOR: [f32; 3] = [0.76, 0.7, 0.5];
+const WATER_COLOR: [f32; 3] = [0.1, 0.3, 0.9];
+const STONE_COLOR: [f32; 3] = [0.5, 0.5, 0.5];
+const FIRE_COLOR: [f32; 3] = [1.0, 0.5, 0.1];
+
+// Sand and water pour in from emitters at the top of the screen, pile up on stone
+// ledges and drain away at the bottom. Fire rises from the bottom and is put out by water.
+pub struct FallingSand {
+    // x position and element of everything pouring in from the top
+    emitters: Vec<(usize, CellType)>,
+    // Cells that have already moved this tick
+    moved: Vec<bool>,
+}
+
+impl Default for FallingSand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FallingSand {
+    pub fn new() -> Self {
+        Self {
+            emitters: Vec::new(),
+            moved: Vec::new(),
+        }
+    }
+
+    fn emitter_count(width: usize) -> usize {
+        (width / 24).max(2)
+    }
+
+    // Sand or water pouring in at a random spot along the top
+    fn emitter(width: usize, rng: &mut StdRng) -> (usize, CellType) {
+        let x = rng.gen_range(0..width);
+        let element = if rng.gen_bool(0.5) {
+            CellType::Sand
+        } else {
+            CellType::Water
+        };
+        (x, element)
+    }
+
+    fn is_empty(world: &World, x: usize, y: usize) -> bool {
+        world.cell_type(x, y) == CellType::Empty
+    }
+
+    // Whether a falling cell of `cell_type` can swap places with whatever is at (x, y)
+    fn can_displace(world: &World, cell_type: CellType, x: usize, y: usize) -> bool {
+        match world.cell_type(x, y) {
+            CellType::Empty => true,
+            // Sand sinks through water
+            CellType::Water => cell_type == CellType::Sand,
+            _ => false,
+        }
+    }
+
+    fn swap(&mut self, world: &mut World, from: (usize, usize), to: (usize, usize)) {
+        let a = world.get_cell(from.0, from.1);
+        let b = world.get_cell(to.0, to.1);
+        world.set_cell(from.0, from.1, b);
+        world.set_cell(to.0, to.1, a);
+        self.moved[to.0 * world.size.1 + to.1] = true;
+    }
+
+    // Try to move down, then diagonally down, then (for liquids) sideways
+    fn fall(&mut self, world: &mut World, x: usize, y: usize, flows: bool, rng: &mut StdRng) {
+        let cell_type = world.cell_type(x, y);
+
+        if y > 0 && Self::can_displace(world, cell_type, x, y - 1) {
+            self.swap(world, (x, y), (x, y - 1));
+            return;
+        }
+
+        // The sides follow the world's boundary mode, and are skipped past an absorbing edge
+        let dir = if rng.gen_bool(0.5) { 1 } else { -1 };
+        let sides = [dir, -dir]
+            .into_iter()
+            .filter_map(|dx| world.offset((x, y), (dx, 0)))
+            .map(|(side, _)| side)
+            .collect::<Vec<_>>();
+
+        if y > 0 {
+            for &side in &sides {
+                if Self::can_displace(world, cell_type, side, y - 1) {
+                    self.swap(world, (x, y), (side, y - 1));
+                    return;
+                }
+            }
+        }
+
+        if flows {
+            for &side in &sides {
+                if Self::is_empty(world, side, y) {
+                    self.swap(world, (x, y), (side, y));
+                    return;
+                }
+            }
+        }
+    }
+
+    fn burn(&mut self, world: &mut World, x: usize, y: usize, rng: &mut StdRng) {
+        let (_, height) = world.size;
+
+        // Fire flickers out after a while, or straight away next to water
+        let touching_water = [
+            world.offset((x, y), (1, 0)),
+            world.offset((x, y), (-1, 0)),
+            Some((x, (y + 1).min(height - 1))),
+            Some((x, y.saturating_sub(1))),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|(nx, ny)| world.cell_type(nx, ny) == CellType::Water);
+        if touching_water || rng.gen_range(0..10) == 0 {
+            world.set_cell_type(x, y, CellType::Empty);
+            return;
+        }
+
+        // Otherwise it rises, wavering left and right
+        if y + 1 < height {
+            let dx = rng.gen_range(-1..=1);
+            let Some((nx, _)) = world.offset((x, y), (dx, 0)) else {
+                return;
+            };
+            if Self::is_empty(world, nx, y + 1) {
+                self.swap(world, (x, y), (nx, y + 1));
+            }
+        }
+    }
+}
+
+impl Automaton for FallingSand {
+    fn seed(&mut self, world: &mut World, ticks: u32, rng: &mut StdRng) {
+        let (width, height) = world.size;
+        if width < 8 || height < 8 {
+            return;
+        }
+
+        // Scatter stone ledges for the sand and water to pile up on
+        for _ in 0..(width / 16).max(1) {
+            let length = rng.gen_range(4..(width / 4).max(5));
+            let x = rng.gen_range(0..width - length);
+            let y = rng.gen_range(1..height * 3 / 4);
+            for x in x..x + length {
+                world.set_cell(x, y, Cell::new(CellType::Stone, ticks));
+            }
+        }
+
+        self.emitters = (0..Self::emitter_count(width))
+            .map(|_| Self::emitter(width, rng))
+            .collect();
+    }
+
+    fn resize(&mut self, size: (usize, usize), rng: &mut StdRng) {
+        let (width, height) = size;
+        if width < 8 || height < 8 {
+            self.emitters.clear();
+            return;
+        }
+        // Emitters that are now off the edge go, and a wider world gets more
+        let count = Self::emitter_count(width);
+        self.emitters.retain(|&(x, _)| x < width);
+        self.emitters.truncate(count);
+        while self.emitters.len() < count {
+            self.emitters.push(Self::emitter(width, rng));
+        }
+    }
+
+    fn step(&mut self, active: &World, inactive: &mut World, ticks: u32, rng: &mut StdRng) {
+        // Elements are moved in place from the bottom up, so a whole column
+        // of sand can fall in a single tick
+        inactive.copy_cells(active);
+        self.moved.clear();
+        self.moved.resize(inactive.len(), false);
+
+        let (width, height) = inactive.size;
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        for (x, y) in active.bottom_up() {
+            if self.moved[x * height + y] {
+                continue;
+            }
+
+            match inactive.cell_type(x, y) {
+                CellType::Sand | CellType::Water if y == 0 => {
+                    // The bottom of the screen slowly drains
+                    if rng.gen_range(0..8) == 0 {
+                        inactive.set_cell_type(x, y, CellType::Empty);
+                    } else {
+                        let flows = inactive.cell_type(x, y) == CellType::Water;
+                        self.fall(inactive, x, y, flows, rng);
+                    }
+                }
+                CellType::Sand => self.fall(inactive, x, y, false, rng),
+                CellType::Water => self.fall(inactive, x, y, true, rng),
+                CellType::Fire => self.burn(inactive, x, y, rng),
+                _ => {}
+            }
+        }
+
+        for &(x, element) in &self.emitters {
+            if Self::is_empty(inactive, x, height - 1) {
+                inactive.set_cell(x, height - 1, Cell::new(element, ticks));
+            }
+        }
+
+        // Fire occasionally breaks out along the bottom
+        if rng.gen_range(0..4) == 0 {
+            let x = rng.gen_range(0..width);
+            if Self::is_empty(inactive, x, 0) {
+                inactive.set_cell(x, 0, Cell::new(CellType::Fire, ticks));
+            }
+        }
+    }
+
+    fn seed_cell_type(&self) -> Option<CellType> {
+        Some(CellType::Sand)
+    }
+
+    fn color(&self, cell: &Cell, _ticks: u32) -> Option<[f32; 3]> {
+        match cell.cell_type {
+            CellType::Sand => Some(SAND_COLOR),
+            CellType::Water => Some(WATER_COLOR),
+            CellType::Stone => Some(STONE_COLOR),
+            CellType::Fire => Some(FIRE_COLOR),
+            _ => None,
+        }
+    }
+
+    fn is_extinct(&self, world: &World) -> bool {
+        // The emitters keep this going forever, unless the world was reset
+        // before it had been seeded
+        !world.types().contains(&CellType::Stone)
+    }
+}
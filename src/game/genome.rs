@@ -0,0 +1,48 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+
+// Heritable traits passed from parent to child, with small mutations
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Genome {
+    // Chance out of 100 of moving each tick
+    pub speed: u16,
+    // Ticks between reproducing
+    pub breed_interval: u16,
+    // Ticks before dying of old age, or forever at u16::MAX
+    pub lifespan: u16,
+}
+
+impl Genome {
+    pub const fn new(speed: u16, breed_interval: u16, lifespan: u16) -> Self {
+        Self {
+            speed,
+            breed_interval,
+            lifespan,
+        }
+    }
+
+    pub fn outlived(&self, age: u32) -> bool {
+        self.lifespan != u16::MAX && age > self.lifespan as u32
+    }
+
+    // A copy of this genome where each trait may drift by up to a tenth
+    pub fn mutate(&self, rng: &mut StdRng) -> Genome {
+        let mut drift = |value: u16, min: u16, max: u16| -> u16 {
+            let range = (value / 10).max(1) as i32;
+            let change = rng.gen_range(-range..=range);
+            (value as i32 + change).clamp(min as i32, max as i32) as u16
+        };
+
+        Genome {
+            speed: drift(self.speed, 1, 100),
+            breed_interval: drift(self.breed_interval, 1, u16::MAX),
+            lifespan: drift(self.lifespan, 1, u16::MAX),
+        }
+    }
+}
+
+impl Default for Genome {
+    fn default() -> Self {
+        Genome::new(100, 1, u16::MAX)
+    }
+}
@@ -0,0 +1,68 @@
+use crate::game::world::{CellType, World};
+
+// Colors from the coolest cells to the hottest
+const GRADIENT: [[f32; 3]; 4] = [
+    [0.05, 0.0, 0.3],
+    [0.7, 0.0, 0.3],
+    [1.0, 0.55, 0.0],
+    [1.0, 1.0, 0.6],
+];
+// Cells cooler than this aren't drawn at all
+const MIN_HEAT: f32 = 0.05;
+
+// Counts how often each cell has changed over the whole run, so the parts of the
+// screen where things keep happening show up after a while
+pub struct Heatmap {
+    changes: Vec<u32>,
+    previous: Vec<CellType>,
+    most_changes: u32,
+}
+
+impl Heatmap {
+    pub fn new(size: (usize, usize)) -> Self {
+        Self {
+            changes: vec![0; size.0 * size.1],
+            previous: vec![CellType::Empty; size.0 * size.1],
+            most_changes: 0,
+        }
+    }
+
+    pub fn record(&mut self, world: &World) {
+        for ((cell_type, previous), changes) in world
+            .types()
+            .iter()
+            .zip(&mut self.previous)
+            .zip(&mut self.changes)
+        {
+            if cell_type != previous {
+                *previous = *cell_type;
+                *changes += 1;
+                self.most_changes = self.most_changes.max(*changes);
+            }
+        }
+    }
+
+    // How hot a cell is compared to the hottest one, between 0 and 1. Changes are
+    // counted on a log scale so a few very busy cells don't wash everything else out.
+    fn heat(&self, index: usize) -> f32 {
+        if self.most_changes == 0 {
+            return 0.0;
+        }
+        let changes = self.changes.get(index).copied().unwrap_or(0);
+        (changes as f32).ln_1p() / (self.most_changes as f32).ln_1p()
+    }
+
+    // The color of a cell on the heat gradient, or None if it has hardly changed
+    pub fn color(&self, index: usize) -> Option<[f32; 3]> {
+        let heat = self.heat(index);
+        if heat < MIN_HEAT {
+            return None;
+        }
+
+        let position = heat * (GRADIENT.len() - 1) as f32;
+        let stop = (position as usize).min(GRADIENT.len() - 2);
+        let t = position - stop as f32;
+        let (from, to) = (GRADIENT[stop], GRADIENT[stop + 1]);
+        Some([0, 1, 2].map(|i| from[i] + (to[i] - from[i]) * t))
+    }
+}
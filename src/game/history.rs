@@ -0,0 +1,43 @@
+use std::collections::VecDeque;
+
+use crate::game::world::World;
+
+// Copies of the most recent generations, so the simulation can be stepped backwards.
+// Only the grid is kept, agents carry on from wherever they are.
+pub struct RewindBuffer {
+    // How much memory the snapshots can take up in total
+    pub budget_bytes: usize,
+    snapshots: VecDeque<(u32, World)>,
+}
+
+impl RewindBuffer {
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            budget_bytes,
+            snapshots: VecDeque::new(),
+        }
+    }
+
+    fn snapshot_bytes(world: &World) -> usize {
+        world.cell_bytes() + world.trail.len() * std::mem::size_of::<f32>()
+    }
+
+    // Remember the world as it was at `tick`, forgetting the oldest snapshots once
+    // the budget is used up
+    pub fn push(&mut self, tick: u32, world: &World) {
+        let capacity = self.budget_bytes / Self::snapshot_bytes(world).max(1);
+        if capacity == 0 {
+            return;
+        }
+
+        while self.snapshots.len() >= capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back((tick, world.clone()));
+    }
+
+    // The most recent snapshot and the tick it was taken at
+    pub fn pop(&mut self) -> Option<(u32, World)> {
+        self.snapshots.pop_back()
+    }
+}
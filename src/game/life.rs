@@ -0,0 +1,140 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::game::automaton::Automaton;
+use crate::game::neighborhood::Neighborhood;
+use crate::game::plaintext::CellsPattern;
+use crate::game::world::{Cell, CellType, World};
+
+const ALIVE_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+
+// A Life-like rule in B/S notation, e.g. "B3/S23" for Conway's Game of Life
+// or "B36/S23" for HighLife. Each array is indexed by the number of live neighbors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub birth: [bool; 9],
+    pub survival: [bool; 9],
+}
+
+impl Rule {
+    pub const CONWAY: Rule = Rule {
+        birth: [false, false, false, true, false, false, false, false, false],
+        survival: [false, false, true, true, false, false, false, false, false],
+    };
+
+    pub fn parse(rulestring: &str) -> Result<Rule, String> {
+        let mut birth = None;
+        let mut survival = None;
+
+        for part in rulestring.trim().split('/') {
+            let mut chars = part.trim().chars();
+            let counts = match chars.next() {
+                Some('B' | 'b') => &mut birth,
+                Some('S' | 's') => &mut survival,
+                _ => return Err(format!("'{}' should start with B or S", part)),
+            };
+            if counts.is_some() {
+                return Err(format!("'{}' is specified more than once", part));
+            }
+
+            let mut neighbors = [false; 9];
+            for c in chars {
+                match c.to_digit(10) {
+                    Some(n) if n <= 8 => neighbors[n as usize] = true,
+                    _ => return Err(format!("'{}' is not a neighbor count from 0 to 8", c)),
+                }
+            }
+            *counts = Some(neighbors);
+        }
+
+        match (birth, survival) {
+            (Some(birth), Some(survival)) => Ok(Rule { birth, survival }),
+            _ => Err(format!("'{}' should look like B3/S23", rulestring)),
+        }
+    }
+}
+
+// A Life-like automaton, running Conway's Game of Life by default
+pub struct LifeLike {
+    pub rule: Rule,
+    pub neighborhood: Neighborhood,
+    // Starting pattern, instead of a random soup
+    pub pattern: Option<CellsPattern>,
+}
+
+impl LifeLike {
+    pub fn new(rule: Rule, neighborhood: Neighborhood, pattern: Option<CellsPattern>) -> Self {
+        Self {
+            rule,
+            neighborhood,
+            pattern,
+        }
+    }
+}
+
+impl Automaton for LifeLike {
+    fn seed(&mut self, world: &mut World, ticks: u32, rng: &mut StdRng) {
+        if let Some(pattern) = &self.pattern {
+            pattern.stamp(world, CellType::Alive, ticks);
+            return;
+        }
+
+        // roughly a quarter of the cells start alive
+        for row in 0..world.size.0 {
+            for col in 0..world.size.1 {
+                if rng.gen_range(0..4) == 0 {
+                    world.set_cell(row, col, Cell::new(CellType::Alive, ticks));
+                }
+            }
+        }
+    }
+
+    fn step(&mut self, active: &World, inactive: &mut World, ticks: u32, _rng: &mut StdRng) {
+        let (rule, neighborhood) = (&self.rule, &self.neighborhood);
+
+        // Empty space stays empty unless the rule brings cells with no neighbors to
+        // life, so only the chunks around living cells need updating
+        let awake = (!rule.birth[0]).then(|| active.awake_chunks(neighborhood.radius()));
+
+        // Each cell only looks at the last tick, so the rows can update in parallel
+        inactive.par_rows(awake.as_deref(), |row, cols, types, created_at| {
+            for (index, col) in cols.enumerate() {
+                // count the living cells around this one.
+                // Counts above 8 from larger neighborhoods never match a B/S rule.
+                let neighbors = neighborhood
+                    .neighbors(active, row, col)
+                    .filter(|&(r, c)| active.cell_type(r, c) == CellType::Alive)
+                    .count();
+
+                match active.cell_type(row, col) {
+                    // A living cell survives if the rule allows this many neighbors
+                    CellType::Alive if rule.survival.get(neighbors) == Some(&true) => {
+                        types[index] = CellType::Alive;
+                        created_at[index] = active.created_at(row, col);
+                    }
+                    // An empty cell comes to life if the rule allows this many neighbors
+                    CellType::Empty if rule.birth.get(neighbors) == Some(&true) => {
+                        types[index] = CellType::Alive;
+                        created_at[index] = ticks;
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    fn seed_cell_type(&self) -> Option<CellType> {
+        Some(CellType::Alive)
+    }
+
+    fn color(&self, cell: &Cell, _ticks: u32) -> Option<[f32; 3]> {
+        match cell.cell_type {
+            CellType::Alive => Some(ALIVE_COLOR),
+            _ => None,
+        }
+    }
+
+    fn is_extinct(&self, world: &World) -> bool {
+        !world.types().contains(&CellType::Alive)
+    }
+}
@@ -1,2 +1,27 @@
-
-pub mod world;
\ No newline at end of file
+pub mod automaton;
+pub mod boids;
+pub mod brush;
+pub mod cursor;
+pub mod cycle;
+pub mod ecosystem;
+pub mod elementary;
+pub mod falling_sand;
+pub mod genome;
+pub mod heatmap;
+pub mod history;
+pub mod life;
+pub mod neighborhood;
+pub mod obstacles;
+pub mod packets;
+pub mod palette;
+pub mod patterns;
+pub mod physarum;
+pub mod plaintext;
+pub mod preditor_prey;
+pub mod recording;
+pub mod seeding;
+pub mod statistics;
+pub mod text;
+pub mod wator;
+pub mod wireworld;
+pub mod world;
@@ -0,0 +1,89 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::game::world::World;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NeighborhoodKind {
+    // Every cell within the radius, including diagonals
+    Moore,
+    // Only cells within the radius counting orthogonal steps
+    VonNeumann,
+}
+
+// The cells around a cell that the rules look at. What happens at the edges of the
+// world depends on its boundary mode.
+#[derive(Clone, Debug)]
+pub struct Neighborhood {
+    offsets: Vec<(isize, isize)>,
+}
+
+impl Neighborhood {
+    pub fn new(kind: NeighborhoodKind, radius: usize) -> Self {
+        let r = radius.max(1) as isize;
+        let mut offsets = Vec::new();
+        for dx in -r..=r {
+            for dy in -r..=r {
+                let inside = match kind {
+                    NeighborhoodKind::Moore => true,
+                    NeighborhoodKind::VonNeumann => dx.abs() + dy.abs() <= r,
+                };
+                if inside && (dx, dy) != (0, 0) {
+                    offsets.push((dx, dy));
+                }
+            }
+        }
+        Self { offsets }
+    }
+
+    pub fn moore() -> Self {
+        Self::new(NeighborhoodKind::Moore, 1)
+    }
+
+    pub fn von_neumann() -> Self {
+        Self::new(NeighborhoodKind::VonNeumann, 1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    // How many cells away the furthest neighbor is
+    pub fn radius(&self) -> usize {
+        self.offsets
+            .iter()
+            .map(|(dx, dy)| dx.unsigned_abs().max(dy.unsigned_abs()))
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Every neighbor of (row, col) that is still inside the world
+    pub fn neighbors<'a>(
+        &'a self,
+        world: &'a World,
+        row: usize,
+        col: usize,
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.offsets
+            .iter()
+            .filter_map(move |&offset| world.offset((row, col), offset))
+    }
+
+    // A single neighbor of (row, col) picked at random, or None if it is off the edge
+    pub fn random_neighbor(
+        &self,
+        world: &World,
+        row: usize,
+        col: usize,
+        rng: &mut StdRng,
+    ) -> Option<(usize, usize)> {
+        let offset = self.offsets[rng.gen_range(0..self.offsets.len())];
+        world.offset((row, col), offset)
+    }
+}
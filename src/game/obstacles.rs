@@ -0,0 +1,109 @@
+use std::path::Path;
+
+use crate::game::world::{Cell, CellType, World};
+
+pub const WALL_COLOR: [f32; 3] = [0.3, 0.3, 0.35];
+
+// Walls loaded from a file, stretched over the whole world when stamped into it.
+// The first line (or the top row of pixels) is the top of the screen.
+pub struct ObstacleMap {
+    pub width: usize,
+    pub height: usize,
+    pub walls: Vec<bool>,
+}
+
+impl ObstacleMap {
+    // Images use dark pixels for walls. Any other file is read as text where '#'
+    // is a wall and everything else is open.
+    pub fn load(path: &Path) -> Result<ObstacleMap, String> {
+        let is_image = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                matches!(extension.to_ascii_lowercase().as_str(), "png" | "bmp")
+            });
+
+        if is_image {
+            Self::load_image(path)
+        } else {
+            Self::load_text(path)
+        }
+    }
+
+    fn load_image(path: &Path) -> Result<ObstacleMap, String> {
+        let image = image::open(path).map_err(|e| e.to_string())?.to_luma8();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let walls = image.pixels().map(|pixel| pixel.0[0] < 128).collect();
+
+        Ok(ObstacleMap {
+            width,
+            height,
+            walls,
+        })
+    }
+
+    fn load_text(path: &Path) -> Result<ObstacleMap, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+        let lines = contents.lines().collect::<Vec<_>>();
+        let width = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let height = lines.len();
+
+        let mut walls = vec![false; width * height];
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                walls[y * width + x] = c == '#';
+            }
+        }
+
+        Ok(ObstacleMap {
+            width,
+            height,
+            walls,
+        })
+    }
+
+    // Whether the cell at x, y of `world` is covered by a wall
+    fn covers(&self, world: &World, x: usize, y: usize) -> bool {
+        let (width, height) = world.size;
+        let map_x = x * self.width / width;
+        let map_y = (height - 1 - y) * self.height / height;
+        self.walls[map_y * self.width + map_x]
+    }
+
+    // Turn every cell covered by a wall into a Wall, whatever was there before
+    pub fn stamp(&self, world: &mut World, ticks: u32) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+
+        let (width, height) = world.size;
+        for x in 0..width {
+            for y in 0..height {
+                if self.covers(world, x, y) {
+                    world.set_cell(x, y, Cell::new(CellType::Wall, ticks));
+                }
+            }
+        }
+    }
+
+    // Take the walls back out again, leaving empty space for the cells to move into
+    pub fn erase(&self, world: &mut World, ticks: u32) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+
+        let (width, height) = world.size;
+        for x in 0..width {
+            for y in 0..height {
+                if self.covers(world, x, y) && world.cell_type(x, y) == CellType::Wall {
+                    world.set_cell(x, y, Cell::new(CellType::Empty, ticks));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+
+use crate::game::automaton::Agent;
+
+// Speeds are in cells per second
+const MIN_SPEED: f32 = 20.0;
+const MAX_SPEED: f32 = 60.0;
+const MAX_PACKETS: usize = 1000;
+// Longest step taken at once, so packets don't jump across the screen after a pause
+const MAX_STEP_SECONDS: f32 = 0.1;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    // Bytes of traffic each packet stands for
+    pub bytes_per_packet: f64,
+    pub download_color: [f32; 3],
+    pub upload_color: [f32; 3],
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            bytes_per_packet: 65536.0,
+            download_color: [0.2, 0.8, 1.0],
+            upload_color: [1.0, 0.6, 0.1],
+        }
+    }
+}
+
+// Packets flying over the automaton, falling in from the top edge for traffic
+// coming in and rising from the bottom edge for traffic going out
+pub struct Packets {
+    pub config: NetworkConfig,
+    pub agents: Vec<Agent>,
+    // Packets to send each second in and out
+    rates: (f64, f64),
+    // Fractions of a packet left over from earlier frames
+    pending: (f64, f64),
+    last_step: Instant,
+    rng: StdRng,
+}
+
+impl Packets {
+    pub fn new(config: NetworkConfig) -> Self {
+        Self {
+            config,
+            agents: Vec::new(),
+            rates: (0.0, 0.0),
+            pending: (0.0, 0.0),
+            last_step: Instant::now(),
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    // Send packets at the rate bytes are being received and sent each second
+    pub fn set_throughput(&mut self, received: f64, sent: f64) {
+        let bytes = self.config.bytes_per_packet.max(1.0);
+        self.rates = (received / bytes, sent / bytes);
+    }
+
+    fn spawn(&mut self, size: (f32, f32), download: bool) {
+        if self.agents.len() >= MAX_PACKETS {
+            return;
+        }
+        let speed = self.rng.gen_range(MIN_SPEED..MAX_SPEED);
+        let (y, velocity, color) = match download {
+            true => (size.1, -speed, self.config.download_color),
+            false => (0.0, speed, self.config.upload_color),
+        };
+        self.agents.push(Agent {
+            position: [self.rng.gen_range(0.0..size.0), y],
+            velocity: [0.0, velocity],
+            color,
+        });
+    }
+
+    // Move every packet on by the time since the last step, dropping the ones that
+    // made it across a world of `size` cells, and send out new ones
+    pub fn step(&mut self, size: (usize, usize)) {
+        let seconds = self.last_step.elapsed().as_secs_f32().min(MAX_STEP_SECONDS);
+        self.last_step = Instant::now();
+        let size = (size.0 as f32, size.1 as f32);
+        if size.0 <= 0.0 || size.1 <= 0.0 {
+            self.agents.clear();
+            return;
+        }
+        for agent in &mut self.agents {
+            agent.position[0] += agent.velocity[0] * seconds;
+            agent.position[1] += agent.velocity[1] * seconds;
+        }
+        self.agents
+            .retain(|agent| agent.position[1] >= 0.0 && agent.position[1] <= size.1);
+
+        self.pending.0 += self.rates.0 * seconds as f64;
+        self.pending.1 += self.rates.1 * seconds as f64;
+        while self.pending.0 >= 1.0 {
+            self.pending.0 -= 1.0;
+            self.spawn(size, true);
+        }
+        while self.pending.1 >= 1.0 {
+            self.pending.1 -= 1.0;
+            self.spawn(size, false);
+        }
+    }
+}
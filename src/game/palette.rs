@@ -0,0 +1,288 @@
+use serde::Deserialize;
+
+// Colors closer together than this count as the same when picking out the main ones
+const DISTINCT_COLORS: f32 = 0.25;
+// Hours it takes to go from the night theme to the day theme and back
+const TWILIGHT_HOURS: f32 = 1.0;
+
+// How a cell's color goes over whatever is drawn under it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlendMode {
+    // Covers what's underneath, as much as its alpha says
+    #[default]
+    Normal,
+    // Adds its light to what's underneath, so it glows
+    Additive,
+}
+
+// How see-through a kind of cell is and how it's blended
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Blend {
+    pub alpha: f32,
+    pub mode: BlendMode,
+}
+
+impl Default for Blend {
+    fn default() -> Self {
+        Self {
+            alpha: 1.0,
+            mode: BlendMode::Normal,
+        }
+    }
+}
+
+// A color that shifts from `young` to `old` as a cell ages
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct ColorRamp {
+    pub young: [f32; 3],
+    pub old: [f32; 3],
+    // How opaque the cells are, from 0 to 1
+    #[serde(default = "ColorRamp::default_alpha")]
+    pub alpha: f32,
+    #[serde(default)]
+    pub blend: BlendMode,
+}
+
+fn mix(from: [f32; 3], to: [f32; 3], t: f32) -> [f32; 3] {
+    [0, 1, 2].map(|i| from[i] + (to[i] - from[i]) * t)
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    (0..3).map(|i| (a[i] - b[i]).powi(2)).sum::<f32>().sqrt()
+}
+
+// The most common colors in `pixels`, most common first. Pixels are grouped into
+// buckets of similar colors, and buckets too close to one already picked are skipped.
+fn dominant_colors(pixels: &[[f32; 3]], count: usize) -> Vec<[f32; 3]> {
+    // 8 levels per channel, holding the number of pixels and their total color
+    let mut buckets = vec![(0usize, [0.0f32; 3]); 8 * 8 * 8];
+    for pixel in pixels {
+        let level = |c: f32| ((c * 8.0) as usize).min(7);
+        let bucket = &mut buckets[level(pixel[0]) * 64 + level(pixel[1]) * 8 + level(pixel[2])];
+        bucket.0 += 1;
+        for (total, c) in bucket.1.iter_mut().zip(pixel) {
+            *total += c;
+        }
+    }
+    buckets.sort_by_key(|&(pixels, _)| std::cmp::Reverse(pixels));
+
+    let mut colors: Vec<[f32; 3]> = Vec::new();
+    for (pixels, total) in buckets {
+        if pixels == 0 || colors.len() == count {
+            break;
+        }
+        let color = total.map(|c| c / pixels as f32);
+        if colors
+            .iter()
+            .all(|&picked| distance(picked, color) > DISTINCT_COLORS)
+        {
+            colors.push(color);
+        }
+    }
+    colors
+}
+
+impl ColorRamp {
+    pub const fn new(young: [f32; 3], old: [f32; 3]) -> Self {
+        Self {
+            young,
+            old,
+            alpha: 1.0,
+            blend: BlendMode::Normal,
+        }
+    }
+
+    fn default_alpha() -> f32 {
+        1.0
+    }
+
+    pub fn style(&self) -> Blend {
+        Blend {
+            alpha: self.alpha.clamp(0.0, 1.0),
+            mode: self.blend,
+        }
+    }
+
+    // The color of a cell `age` ticks old, reaching `old` once it's `old_age` ticks old
+    pub fn at(&self, age: u32, old_age: u32) -> [f32; 3] {
+        let t = (age as f32 / old_age.max(1) as f32).min(1.0);
+        mix(self.young, self.old, t)
+    }
+
+    // The blend mode switches over halfway
+    fn blend(&self, other: &ColorRamp, t: f32) -> ColorRamp {
+        ColorRamp {
+            young: mix(self.young, other.young, t),
+            old: mix(self.old, other.old, t),
+            alpha: self.alpha + (other.alpha - self.alpha) * t,
+            blend: if t < 0.5 { self.blend } else { other.blend },
+        }
+    }
+}
+
+// The colors preditors, prey and grass are drawn with
+#[derive(Clone, Debug, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    pub prey: ColorRamp,
+    pub preditor: ColorRamp,
+    pub grass: [f32; 3],
+}
+
+impl Theme {
+    fn new(name: &str, prey: ColorRamp, preditor: ColorRamp, grass: [f32; 3]) -> Self {
+        Self {
+            name: name.to_string(),
+            prey,
+            preditor,
+            grass,
+        }
+    }
+
+    pub fn builtin() -> Vec<Theme> {
+        vec![
+            Theme::new(
+                "classic",
+                ColorRamp::new([0.5, 1.0, 0.4], [0.0, 0.45, 0.0]),
+                ColorRamp::new([1.0, 0.45, 0.3], [0.5, 0.0, 0.0]),
+                [0.0, 0.15, 0.0],
+            ),
+            Theme::new(
+                "neon",
+                ColorRamp::new([0.2, 1.0, 1.0], [0.0, 0.4, 0.6]),
+                ColorRamp::new([1.0, 0.2, 0.9], [0.5, 0.0, 0.5]),
+                [0.05, 0.0, 0.15],
+            ),
+            Theme::new(
+                "pastel",
+                ColorRamp::new([0.75, 0.95, 0.8], [0.45, 0.7, 0.55]),
+                ColorRamp::new([1.0, 0.75, 0.8], [0.75, 0.45, 0.55]),
+                [0.12, 0.14, 0.16],
+            ),
+            Theme::new(
+                "monochrome",
+                ColorRamp::new([1.0, 1.0, 1.0], [0.45, 0.45, 0.45]),
+                ColorRamp::new([0.6, 0.6, 0.6], [0.2, 0.2, 0.2]),
+                [0.08, 0.08, 0.08],
+            ),
+            Theme::new(
+                "solarized",
+                ColorRamp::new([0.52, 0.6, 0.0], [0.16, 0.63, 0.6]),
+                ColorRamp::new([0.71, 0.54, 0.0], [0.86, 0.2, 0.18]),
+                [0.03, 0.21, 0.26],
+            ),
+        ]
+    }
+
+    // A theme built around one color, with prey in shades of it and preditors in
+    // its opposite
+    pub fn from_accent(accent: [f32; 3]) -> Theme {
+        let lighten = |color: [f32; 3], amount: f32| color.map(|c| c + (1.0 - c) * amount);
+        let darken = |color: [f32; 3], amount: f32| color.map(|c| c * amount);
+        let max = accent.iter().copied().fold(0.0, f32::max);
+        let min = accent.iter().copied().fold(1.0, f32::min);
+        let opposite = accent.map(|c| max + min - c);
+
+        Theme::new(
+            "accent",
+            ColorRamp::new(lighten(accent, 0.4), darken(accent, 0.45)),
+            ColorRamp::new(lighten(opposite, 0.3), darken(opposite, 0.45)),
+            darken(accent, 0.15),
+        )
+    }
+
+    // A theme that fits in with an image: prey in its most common color and preditors
+    // in whichever of the other main colors stands out most against them
+    pub fn from_colors(name: &str, pixels: &[[f32; 3]]) -> Option<Theme> {
+        let colors = dominant_colors(pixels, 5);
+        let prey = *colors.first()?;
+        let preditor = colors
+            .iter()
+            .copied()
+            .max_by(|a, b| distance(prey, *a).total_cmp(&distance(prey, *b)))
+            .filter(|&color| color != prey)
+            // A single colored image gets preditors in the opposite color
+            .unwrap_or_else(|| prey.map(|c| 1.0 - c));
+
+        let lighten = |color: [f32; 3], amount: f32| color.map(|c| c + (1.0 - c) * amount);
+        let darken = |color: [f32; 3], amount: f32| color.map(|c| c * amount);
+        Some(Theme::new(
+            name,
+            ColorRamp::new(lighten(prey, 0.3), darken(prey, 0.5)),
+            ColorRamp::new(lighten(preditor, 0.3), darken(preditor, 0.5)),
+            darken(prey, 0.15),
+        ))
+    }
+
+    // Somewhere between this theme at 0 and `other` at 1
+    pub fn blend(&self, other: &Theme, t: f32) -> Theme {
+        Theme::new(
+            &self.name,
+            self.prey.blend(&other.prey, t),
+            self.preditor.blend(&other.preditor, t),
+            mix(self.grass, other.grass, t),
+        )
+    }
+
+    // This theme with every color scaled by `brightness`
+    pub fn dimmed(&self, brightness: f32) -> Theme {
+        // Only the colors go dark, each ramp keeps its own alpha and blending
+        let dark = |ramp: &ColorRamp| ColorRamp {
+            young: [0.0; 3],
+            old: [0.0; 3],
+            ..*ramp
+        };
+        let black = Theme::new(&self.name, dark(&self.prey), dark(&self.preditor), [0.0; 3]);
+        self.blend(&black, 1.0 - brightness)
+    }
+
+    // The built in themes followed by the ones from the config file
+    pub fn all(custom: &[Theme]) -> Vec<Theme> {
+        let mut themes = Self::builtin();
+        themes.extend_from_slice(custom);
+        themes
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::builtin().remove(0)
+    }
+}
+
+// Switches between a day theme and a dimmer night theme with the time of day
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct DayNightConfig {
+    pub day_theme: String,
+    pub night_theme: String,
+    // Local time in hours by which it's fully day and fully night, with an hour of
+    // twilight before each
+    pub sunrise: f32,
+    pub sunset: f32,
+    // How bright the night theme is drawn, from 0 to 1
+    pub night_brightness: f32,
+}
+
+impl DayNightConfig {
+    // How much it's day at `hour`, from 0 at night to 1 during the day, fading
+    // between the two around sunrise and sunset
+    pub fn daylight(&self, hour: f32) -> f32 {
+        let rise = ((hour - self.sunrise) / TWILIGHT_HOURS + 1.0).clamp(0.0, 1.0);
+        let set = ((self.sunset - hour) / TWILIGHT_HOURS).clamp(0.0, 1.0);
+        rise.min(set)
+    }
+}
+
+impl Default for DayNightConfig {
+    fn default() -> Self {
+        Self {
+            day_theme: "classic".to_string(),
+            night_theme: "classic".to_string(),
+            sunrise: 7.0,
+            sunset: 20.0,
+            night_brightness: 0.4,
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::game::plaintext::CellsPattern;
+
+// A classic pattern bundled with the app, in the plaintext .cells format
+pub struct LibraryPattern {
+    pub name: &'static str,
+    // What it does, which the tray menu groups the patterns by
+    pub kind: &'static str,
+    pub cells: &'static str,
+}
+
+impl LibraryPattern {
+    pub fn pattern(&self) -> Result<CellsPattern, String> {
+        let mut pattern = CellsPattern::parse(self.cells)?;
+        pattern.name = Some(self.name.to_string());
+        Ok(pattern)
+    }
+}
+
+// These are for life's usual B3/S23 rule, and behave differently under any other
+pub const LIBRARY: [LibraryPattern; 13] = [
+    LibraryPattern {
+        name: "Glider",
+        kind: "Spaceships",
+        cells: ".O.\n..O\nOOO",
+    },
+    LibraryPattern {
+        name: "Lightweight spaceship",
+        kind: "Spaceships",
+        cells: "O..O.\n....O\nO...O\n.OOOO",
+    },
+    LibraryPattern {
+        name: "Middleweight spaceship",
+        kind: "Spaceships",
+        cells: "...O..\n.O...O\nO.....\nO....O\nOOOOO.",
+    },
+    LibraryPattern {
+        name: "Heavyweight spaceship",
+        kind: "Spaceships",
+        cells: "...OO..\n.O....O\nO......\nO.....O\nOOOOOO.",
+    },
+    LibraryPattern {
+        name: "Gosper glider gun",
+        kind: "Guns",
+        cells: "\
+........................O
+......................O.O
+............OO......OO............OO
+...........O...O....OO............OO
+OO........O.....O...OO
+OO........O...O.OO....O.O
+..........O.....O.......O
+...........O...O
+............OO",
+    },
+    LibraryPattern {
+        name: "Simkin glider gun",
+        kind: "Guns",
+        cells: "\
+OO.....OO
+OO.....OO
+
+....OO
+....OO
+
+
+
+
+......................OO.OO
+.....................O.....O
+.....................O......O..OO
+.....................OOO...O...OO
+..........................O
+
+
+
+....................OO
+....................O
+.....................OOO
+.......................O",
+    },
+    LibraryPattern {
+        name: "Switch engine",
+        kind: "Puffers",
+        cells: "OOO.O\nO....\n...OO\n.OO.O\nO.O.O",
+    },
+    LibraryPattern {
+        name: "Double switch engine",
+        kind: "Puffers",
+        cells: "OOOOOOOO.OOOOO...OOO......OOOOOOO.OOOOO",
+    },
+    LibraryPattern {
+        name: "Blinker",
+        kind: "Oscillators",
+        cells: "OOO",
+    },
+    LibraryPattern {
+        name: "Toad",
+        kind: "Oscillators",
+        cells: ".OOO\nOOO.",
+    },
+    LibraryPattern {
+        name: "Beacon",
+        kind: "Oscillators",
+        cells: "OO..\nOO..\n..OO\n..OO",
+    },
+    LibraryPattern {
+        name: "Pulsar",
+        kind: "Oscillators",
+        cells: "\
+..OOO...OOO
+
+O....O.O....O
+O....O.O....O
+O....O.O....O
+..OOO...OOO
+
+..OOO...OOO
+O....O.O....O
+O....O.O....O
+O....O.O....O
+
+..OOO...OOO",
+    },
+    LibraryPattern {
+        name: "Pentadecathlon",
+        kind: "Oscillators",
+        cells: "..O....O\nOO.OOOO.OO\n..O....O",
+    },
+];
@@ -0,0 +1,202 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::game::automaton::Automaton;
+use crate::game::world::{Cell, CellType, World};
+
+const TRAIL_COLOR: [f32; 3] = [1.0, 0.85, 0.3];
+
+// Angles are in radians, distances in cells
+const SENSOR_ANGLE: f32 = 0.4;
+const SENSOR_DISTANCE: f32 = 9.0;
+const TURN_ANGLE: f32 = 0.6;
+const SPEED: f32 = 1.0;
+const DEPOSIT: f32 = 0.5;
+// Fraction of the trail left after each tick
+const DECAY: f32 = 0.9;
+const MAX_TRAIL: f32 = 4.0;
+const MAX_PARTICLES: usize = 20000;
+
+struct Particle {
+    position: [f32; 2],
+    heading: f32,
+}
+
+// Slime mold: particles deposit pheromone into the world's trail layer, which
+// diffuses and decays each tick, and steer toward the strongest trail ahead of them
+pub struct Physarum {
+    particles: Vec<Particle>,
+}
+
+impl Default for Physarum {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Physarum {
+    pub fn new() -> Self {
+        Self {
+            particles: Vec::new(),
+        }
+    }
+
+    fn trail_at(world: &World, position: [f32; 2]) -> f32 {
+        let (width, height) = world.size;
+        let x = (position[0] as usize).min(width - 1);
+        let y = (position[1] as usize).min(height - 1);
+        world.trail[x * height + y]
+    }
+
+    // Sensing past an absorbing edge finds no trail
+    fn sense(world: &World, particle: &Particle, angle: f32) -> f32 {
+        let (width, height) = (world.size.0 as f32, world.size.1 as f32);
+        let heading = particle.heading + angle;
+        let (mut vx, mut vy) = (heading.cos(), heading.sin());
+        let x =
+            world
+                .boundary
+                .resolve_f32(particle.position[0] + vx * SENSOR_DISTANCE, &mut vx, width);
+        let y = world.boundary.resolve_f32(
+            particle.position[1] + vy * SENSOR_DISTANCE,
+            &mut vy,
+            height,
+        );
+        match (x, y) {
+            (Some(x), Some(y)) => Self::trail_at(world, [x, y]),
+            _ => 0.0,
+        }
+    }
+
+    // Spread the trail with a 3x3 box blur, then let it fade
+    fn diffuse(active: &World, inactive: &mut World) {
+        let (width, height) = active.size;
+        for x in 0..width {
+            for y in 0..height {
+                let mut sum = 0.0;
+                for dx in -1..=1 {
+                    for dy in -1..=1 {
+                        if let Some((nx, ny)) = active.offset((x, y), (dx, dy)) {
+                            sum += active.trail[nx * height + ny];
+                        }
+                    }
+                }
+                inactive.trail[x * height + y] = sum / 9.0 * DECAY;
+            }
+        }
+    }
+}
+
+impl Automaton for Physarum {
+    fn seed(&mut self, world: &mut World, _ticks: u32, rng: &mut StdRng) {
+        let (width, height) = world.size;
+        if width == 0 || height == 0 {
+            self.particles.clear();
+            return;
+        }
+        let count = (width * height / 10).clamp(1, MAX_PARTICLES);
+
+        // Start everything in a disc facing outward, which quickly breaks up into a network
+        let radius = width.min(height) as f32 / 3.0;
+        let center = [width as f32 / 2.0, height as f32 / 2.0];
+        self.particles = (0..count)
+            .map(|_| {
+                let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+                let distance = radius * rng.gen::<f32>().sqrt();
+                Particle {
+                    position: [
+                        center[0] + angle.cos() * distance,
+                        center[1] + angle.sin() * distance,
+                    ],
+                    heading: angle,
+                }
+            })
+            .collect();
+
+        world.trail.fill(0.0);
+    }
+
+    fn resize(&mut self, size: (usize, usize), _rng: &mut StdRng) {
+        if size.0 == 0 || size.1 == 0 {
+            return;
+        }
+        let size = [size.0, size.1].map(|size| size as f32);
+        for particle in &mut self.particles {
+            for (position, size) in particle.position.iter_mut().zip(size) {
+                *position = position.rem_euclid(size);
+            }
+        }
+    }
+
+    fn step(&mut self, active: &World, inactive: &mut World, _ticks: u32, rng: &mut StdRng) {
+        if active.size.0 == 0 || active.size.1 == 0 {
+            return;
+        }
+        let (width, height) = (active.size.0 as f32, active.size.1 as f32);
+
+        Self::diffuse(active, inactive);
+
+        // Particles that wander off an absorbing edge are gone
+        self.particles.retain_mut(|particle| {
+            let left = Self::sense(active, particle, SENSOR_ANGLE);
+            let forward = Self::sense(active, particle, 0.0);
+            let right = Self::sense(active, particle, -SENSOR_ANGLE);
+
+            if forward >= left && forward >= right {
+                // Keep going straight
+            } else if forward < left && forward < right {
+                // Both sides are stronger, pick one at random
+                if rng.gen_bool(0.5) {
+                    particle.heading += TURN_ANGLE;
+                } else {
+                    particle.heading -= TURN_ANGLE;
+                }
+            } else if left > right {
+                particle.heading += TURN_ANGLE;
+            } else {
+                particle.heading -= TURN_ANGLE;
+            }
+
+            // Particles bounce by turning their heading around
+            let (mut vx, mut vy) = (particle.heading.cos(), particle.heading.sin());
+            let x = active
+                .boundary
+                .resolve_f32(particle.position[0] + vx * SPEED, &mut vx, width);
+            let y = active
+                .boundary
+                .resolve_f32(particle.position[1] + vy * SPEED, &mut vy, height);
+            let (Some(x), Some(y)) = (x, y) else {
+                return false;
+            };
+            particle.heading = vy.atan2(vx);
+
+            // Walls turn particles around
+            let (cell_x, cell_y) = (
+                (x as usize).min(active.size.0 - 1),
+                (y as usize).min(active.size.1 - 1),
+            );
+            if active.cell_type(cell_x, cell_y) == CellType::Wall {
+                particle.heading += std::f32::consts::PI;
+                return true;
+            }
+            particle.position = [x, y];
+
+            let (x, y) = (cell_x, cell_y);
+            let trail = &mut inactive.trail[x * active.size.1 + y];
+            *trail = (*trail + DEPOSIT).min(MAX_TRAIL);
+            true
+        });
+    }
+
+    fn color(&self, _cell: &Cell, _ticks: u32) -> Option<[f32; 3]> {
+        None
+    }
+
+    fn trail_color(&self) -> Option<[f32; 3]> {
+        Some(TRAIL_COLOR)
+    }
+
+    fn is_extinct(&self, _world: &World) -> bool {
+        self.particles.is_empty()
+    }
+}
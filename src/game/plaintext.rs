@@ -0,0 +1,176 @@
+use std::path::Path;
+
+use crate::game::world::{Cell, CellType, World};
+
+// A rectangle of cells, measured from the bottom left corner of the world
+#[derive(Clone, Copy, Debug)]
+pub struct Region {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Region {
+    // The smallest region holding every occupied cell, walls aside
+    pub fn bounding_box(world: &World) -> Option<Region> {
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        for (index, cell_type) in world.types().iter().enumerate() {
+            if let CellType::Empty | CellType::Wall = cell_type {
+                continue;
+            }
+            let (x, y) = world.get_cell_x_y(index);
+            bounds = Some(match bounds {
+                Some((min_x, min_y, max_x, max_y)) => {
+                    (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+                }
+                None => (x, y, x, y),
+            });
+        }
+
+        bounds.map(|(min_x, min_y, max_x, max_y)| Region {
+            x: min_x,
+            y: min_y,
+            width: max_x - min_x + 1,
+            height: max_y - min_y + 1,
+        })
+    }
+}
+
+// A pattern in the plaintext .cells format used by other cellular automata tools.
+// Lines starting with '!' are comments, 'O' is a live cell and '.' a dead one.
+// The first line is the top of the pattern.
+pub struct CellsPattern {
+    pub name: Option<String>,
+    pub width: usize,
+    pub height: usize,
+    pub alive: Vec<bool>,
+}
+
+impl CellsPattern {
+    pub fn parse(contents: &str) -> Result<CellsPattern, String> {
+        let mut name = None;
+        let mut lines = Vec::new();
+        for line in contents.lines() {
+            if let Some(comment) = line.strip_prefix('!') {
+                if let Some(pattern_name) = comment.strip_prefix("Name:") {
+                    name = Some(pattern_name.trim().to_string());
+                }
+                continue;
+            }
+            lines.push(line.trim_end());
+        }
+
+        let width = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let height = lines.len();
+
+        let mut alive = vec![false; width * height];
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                alive[y * width + x] = match c {
+                    'O' | 'o' | '*' => true,
+                    '.' => false,
+                    _ => return Err(format!("'{}' is not a .cells character", c)),
+                };
+            }
+        }
+
+        Ok(CellsPattern {
+            name,
+            width,
+            height,
+            alive,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<CellsPattern, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::parse(&contents)
+    }
+
+    // Copy a region of the world, treating every occupied cell as alive
+    pub fn from_region(world: &World, region: Region) -> CellsPattern {
+        let mut alive = vec![false; region.width * region.height];
+        for row in 0..region.height {
+            for col in 0..region.width {
+                let (x, y) = (region.x + col, region.y + region.height - 1 - row);
+                if x >= world.size.0 || y >= world.size.1 {
+                    continue;
+                }
+                alive[row * region.width + col] =
+                    !matches!(world.cell_type(x, y), CellType::Empty | CellType::Wall);
+            }
+        }
+
+        CellsPattern {
+            name: None,
+            width: region.width,
+            height: region.height,
+            alive,
+        }
+    }
+
+    pub fn to_cells_string(&self) -> String {
+        let mut contents = String::new();
+        if let Some(name) = &self.name {
+            contents.push_str(&format!("!Name: {}\n", name));
+        }
+        for row in self.alive.chunks(self.width.max(1)) {
+            let line = row
+                .iter()
+                .map(|&alive| if alive { 'O' } else { '.' })
+                .collect::<String>();
+            contents.push_str(line.trim_end_matches('.'));
+            contents.push('\n');
+        }
+        contents
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.to_cells_string()).map_err(|e| e.to_string())
+    }
+
+    // Place the live cells into the middle of the world
+    pub fn stamp(&self, world: &mut World, cell_type: CellType, ticks: u32) {
+        let (width, height) = world.size;
+        let offset_x = width.saturating_sub(self.width) / 2;
+        let offset_y = height.saturating_sub(self.height) / 2;
+
+        for row in 0..self.height.min(height) {
+            for col in 0..self.width.min(width) {
+                if self.alive[row * self.width + col] {
+                    let y = offset_y + self.height.min(height) - 1 - row;
+                    world.set_cell(offset_x + col, y, Cell::new(cell_type, ticks));
+                }
+            }
+        }
+    }
+
+    // Place the live cells with the pattern's bottom left corner at `corner`, wrapping
+    // around the edges of the world
+    pub fn stamp_at(
+        &self,
+        world: &mut World,
+        corner: (usize, usize),
+        cell_type: CellType,
+        ticks: u32,
+    ) {
+        let (width, height) = world.size;
+        if width == 0 || height == 0 {
+            return;
+        }
+        for row in 0..self.height {
+            for col in 0..self.width {
+                if self.alive[row * self.width + col] {
+                    let x = (corner.0 + col) % width;
+                    let y = (corner.1 + self.height - 1 - row) % height;
+                    world.set_cell(x, y, Cell::new(cell_type, ticks));
+                }
+            }
+        }
+    }
+}
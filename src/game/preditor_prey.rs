@@ -0,0 +1,362 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::game::automaton::{Automaton, Death};
+use crate::game::cursor::{Cursor, CursorConfig, CursorReaction};
+use crate::game::genome::Genome;
+use crate::game::neighborhood::Neighborhood;
+use crate::game::palette::{Blend, Theme};
+use crate::game::seeding::{seed_count, Seeder};
+use crate::game::world::{Cell, CellType, SimulationConfig, World};
+
+// Prey live forever, so they are drawn fully grown after this many ticks
+const PREY_OLD_AGE: u32 = 200;
+
+pub struct PreditorPrey {
+    // Prey can only reproduce by eating the grass under them
+    pub grass: bool,
+    // Children inherit a mutated copy of their parent's genome
+    pub evolution: bool,
+    pub config: SimulationConfig,
+    pub neighborhood: Neighborhood,
+    pub theme: Theme,
+    pub cursor: Cursor,
+    // Creatures that died in the last step
+    deaths: Vec<Death>,
+}
+
+impl PreditorPrey {
+    pub fn new(
+        grass: bool,
+        evolution: bool,
+        config: SimulationConfig,
+        neighborhood: Neighborhood,
+        cursor: CursorConfig,
+    ) -> Self {
+        Self {
+            grass,
+            evolution,
+            config,
+            neighborhood,
+            theme: Theme::default(),
+            cursor: Cursor::new(cursor),
+            deaths: Vec::new(),
+        }
+    }
+
+    // Prey reproduce every breed interval and live forever
+    fn prey_genome(&self) -> Genome {
+        Genome::new(100, self.config.prey_breed_interval, u16::MAX)
+    }
+
+    // Preditors reproduce whenever they eat and die after their lifespan
+    fn preditor_genome(&self) -> Genome {
+        Genome::new(100, 1, self.config.preditor_lifespan)
+    }
+
+    fn spawn(cell_type: CellType, genome: Genome, ticks: u32) -> Cell {
+        let mut cell = Cell::new(cell_type, ticks);
+        cell.genome = genome;
+        cell
+    }
+
+    fn child(&self, parent: &Cell, cell_type: CellType, ticks: u32, rng: &mut StdRng) -> Cell {
+        let genome = if self.evolution {
+            parent.genome.mutate(rng)
+        } else {
+            parent.genome
+        };
+        Self::spawn(cell_type, genome, ticks)
+    }
+
+    // Whether a creature breeds this tick: by chance if one is configured, otherwise
+    // once every breed interval since it was created
+    fn ready_to_breed(cell: &Cell, age: u32, chance: Option<f64>, rng: &mut StdRng) -> bool {
+        match chance {
+            Some(chance) => rng.gen_bool(chance.clamp(0.0, 1.0)),
+            None => age.is_multiple_of(cell.genome.breed_interval as u32),
+        }
+    }
+
+    // Put a creature into a cell, leaving the grass underneath it alone
+    fn place(world: &mut World, row: usize, col: usize, creature: Cell) {
+        let grass = world.get_cell(row, col).grass;
+        world.set_cell(row, col, Cell { grass, ..creature });
+    }
+
+    // Tint the species color by how far each trait has drifted from where it started:
+    // red for speed, green for breed interval and blue for lifespan
+    fn genotype_color(&self, cell: &Cell, base: [f32; 3]) -> [f32; 3] {
+        let start = match cell.cell_type {
+            CellType::Preditor => self.preditor_genome(),
+            _ => self.prey_genome(),
+        };
+        let drift = |value: u16, start: u16| (value as f32 / start as f32 / 2.0).min(1.0);
+        let tint = [
+            drift(cell.genome.speed, start.speed),
+            drift(cell.genome.breed_interval, start.breed_interval),
+            drift(cell.genome.lifespan, start.lifespan),
+        ];
+        [0, 1, 2].map(|i| base[i] * 0.5 + tint[i] * 0.5)
+    }
+
+    // Look at random neighbors and return the first one whose cell type in `inactive`
+    // is accepted. Stepping off an absorbing edge finds nothing, so the creature is lost.
+    // Creatures near the mouse pick whichever of them takes them towards or away from it.
+    fn find_neighbor(
+        &self,
+        inactive: &World,
+        (row, col, cell_type): (usize, usize, CellType),
+        rng: &mut StdRng,
+        accept: impl Fn(CellType) -> bool,
+    ) -> Option<(usize, usize)> {
+        let reaction = self.cursor.reaction(cell_type, (row, col));
+        if reaction != CursorReaction::Ignore {
+            let candidates = (0..self.neighborhood.len())
+                .filter_map(|_| self.neighborhood.random_neighbor(inactive, row, col, rng))
+                .filter(|&(r, c)| accept(inactive.cell_type(r, c)));
+            return self.cursor.steer(reaction, candidates);
+        }
+
+        for _ in 0..self.neighborhood.len() {
+            let (neighbor_row, neighbor_col) =
+                self.neighborhood.random_neighbor(inactive, row, col, rng)?;
+
+            if accept(inactive.cell_type(neighbor_row, neighbor_col)) {
+                return Some((neighbor_row, neighbor_col));
+            }
+        }
+        None
+    }
+}
+
+impl Automaton for PreditorPrey {
+    fn seed(&mut self, world: &mut World, ticks: u32, rng: &mut StdRng) {
+        let seeder = Seeder::new(self.config.seed_layout, world, rng);
+
+        let preditor = Self::spawn(CellType::Preditor, self.preditor_genome(), ticks);
+        let count = seed_count(
+            world,
+            self.config.preditor_seed_density,
+            self.config.preditor_seed_count,
+        );
+        seeder.place(world, count, preditor, rng);
+
+        let prey = Self::spawn(CellType::Prey, self.prey_genome(), ticks);
+        let count = seed_count(
+            world,
+            self.config.prey_seed_density,
+            self.config.prey_seed_count,
+        );
+        seeder.place(world, count, prey, rng);
+    }
+
+    fn step(&mut self, active: &World, inactive: &mut World, ticks: u32, rng: &mut StdRng) {
+        self.deaths.clear();
+        if self.grass {
+            // Eaten grass slowly grows back
+            for row in 0..active.size.0 {
+                for col in 0..active.size.1 {
+                    let grass = active.get_cell(row, col).grass;
+                    inactive.creature_mut(row, col).grass = grass.saturating_sub(1);
+                }
+            }
+        }
+
+        for row in 0..active.size.0 {
+            for col in 0..active.size.1 {
+                let cell = &active.get_cell(row, col);
+                let age = ticks - cell.created_at;
+
+                match cell.cell_type {
+                    CellType::Prey => {
+                        if cell.genome.outlived(age) {
+                            self.deaths.push(Death {
+                                row,
+                                col,
+                                cell: *cell,
+                            });
+                            continue;
+                        }
+
+                        let Some((neighbor_row, neighbor_col)) = self.find_neighbor(
+                            inactive,
+                            (row, col, CellType::Prey),
+                            rng,
+                            |cell_type| matches!(cell_type, CellType::Empty),
+                        ) else {
+                            // If it can't find an empty neighbor it will die
+                            self.deaths.push(Death {
+                                row,
+                                col,
+                                cell: *cell,
+                            });
+                            continue;
+                        };
+
+                        // The prey will try to reproduce itself every breed interval since it
+                        // was created (or by chance), as long as it has grass to eat
+                        let has_grass = !self.grass || inactive.get_cell(row, col).grass == 0;
+                        let breeds =
+                            Self::ready_to_breed(cell, age, self.config.prey_breed_chance, rng);
+                        if breeds && has_grass {
+                            if self.grass {
+                                inactive.creature_mut(row, col).grass = self.config.grass_regrowth;
+                            }
+
+                            let child = self.child(cell, CellType::Prey, ticks, rng);
+                            Self::place(inactive, neighbor_row, neighbor_col, child);
+
+                            // copy itself to the new cell
+                            Self::place(inactive, row, col, *cell);
+
+                            continue;
+                        }
+
+                        // Slow prey sometimes stay where they are
+                        if rng.gen_range(0..100) >= cell.genome.speed {
+                            Self::place(inactive, row, col, *cell);
+                            continue;
+                        }
+
+                        // The prey will move to a random empty neighbor
+                        Self::place(inactive, neighbor_row, neighbor_col, *cell);
+                    }
+
+                    CellType::Preditor => {
+                        // If the preditor has outlived its lifespan it will die
+                        if cell.genome.outlived(age) {
+                            self.deaths.push(Death {
+                                row,
+                                col,
+                                cell: *cell,
+                            });
+                            continue;
+                        }
+
+                        // The preditor will look in one spot
+                        // If it sees a prey it will convert it to a predator
+                        // If it sees an empty spot it will move to it
+                        // If it sees a predator it will not move
+                        let Some((neighbor_row, neighbor_col)) = self.find_neighbor(
+                            inactive,
+                            (row, col, CellType::Preditor),
+                            rng,
+                            |cell_type| matches!(cell_type, CellType::Empty | CellType::Prey),
+                        ) else {
+                            // If it can't find an empty neighbor it will die
+                            self.deaths.push(Death {
+                                row,
+                                col,
+                                cell: *cell,
+                            });
+                            continue;
+                        };
+
+                        // Slow preditors sometimes stay where they are
+                        if rng.gen_range(0..100) >= cell.genome.speed {
+                            Self::place(inactive, row, col, *cell);
+                            continue;
+                        }
+
+                        match inactive.cell_type(neighbor_row, neighbor_col) {
+                            CellType::Prey => {
+                                let catch_chance = self.config.catch_chance.clamp(0.0, 1.0);
+                                let caught = rng.gen_bool(catch_chance);
+                                if caught {
+                                    self.deaths.push(Death {
+                                        row: neighbor_row,
+                                        col: neighbor_col,
+                                        cell: inactive.get_cell(neighbor_row, neighbor_col),
+                                    });
+                                }
+                                if !caught {
+                                    // The prey got away, so the preditor stays put
+                                    Self::place(inactive, row, col, *cell);
+                                } else if Self::ready_to_breed(
+                                    cell,
+                                    age,
+                                    self.config.preditor_breed_chance,
+                                    rng,
+                                ) {
+                                    let child = self.child(cell, CellType::Preditor, ticks, rng);
+                                    Self::place(inactive, neighbor_row, neighbor_col, child);
+                                    Self::place(inactive, row, col, *cell);
+                                } else {
+                                    // Not ready to breed, so it just eats the prey
+                                    Self::place(inactive, neighbor_row, neighbor_col, *cell);
+                                }
+                            }
+                            CellType::Empty => {
+                                Self::place(inactive, neighbor_row, neighbor_col, *cell);
+                            }
+                            _ => {}
+                        }
+                    }
+                    _ => continue,
+                }
+            }
+        }
+    }
+
+    fn seed_cell_type(&self) -> Option<CellType> {
+        Some(CellType::Prey)
+    }
+
+    fn set_cursor(&mut self, position: Option<(usize, usize)>) {
+        self.cursor.position = position;
+    }
+
+    fn color(&self, cell: &Cell, ticks: u32) -> Option<[f32; 3]> {
+        let age = ticks.saturating_sub(cell.created_at);
+        let old_age = match cell.genome.lifespan {
+            u16::MAX => PREY_OLD_AGE,
+            lifespan => lifespan as u32,
+        };
+        let base = match cell.cell_type {
+            CellType::Prey => self.theme.prey.at(age, old_age),
+            CellType::Preditor => self.theme.preditor.at(age, old_age),
+            CellType::Empty if self.grass && cell.grass == 0 => return Some(self.theme.grass),
+            _ => return None,
+        };
+
+        if self.evolution {
+            Some(self.genotype_color(cell, base))
+        } else {
+            Some(base)
+        }
+    }
+
+    fn blend(&self, cell: &Cell) -> Blend {
+        match cell.cell_type {
+            CellType::Prey => self.theme.prey.style(),
+            CellType::Preditor => self.theme.preditor.style(),
+            _ => Blend::default(),
+        }
+    }
+
+    fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+    }
+
+    fn take_deaths(&mut self) -> Vec<Death> {
+        std::mem::take(&mut self.deaths)
+    }
+
+    fn configure(&mut self, config: &SimulationConfig) {
+        self.config = config.clone();
+    }
+
+    fn is_extinct(&self, world: &World) -> bool {
+        let mut preditor_count = 0;
+        let mut prey_count = 0;
+        for cell_type in world.types() {
+            match cell_type {
+                CellType::Preditor => preditor_count += 1,
+                CellType::Prey => prey_count += 1,
+                _ => {}
+            }
+        }
+        preditor_count == 0 || prey_count == 0
+    }
+}
@@ -0,0 +1,198 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+use rand::rngs::StdRng;
+
+use crate::config::Config;
+use crate::game::automaton::Automaton;
+use crate::game::palette::{Blend, Theme};
+use crate::game::world::{Cell, CellType, World};
+
+// Recordings start with this, followed by the world width and height as u32s and the
+// seed as a u64. Every tick after that is a u32 count of changed cells, then the
+// u32 index and two byte cell type of each change. Everything is little endian.
+const MAGIC: &[u8; 6] = b"ABREC1";
+
+fn read_u32(reader: &mut impl Read) -> std::io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+// Writes the cells that changed each tick to a file
+pub struct Recorder {
+    writer: BufWriter<File>,
+    previous: Vec<CellType>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, size: (usize, usize), seed: u64) -> Result<Recorder, String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let mut writer = BufWriter::new(file);
+
+        let header = [
+            MAGIC.as_slice(),
+            &(size.0 as u32).to_le_bytes(),
+            &(size.1 as u32).to_le_bytes(),
+            &seed.to_le_bytes(),
+        ]
+        .concat();
+        writer.write_all(&header).map_err(|e| e.to_string())?;
+
+        Ok(Recorder {
+            writer,
+            // The first tick is recorded against an empty world
+            previous: vec![CellType::Empty; size.0 * size.1],
+        })
+    }
+
+    pub fn record(&mut self, world: &World) -> std::io::Result<()> {
+        let changes = world
+            .types()
+            .iter()
+            .zip(&mut self.previous)
+            .enumerate()
+            .filter(|(_, (cell_type, previous))| cell_type != previous)
+            .map(|(index, (cell_type, previous))| {
+                *previous = *cell_type;
+                (index as u32, cell_type.to_bytes())
+            })
+            .collect::<Vec<_>>();
+
+        self.writer
+            .write_all(&(changes.len() as u32).to_le_bytes())?;
+        for (index, cell_type) in changes {
+            self.writer.write_all(&index.to_le_bytes())?;
+            self.writer.write_all(&cell_type)?;
+        }
+        Ok(())
+    }
+}
+
+// Plays a recording back in place of running the rules, looping once it reaches
+// the end. Cells are colored by the automaton the recording was made with.
+pub struct Replay {
+    path: PathBuf,
+    reader: BufReader<File>,
+    // Size of the recorded world, which may not match this one
+    size: (usize, usize),
+    automaton: Box<dyn Automaton>,
+    finished: bool,
+}
+
+impl Replay {
+    pub fn open(path: &Path, config: &Config) -> Result<Replay, String> {
+        let (reader, size) = Self::open_reader(path).map_err(|e| e.to_string())?;
+        Ok(Replay {
+            path: path.to_path_buf(),
+            reader,
+            size,
+            automaton: config.automaton.create(config),
+            finished: false,
+        })
+    }
+
+    fn open_reader(path: &Path) -> std::io::Result<(BufReader<File>, (usize, usize))> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                "not a recording",
+            ));
+        }
+        let width = read_u32(&mut reader)? as usize;
+        let height = read_u32(&mut reader)? as usize;
+        // The seed is only there for whoever wants to run the simulation again
+        let mut seed = [0; 8];
+        reader.read_exact(&mut seed)?;
+
+        Ok((reader, (width, height)))
+    }
+
+    // Apply the next recorded tick to `world`
+    fn play_tick(&mut self, world: &mut World, ticks: u32) -> std::io::Result<()> {
+        let changes = read_u32(&mut self.reader)?;
+        for _ in 0..changes {
+            let index = read_u32(&mut self.reader)? as usize;
+            let mut cell_type = [0; 2];
+            self.reader.read_exact(&mut cell_type)?;
+
+            let Some(cell_type) = CellType::from_bytes(cell_type) else {
+                continue;
+            };
+            let (x, y) = (index / self.size.1, index % self.size.1);
+            if x < world.size.0 && y < world.size.1 {
+                world.set_cell(x, y, Cell::new(cell_type, ticks));
+            }
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, world: &mut World, ticks: u32) {
+        if let Err(e) = self.play_tick(world, ticks) {
+            if e.kind() != ErrorKind::UnexpectedEof {
+                tracing::warn!("Failed to read {}: {}", self.path.display(), e);
+            }
+            self.finished = true;
+        }
+    }
+}
+
+impl Automaton for Replay {
+    fn seed(&mut self, world: &mut World, ticks: u32, _rng: &mut StdRng) {
+        // Start again from the beginning
+        match Self::open_reader(&self.path) {
+            Ok((reader, size)) => {
+                self.reader = reader;
+                self.size = size;
+                self.finished = false;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to open {}: {}", self.path.display(), e);
+                return;
+            }
+        }
+
+        for row in 0..world.size.0 {
+            for col in 0..world.size.1 {
+                world.set_cell(row, col, Cell::new(CellType::Empty, ticks));
+            }
+        }
+        self.play(world, ticks);
+    }
+
+    fn step(&mut self, active: &World, inactive: &mut World, ticks: u32, _rng: &mut StdRng) {
+        inactive.copy_cells(active);
+        self.play(inactive, ticks);
+    }
+
+    // Recordings already hold everything that was on screen
+    fn seed_cell_type(&self) -> Option<CellType> {
+        None
+    }
+
+    fn set_theme(&mut self, theme: &Theme) {
+        self.automaton.set_theme(theme);
+    }
+
+    fn color(&self, cell: &Cell, ticks: u32) -> Option<[f32; 3]> {
+        self.automaton.color(cell, ticks)
+    }
+
+    fn blend(&self, cell: &Cell) -> Blend {
+        self.automaton.blend(cell)
+    }
+
+    fn is_extinct(&self, _world: &World) -> bool {
+        self.finished
+    }
+
+    // Recordings are allowed to repeat themselves
+    fn reseed_on_cycle(&self) -> bool {
+        false
+    }
+}
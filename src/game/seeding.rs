@@ -0,0 +1,168 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::game::world::{Cell, CellType, World};
+
+// Cells between the points of the noise lattice, roughly the size of a blob
+const NOISE_SCALE: f32 = 24.0;
+// Noise above this is inside a blob
+const NOISE_THRESHOLD: f32 = 0.1;
+const CLUSTER_COUNT: usize = 6;
+// Give up on finding space after this many tries per creature
+const ATTEMPTS_PER_CELL: usize = 100;
+
+// How many creatures to seed: `count` if it's set, otherwise `density` percent of
+// the world, so bigger screens get a bigger population
+pub fn seed_count(world: &World, density: f64, count: Option<usize>) -> usize {
+    count.unwrap_or_else(|| (world.len() as f64 * density / 100.0).round() as usize)
+}
+
+// Where the starting population is placed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeedLayout {
+    // Anywhere at random
+    #[default]
+    Uniform,
+    // Inside blobs picked out of Perlin noise
+    Noise,
+    // Bunched up around a few random points
+    Clusters,
+    // At random on the left, mirrored onto the right
+    Mirrored,
+}
+
+// Places creatures in a world following a layout. Made fresh for every seeding, so
+// each one gets new blobs and clusters.
+pub struct Seeder {
+    layout: SeedLayout,
+    // Perlin noise gradients, one per lattice point
+    gradients: Vec<[f32; 2]>,
+    lattice_height: usize,
+    clusters: Vec<[f32; 2]>,
+    cluster_radius: f32,
+}
+
+impl Seeder {
+    pub fn new(layout: SeedLayout, world: &World, rng: &mut StdRng) -> Self {
+        let (width, height) = world.size;
+
+        let mut gradients = Vec::new();
+        let lattice_height = (height as f32 / NOISE_SCALE) as usize + 2;
+        if layout == SeedLayout::Noise {
+            let lattice_width = (width as f32 / NOISE_SCALE) as usize + 2;
+            gradients = (0..lattice_width * lattice_height)
+                .map(|_| {
+                    let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+                    [angle.cos(), angle.sin()]
+                })
+                .collect();
+        }
+
+        let mut clusters = Vec::new();
+        if layout == SeedLayout::Clusters {
+            clusters = (0..CLUSTER_COUNT)
+                .map(|_| {
+                    [
+                        rng.gen_range(0.0..width as f32),
+                        rng.gen_range(0.0..height as f32),
+                    ]
+                })
+                .collect();
+        }
+
+        Self {
+            layout,
+            gradients,
+            lattice_height,
+            clusters,
+            cluster_radius: width.min(height) as f32 / 8.0,
+        }
+    }
+
+    fn gradient(&self, x: usize, y: usize, dx: f32, dy: f32) -> f32 {
+        let [gx, gy] = self.gradients[x * self.lattice_height + y];
+        gx * dx + gy * dy
+    }
+
+    // Perlin noise at a cell, roughly between -0.7 and 0.7
+    fn noise(&self, x: usize, y: usize) -> f32 {
+        let (x, y) = (x as f32 / NOISE_SCALE, y as f32 / NOISE_SCALE);
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (dx, dy) = (x.fract(), y.fract());
+        let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+        let bottom = lerp(
+            self.gradient(x0, y0, dx, dy),
+            self.gradient(x0 + 1, y0, dx - 1.0, dy),
+            fade(dx),
+        );
+        let top = lerp(
+            self.gradient(x0, y0 + 1, dx, dy - 1.0),
+            self.gradient(x0 + 1, y0 + 1, dx - 1.0, dy - 1.0),
+            fade(dx),
+        );
+        lerp(bottom, top, fade(dy))
+    }
+
+    // A random spot following the layout, which may be outside the world
+    fn position(&self, size: (usize, usize), rng: &mut StdRng) -> (isize, isize) {
+        let (width, height) = size;
+        match self.layout {
+            SeedLayout::Uniform | SeedLayout::Noise => (
+                rng.gen_range(0..width) as isize,
+                rng.gen_range(0..height) as isize,
+            ),
+            SeedLayout::Clusters => {
+                let [x, y] = self.clusters[rng.gen_range(0..self.clusters.len())];
+                // Adding two rolls together bunches them up towards the middle
+                let mut offset =
+                    || (rng.gen_range(-1.0..1.0) + rng.gen_range(-1.0..1.0)) * self.cluster_radius;
+                ((x + offset()) as isize, (y + offset()) as isize)
+            }
+            SeedLayout::Mirrored => (
+                rng.gen_range(0..width.div_ceil(2)) as isize,
+                rng.gen_range(0..height) as isize,
+            ),
+        }
+    }
+
+    // Put `count` copies of `cell` into empty spots, fewer if there isn't room
+    pub fn place(&self, world: &mut World, count: usize, cell: Cell, rng: &mut StdRng) {
+        let (width, height) = world.size;
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let mut placed = 0;
+        for _ in 0..count * ATTEMPTS_PER_CELL {
+            if placed >= count {
+                break;
+            }
+
+            let (x, y) = self.position(world.size, rng);
+            if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+                continue;
+            }
+            let (x, y) = (x as usize, y as usize);
+            if world.cell_type(x, y) != CellType::Empty {
+                continue;
+            }
+            if self.layout == SeedLayout::Noise && self.noise(x, y) < NOISE_THRESHOLD {
+                continue;
+            }
+
+            world.set_cell(x, y, cell);
+            placed += 1;
+
+            let mirror = width - 1 - x;
+            if self.layout == SeedLayout::Mirrored && world.cell_type(mirror, y) == CellType::Empty
+            {
+                world.set_cell(mirror, y, cell);
+                placed += 1;
+            }
+        }
+    }
+}
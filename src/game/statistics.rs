@@ -0,0 +1,99 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::game::world::{CellType, World};
+
+// How many ticks of history are kept
+const HISTORY_LENGTH: usize = 600;
+
+// What the world looked like after one tick
+#[derive(Clone, Debug, Default)]
+pub struct Sample {
+    pub tick: u32,
+    // Number of cells of each type, leaving out empty cells and walls
+    pub populations: HashMap<CellType, usize>,
+    pub births: usize,
+    pub deaths: usize,
+}
+
+impl Sample {
+    // A short line like "Prey: 300, Preditor: 100", largest population first
+    pub fn summary(&self) -> String {
+        let mut populations = self.populations.iter().collect::<Vec<_>>();
+        populations.sort_by(|a, b| b.1.cmp(a.1));
+        populations
+            .iter()
+            .map(|(cell_type, count)| format!("{:?}: {}", cell_type, count))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+// Population counts for every tick, with births and deaths worked out from the
+// difference between generations
+#[derive(Default)]
+pub struct Statistics {
+    pub total_births: u64,
+    pub total_deaths: u64,
+    // Most cells alive at once since counting started
+    pub peak_population: usize,
+    // The last few hundred ticks, oldest first
+    pub history: VecDeque<Sample>,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Count the new generation. Any cell created this tick is a birth, and whatever
+    // is missing from the old population after that must have died.
+    pub fn record(&mut self, world: &World, tick: u32) {
+        let mut sample = Sample {
+            tick,
+            ..Default::default()
+        };
+        for cell in world.cells() {
+            if let CellType::Empty | CellType::Wall = cell.cell_type {
+                continue;
+            }
+            *sample.populations.entry(cell.cell_type).or_insert(0) += 1;
+            if cell.created_at == tick {
+                sample.births += 1;
+            }
+        }
+
+        let previous = self
+            .latest()
+            .map(|latest| latest.populations.values().sum())
+            .unwrap_or(0);
+        let current = sample.populations.values().sum::<usize>();
+        sample.deaths = (previous + sample.births).saturating_sub(current);
+
+        self.total_births += sample.births as u64;
+        self.total_deaths += sample.deaths as u64;
+        self.peak_population = self.peak_population.max(current);
+
+        if self.history.len() == HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+        self.history.push_back(sample);
+    }
+
+    pub fn latest(&self) -> Option<&Sample> {
+        self.history.back()
+    }
+
+    // Kinds of cell that were alive the tick before last but not any more
+    pub fn died_out(&self) -> Vec<CellType> {
+        let mut samples = self.history.iter().rev();
+        let (Some(latest), Some(previous)) = (samples.next(), samples.next()) else {
+            return Vec::new();
+        };
+        previous
+            .populations
+            .keys()
+            .filter(|cell_type| !latest.populations.contains_key(cell_type))
+            .copied()
+            .collect()
+    }
+}
@@ -0,0 +1,129 @@
+use crate::game::plaintext::CellsPattern;
+use crate::game::world::{CellType, World};
+
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+// How much of the world the text is allowed to cover at most
+const MAX_COVERAGE: f32 = 0.75;
+
+// Rows of a 5x7 bitmap font, top row first, with the leftmost pixel in the highest bit.
+// Lowercase letters are drawn as uppercase and anything missing as a question mark.
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x0A, 0x04, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        ' ' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        '!' => [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '\'' => [0x0C, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+// A message drawn into the world when it's seeded, so it shows up on the desktop and
+// then dissolves into the simulation. Lines are split on '\n'.
+pub struct TextSeed {
+    pub text: String,
+}
+
+impl TextSeed {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+        }
+    }
+
+    // Draw the text at one cell per pixel, leaving a column between letters and a
+    // row between lines
+    fn rasterize(&self, scale: usize) -> CellsPattern {
+        let lines = self.text.lines().collect::<Vec<_>>();
+        let columns = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let width = (columns * (GLYPH_WIDTH + 1)).saturating_sub(1) * scale;
+        let height = (lines.len() * (GLYPH_HEIGHT + 1)).saturating_sub(1) * scale;
+        let mut alive = vec![false; width * height];
+
+        for (line_index, line) in lines.iter().enumerate() {
+            for (char_index, c) in line.chars().enumerate() {
+                let rows = glyph(c);
+                for (glyph_y, row) in rows.iter().enumerate() {
+                    for glyph_x in 0..GLYPH_WIDTH {
+                        if row & (1 << (GLYPH_WIDTH - 1 - glyph_x)) == 0 {
+                            continue;
+                        }
+                        let x = (char_index * (GLYPH_WIDTH + 1) + glyph_x) * scale;
+                        let y = (line_index * (GLYPH_HEIGHT + 1) + glyph_y) * scale;
+                        for dy in 0..scale {
+                            for dx in 0..scale {
+                                alive[(y + dy) * width + x + dx] = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        CellsPattern {
+            name: None,
+            width,
+            height,
+            alive,
+        }
+    }
+
+    // Write the text into the middle of the world, as large as fits
+    pub fn stamp(&self, world: &mut World, cell_type: CellType, ticks: u32) {
+        let unscaled = self.rasterize(1);
+        if unscaled.width == 0 || unscaled.height == 0 {
+            return;
+        }
+
+        let (width, height) = world.size;
+        let scale_x = (width as f32 * MAX_COVERAGE) as usize / unscaled.width;
+        let scale_y = (height as f32 * MAX_COVERAGE) as usize / unscaled.height;
+        let scale = scale_x.min(scale_y).max(1);
+
+        self.rasterize(scale).stamp(world, cell_type, ticks);
+    }
+}
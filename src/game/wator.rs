@@ -0,0 +1,247 @@
+use rand::rngs::StdRng;
+use rand::seq::IteratorRandom;
+use rand::Rng;
+
+use crate::game::automaton::{Automaton, Death};
+use crate::game::cursor::{Cursor, CursorConfig, CursorReaction};
+use crate::game::neighborhood::Neighborhood;
+use crate::game::world::{Cell, CellType, World};
+
+const FISH_COLOR: [f32; 3] = [0.0, 0.6, 1.0];
+const SHARK_COLOR: [f32; 3] = [1.0, 0.4, 0.0];
+
+// Ticks between fish giving birth
+const FISH_BREED_TIME: u16 = 3;
+// Ticks between sharks giving birth
+const SHARK_BREED_TIME: u16 = 10;
+// Energy a shark is born with
+const SHARK_START_ENERGY: u16 = 4;
+// Energy a shark gains from eating a fish
+const SHARK_FISH_ENERGY: u16 = 3;
+
+// Wa-Tor: fish (prey) wander and breed, sharks (preditors) hunt fish for energy
+// and starve when they run out. Each cell carries its own energy and breed timer.
+pub struct WaTor {
+    // Classic Wa-Tor only looks at the four orthogonal neighbors
+    pub neighborhood: Neighborhood,
+    // Cells that have already moved this tick
+    moved: Vec<bool>,
+    pub cursor: Cursor,
+    // Fish eaten and sharks starved in the last step
+    deaths: Vec<Death>,
+}
+
+impl WaTor {
+    pub fn new(neighborhood: Neighborhood, cursor: CursorConfig) -> Self {
+        Self {
+            neighborhood,
+            moved: Vec::new(),
+            cursor: Cursor::new(cursor),
+            deaths: Vec::new(),
+        }
+    }
+
+    // A random neighbor holding the given cell type, or the one towards or away
+    // from the mouse if the creature at (row, col) has noticed it
+    fn random_neighbor(
+        &self,
+        world: &World,
+        row: usize,
+        col: usize,
+        cell_type: CellType,
+        rng: &mut StdRng,
+    ) -> Option<(usize, usize)> {
+        let neighbors = self
+            .neighborhood
+            .neighbors(world, row, col)
+            .filter(|&(r, c)| world.cell_type(r, c) == cell_type);
+        let reaction = self.cursor.reaction(world.cell_type(row, col), (row, col));
+        if reaction != CursorReaction::Ignore {
+            return self.cursor.steer(reaction, neighbors);
+        }
+        neighbors.choose(rng)
+    }
+
+    // Move the creature at (row, col) to (to_row, to_col), leaving a newborn behind
+    // if it is ready to breed
+    fn move_creature(
+        world: &mut World,
+        moved: &mut [bool],
+        (row, col): (usize, usize),
+        (to_row, to_col): (usize, usize),
+        breed_time: u16,
+        ticks: u32,
+    ) {
+        let mut creature = world.get_cell(row, col);
+        let to_idx = to_row * world.size.1 + to_col;
+        moved[to_idx] = true;
+
+        if creature.breed_timer >= breed_time {
+            creature.breed_timer = 0;
+            let mut child = Cell::new(creature.cell_type, ticks);
+            if let CellType::Preditor = child.cell_type {
+                child.energy = SHARK_START_ENERGY;
+            }
+            world.set_cell(row, col, child);
+        } else {
+            world.set_cell_type(row, col, CellType::Empty);
+        }
+
+        world.set_cell(to_row, to_col, creature);
+    }
+}
+
+impl Automaton for WaTor {
+    fn seed(&mut self, world: &mut World, ticks: u32, rng: &mut StdRng) {
+        for row in 0..world.size.0 {
+            for col in 0..world.size.1 {
+                if let CellType::Prey | CellType::Preditor = world.cell_type(row, col) {
+                    continue;
+                }
+
+                // Random breed timers keep the population from breeding in lockstep
+                let roll = rng.gen_range(0..100);
+                if roll < 2 {
+                    let mut shark = Cell::new(CellType::Preditor, ticks);
+                    shark.energy = SHARK_START_ENERGY;
+                    shark.breed_timer = rng.gen_range(0..SHARK_BREED_TIME);
+                    world.set_cell(row, col, shark);
+                } else if roll < 20 {
+                    let mut fish = Cell::new(CellType::Prey, ticks);
+                    fish.breed_timer = rng.gen_range(0..FISH_BREED_TIME);
+                    world.set_cell(row, col, fish);
+                }
+            }
+        }
+    }
+
+    fn step(&mut self, active: &World, inactive: &mut World, ticks: u32, rng: &mut StdRng) {
+        // Wa-Tor is updated in place, one creature at a time
+        inactive.copy_cells(active);
+        self.moved.clear();
+        self.moved.resize(inactive.len(), false);
+        self.deaths.clear();
+
+        let (rows, cols) = inactive.size;
+
+        // Sharks hunt first, so fish can't slip away in the same tick
+        for row in 0..rows {
+            for col in 0..cols {
+                let idx = row * cols + col;
+                if self.moved[idx] {
+                    continue;
+                }
+                let CellType::Preditor = inactive.cell_type(row, col) else {
+                    continue;
+                };
+
+                let shark = inactive.creature_mut(row, col);
+                shark.breed_timer = shark.breed_timer.saturating_add(1);
+                shark.energy = shark.energy.saturating_sub(1);
+
+                if let Some(fish) = self.random_neighbor(inactive, row, col, CellType::Prey, rng) {
+                    self.deaths.push(Death {
+                        row: fish.0,
+                        col: fish.1,
+                        cell: inactive.get_cell(fish.0, fish.1),
+                    });
+                    let shark = inactive.creature_mut(row, col);
+                    shark.energy = shark.energy.saturating_add(SHARK_FISH_ENERGY);
+                    Self::move_creature(
+                        inactive,
+                        &mut self.moved,
+                        (row, col),
+                        fish,
+                        SHARK_BREED_TIME,
+                        ticks,
+                    );
+                    continue;
+                }
+
+                // A shark that didn't eat and has no energy left starves
+                let shark = inactive.get_cell(row, col);
+                if shark.energy == 0 {
+                    self.deaths.push(Death {
+                        row,
+                        col,
+                        cell: shark,
+                    });
+                    inactive.set_cell_type(row, col, CellType::Empty);
+                    continue;
+                }
+
+                if let Some(empty) = self.random_neighbor(inactive, row, col, CellType::Empty, rng)
+                {
+                    Self::move_creature(
+                        inactive,
+                        &mut self.moved,
+                        (row, col),
+                        empty,
+                        SHARK_BREED_TIME,
+                        ticks,
+                    );
+                }
+            }
+        }
+
+        for row in 0..rows {
+            for col in 0..cols {
+                let idx = row * cols + col;
+                if self.moved[idx] {
+                    continue;
+                }
+                let CellType::Prey = inactive.cell_type(row, col) else {
+                    continue;
+                };
+
+                let fish = inactive.creature_mut(row, col);
+                fish.breed_timer = fish.breed_timer.saturating_add(1);
+
+                if let Some(empty) = self.random_neighbor(inactive, row, col, CellType::Empty, rng)
+                {
+                    Self::move_creature(
+                        inactive,
+                        &mut self.moved,
+                        (row, col),
+                        empty,
+                        FISH_BREED_TIME,
+                        ticks,
+                    );
+                }
+            }
+        }
+    }
+
+    fn seed_cell_type(&self) -> Option<CellType> {
+        Some(CellType::Prey)
+    }
+
+    fn set_cursor(&mut self, position: Option<(usize, usize)>) {
+        self.cursor.position = position;
+    }
+
+    fn take_deaths(&mut self) -> Vec<Death> {
+        std::mem::take(&mut self.deaths)
+    }
+
+    fn color(&self, cell: &Cell, _ticks: u32) -> Option<[f32; 3]> {
+        match cell.cell_type {
+            CellType::Prey => Some(FISH_COLOR),
+            CellType::Preditor => Some(SHARK_COLOR),
+            _ => None,
+        }
+    }
+
+    fn is_extinct(&self, world: &World) -> bool {
+        let mut shark_count = 0;
+        let mut fish_count = 0;
+        for cell_type in world.types() {
+            match cell_type {
+                CellType::Preditor => shark_count += 1,
+                CellType::Prey => fish_count += 1,
+                _ => {}
+            }
+        }
+        shark_count == 0 || fish_count == 0
+    }
+}
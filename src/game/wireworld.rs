@@ -0,0 +1,179 @@
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::game::automaton::Automaton;
+use crate::game::world::{Cell, CellType, World};
+
+const CONDUCTOR_COLOR: [f32; 3] = [1.0, 0.8, 0.0];
+const ELECTRON_HEAD_COLOR: [f32; 3] = [0.2, 0.4, 1.0];
+const ELECTRON_TAIL_COLOR: [f32; 3] = [1.0, 0.2, 0.0];
+
+// A circuit loaded from a text file, stored as the cell type of every character
+// with the first line at the top
+pub struct Pattern {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<CellType>,
+}
+
+impl Pattern {
+    // Circuits use '#' for conductors, '@' for electron heads and '~' for electron tails.
+    // Any other character is left empty.
+    pub fn load(path: &Path) -> Result<Pattern, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+        let lines = contents.lines().collect::<Vec<_>>();
+        let width = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let height = lines.len();
+
+        let mut cells = vec![CellType::Empty; width * height];
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                cells[y * width + x] = match c {
+                    '#' => CellType::Conductor,
+                    '@' => CellType::ElectronHead,
+                    '~' => CellType::ElectronTail,
+                    _ => CellType::Empty,
+                };
+            }
+        }
+
+        Ok(Pattern {
+            width,
+            height,
+            cells,
+        })
+    }
+}
+
+pub struct WireWorld {
+    pub pattern: Option<Pattern>,
+}
+
+impl WireWorld {
+    pub fn new(pattern: Option<Pattern>) -> Self {
+        Self { pattern }
+    }
+
+    // Stamp the loaded circuit into the center of the world
+    fn seed_pattern(pattern: &Pattern, world: &mut World, ticks: u32) {
+        let (rows, cols) = world.size;
+        let offset_x = rows.saturating_sub(pattern.width) / 2;
+        let offset_y = cols.saturating_sub(pattern.height) / 2;
+
+        for y in 0..pattern.height.min(cols) {
+            for x in 0..pattern.width.min(rows) {
+                let cell_type = pattern.cells[y * pattern.width + x];
+                if let CellType::Empty = cell_type {
+                    continue;
+                }
+                // The first line of the file is the top of the screen
+                let y = offset_y + pattern.height.min(cols) - 1 - y;
+                world.set_cell(offset_x + x, y, Cell::new(cell_type, ticks));
+            }
+        }
+    }
+
+    // Without a circuit file, fill the world with rectangular loops that each
+    // carry a single electron around like a clock
+    fn seed_loops(world: &mut World, ticks: u32, rng: &mut StdRng) {
+        let (rows, cols) = world.size;
+        if rows < 8 || cols < 8 {
+            return;
+        }
+
+        for _ in 0..(rows * cols / 2000).max(1) {
+            let width = rng.gen_range(4..(rows / 4).clamp(5, 60));
+            let height = rng.gen_range(4..(cols / 4).clamp(5, 60));
+            let left = rng.gen_range(0..rows - width);
+            let bottom = rng.gen_range(0..cols - height);
+
+            for x in left..left + width {
+                world.set_cell_type(x, bottom, CellType::Conductor);
+                world.set_cell_type(x, bottom + height - 1, CellType::Conductor);
+            }
+            for y in bottom..bottom + height {
+                world.set_cell_type(left, y, CellType::Conductor);
+                world.set_cell_type(left + width - 1, y, CellType::Conductor);
+            }
+
+            world.set_cell_type(left + 1, bottom, CellType::ElectronHead);
+            world.set_cell_type(left, bottom, CellType::ElectronTail);
+        }
+
+        world.set_created_at(ticks);
+    }
+}
+
+impl Automaton for WireWorld {
+    fn seed(&mut self, world: &mut World, ticks: u32, rng: &mut StdRng) {
+        match &self.pattern {
+            Some(pattern) => Self::seed_pattern(pattern, world, ticks),
+            None => Self::seed_loops(world, ticks, rng),
+        }
+    }
+
+    fn step(&mut self, active: &World, inactive: &mut World, _ticks: u32, _rng: &mut StdRng) {
+        // Nothing happens away from the wires
+        let awake = active.awake_chunks(1);
+        inactive.par_rows(Some(&awake), |row, cols, types, created_at| {
+            for (index, col) in cols.enumerate() {
+                let cell_type = match active.cell_type(row, col) {
+                    CellType::ElectronHead => CellType::ElectronTail,
+                    CellType::ElectronTail => CellType::Conductor,
+                    CellType::Conductor => {
+                        // A conductor becomes a head if one or two neighbors are heads
+                        let mut heads = 0;
+                        for d_row in -1..=1 {
+                            for d_col in -1..=1 {
+                                let Some((r, c)) = active.offset((row, col), (d_row, d_col)) else {
+                                    continue;
+                                };
+                                if let CellType::ElectronHead = active.cell_type(r, c) {
+                                    heads += 1;
+                                }
+                            }
+                        }
+                        if heads == 1 || heads == 2 {
+                            CellType::ElectronHead
+                        } else {
+                            CellType::Conductor
+                        }
+                    }
+                    _ => continue,
+                };
+
+                types[index] = cell_type;
+                created_at[index] = active.created_at(row, col);
+            }
+        });
+    }
+
+    fn seed_cell_type(&self) -> Option<CellType> {
+        Some(CellType::Conductor)
+    }
+
+    fn color(&self, cell: &Cell, _ticks: u32) -> Option<[f32; 3]> {
+        match cell.cell_type {
+            CellType::Conductor => Some(CONDUCTOR_COLOR),
+            CellType::ElectronHead => Some(ELECTRON_HEAD_COLOR),
+            CellType::ElectronTail => Some(ELECTRON_TAIL_COLOR),
+            _ => None,
+        }
+    }
+
+    fn is_extinct(&self, world: &World) -> bool {
+        !world.types().contains(&CellType::ElectronHead)
+    }
+
+    // Circuits are meant to loop forever
+    fn reseed_on_cycle(&self) -> bool {
+        false
+    }
+}
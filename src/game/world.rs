@@ -1,309 +1,1021 @@
-use rand::Rng;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::Deserialize;
+
+use crate::game::automaton::{Agent, Automaton, Death};
+use crate::game::boids::Boids;
+use crate::game::cycle::CycleDetector;
+use crate::game::genome::Genome;
+use crate::game::heatmap::Heatmap;
+use crate::game::history::RewindBuffer;
+use crate::game::obstacles::ObstacleMap;
+use crate::game::plaintext::CellsPattern;
+use crate::game::recording::Recorder;
+use crate::game::seeding::SeedLayout;
+use crate::game::statistics::Statistics;
+use crate::game::text::TextSeed;
+
+// Ticks between population counts being written to the log
+const STATS_LOG_INTERVAL: u32 = 100;
+// Fewest cells each thread is handed at once, since handing out less would take
+// longer than the work itself
+const PARALLEL_MIN_CELLS: usize = 64 * 1024;
+// Worlds are split into squares this many cells across, so rules can skip the ones
+// with nothing going on
+const CHUNK_SIZE: usize = 32;
+
+// Saved worlds start with this, then the width, height and tick as u32s, followed by
+// the two byte cell type and u32 age of every cell. Everything is little endian.
+const SAVE_MAGIC: &[u8; 6] = b"ABWLD1";
+
+/// What happens at the edges of the world
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoundaryMode {
+    /// Wrap around to the opposite edge
+    #[default]
+    Toroidal,
+    /// Bounce back off the edge
+    Reflective,
+    /// Fall off the edge and disappear
+    Absorbing,
+}
+
+impl BoundaryMode {
+    /// Where a position on an axis of length `size` ends up, or None if it fell off the edge
+    pub fn resolve(self, position: isize, size: usize) -> Option<usize> {
+        let last = size as isize - 1;
+        match self {
+            BoundaryMode::Toroidal => Some(position.rem_euclid(size as isize) as usize),
+            BoundaryMode::Reflective => {
+                let position = if position < 0 {
+                    -position
+                } else if position > last {
+                    2 * last - position
+                } else {
+                    position
+                };
+                Some(position.clamp(0, last) as usize)
+            }
+            BoundaryMode::Absorbing => (0..=last).contains(&position).then_some(position as usize),
+        }
+    }
+
+    /// The same for a moving agent, turning its velocity around when it bounces
+    pub fn resolve_f32(self, position: f32, velocity: &mut f32, size: f32) -> Option<f32> {
+        match self {
+            BoundaryMode::Toroidal => Some(position.rem_euclid(size)),
+            BoundaryMode::Reflective => {
+                if position < 0.0 || position >= size {
+                    *velocity = -*velocity;
+                }
+                let position = if position < 0.0 {
+                    -position
+                } else if position >= size {
+                    2.0 * size - position
+                } else {
+                    position
+                };
+                Some(position.clamp(0.0, size - 0.001))
+            }
+            BoundaryMode::Absorbing => (0.0..size).contains(&position).then_some(position),
+        }
+    }
+}
 
+/// A grid of cells, stored as one array for each part of a cell rather than one
+/// array of cells. Most rules only look at the cell types, so that is all they
+/// have to read.
+#[derive(Clone)]
 pub struct World {
     pub size: (usize, usize),
-    pub prey_count: u32,
-    pub preditor_count: u32,
-    pub cells: Vec<Cell>,
+    pub boundary: BoundaryMode,
+    // Row by row, like everything else here
+    types: Vec<CellType>,
+    created_at: Vec<u32>,
+    // Only the preditor-prey rules and Wa-Tor use these, so they are left empty
+    // until something other than the defaults is written to them
+    creatures: Vec<Creature>,
+    // For each chunk, whether anything other than empty space and walls might be in
+    // it. Row by row, like the cells.
+    occupied: Vec<bool>,
+    /// Pheromone left behind by agents, drawn underneath the cells
+    pub trail: Vec<f32>,
 }
 
 impl World {
+    /// An empty world with `size.0` rows and `size.1` columns
     pub fn new(size: (usize, usize)) -> Self {
         let (rows, cols) = size;
-        let mut cells = vec![
-            Cell {
-                cell_type: CellType::Empty,
-                created_at: 0,
-            };
-            rows as usize * cols as usize
-        ];
+        let (chunk_rows, chunk_cols) = chunk_size(size);
 
         Self {
             size,
-            cells,
-            prey_count: 0,
-            preditor_count: 0,
+            boundary: BoundaryMode::default(),
+            types: vec![CellType::Empty; rows * cols],
+            created_at: vec![0; rows * cols],
+            creatures: Vec::new(),
+            occupied: vec![false; chunk_rows * chunk_cols],
+            trail: vec![0.0; rows * cols],
         }
     }
 
-    pub fn seed_preditor_prey(&mut self, ticks: u32) {
-        // add 100 random placed preditors
-        for _ in 0..100 {
-            let mut random_idx;
-            loop {
-                random_idx = rand::thread_rng().gen_range(0..self.cells.len());
-                match self.cells[random_idx].cell_type {
-                    CellType::Preditor | CellType::Prey => continue, // Skip and retry
-                    _ => break,                                      // Found a valid spot
-                }
+    /// Number of cells
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    /// Whether the world is too small to hold a single cell
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    /// Every cell's type, row by row
+    pub fn types(&self) -> &[CellType] {
+        &self.types
+    }
+
+    /// Bytes the cells take up, which is less when nothing has used the creatures
+    pub fn cell_bytes(&self) -> usize {
+        self.types.len() * std::mem::size_of::<CellType>()
+            + self.created_at.len() * std::mem::size_of::<u32>()
+            + self.creatures.len() * std::mem::size_of::<Creature>()
+    }
+
+    /// Empty every cell except the walls, which never change
+    pub fn clear_cell_types(&mut self) {
+        for cell_type in &mut self.types {
+            if *cell_type != CellType::Wall {
+                *cell_type = CellType::Empty;
             }
+        }
+    }
+
+    /// Make this world's cells the same as `other`'s, which has to be the same size
+    pub fn copy_cells(&mut self, other: &World) {
+        self.types.copy_from_slice(&other.types);
+        self.created_at.copy_from_slice(&other.created_at);
+        self.creatures.clone_from(&other.creatures);
+        self.occupied.copy_from_slice(&other.occupied);
+    }
+
+    fn index(&self, row: usize, col: usize) -> usize {
+        row * self.size.1 + col
+    }
 
-            self.cells[random_idx] = Cell {
-                cell_type: CellType::Preditor,
-                created_at: ticks,
-            };
+    fn chunk(&self, row: usize, col: usize) -> usize {
+        row / CHUNK_SIZE * chunk_size(self.size).1 + col / CHUNK_SIZE
+    }
+
+    /// Copy out everything about the cell at `row`, `col`
+    pub fn get_cell(&self, row: usize, col: usize) -> Cell {
+        self.cell_at(self.index(row, col))
+    }
+
+    /// The cell at `index` in the order of the rows
+    pub fn cell_at(&self, index: usize) -> Cell {
+        let creature = self.creatures.get(index).copied().unwrap_or_default();
+        Cell {
+            cell_type: self.types[index],
+            created_at: self.created_at[index],
+            energy: creature.energy,
+            breed_timer: creature.breed_timer,
+            grass: creature.grass,
+            genome: creature.genome,
         }
+    }
 
-        // add 300 random placed prey
-        for _ in 0..300 {
-            let mut random_idx;
-            loop {
-                random_idx = rand::thread_rng().gen_range(0..self.cells.len());
-                match self.cells[random_idx].cell_type {
-                    CellType::Preditor | CellType::Prey => continue, // Skip and retry
-                    _ => break,                                      // Found a valid spot
-                }
-            }
+    /// Every cell, row by row
+    pub fn cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        (0..self.len()).map(|index| self.cell_at(index))
+    }
 
-            self.cells[random_idx] = Cell {
-                cell_type: CellType::Prey,
-                created_at: ticks,
-            };
+    /// Overwrite everything about the cell at `row`, `col`
+    pub fn set_cell(&mut self, row: usize, col: usize, cell: Cell) {
+        self.set_cell_type(row, col, cell.cell_type);
+        let index = self.index(row, col);
+        self.created_at[index] = cell.created_at;
+        let creature = Creature {
+            energy: cell.energy,
+            breed_timer: cell.breed_timer,
+            grass: cell.grass,
+            genome: cell.genome,
+        };
+        if !self.creatures.is_empty() || creature != Creature::default() {
+            self.creatures_mut()[index] = creature;
         }
     }
 
-    pub fn clear_cell_types(&mut self) {
-        for cell in &mut self.cells {
-            cell.cell_type = CellType::Empty;
+    /// What the cell at `row`, `col` is
+    pub fn cell_type(&self, row: usize, col: usize) -> CellType {
+        self.types[self.index(row, col)]
+    }
+
+    /// Change what the cell at `row`, `col` is
+    pub fn set_cell_type(&mut self, row: usize, col: usize, cell_type: CellType) {
+        let index = self.index(row, col);
+        self.types[index] = cell_type;
+        if occupies(cell_type) {
+            let chunk = self.chunk(row, col);
+            self.occupied[chunk] = true;
         }
     }
 
-    pub fn get_cell(&self, row: usize, col: usize) -> &Cell {
-        &self.cells[row * self.size.1 + col]
+    /// The tick the cell at `row`, `col` was created on
+    pub fn created_at(&self, row: usize, col: usize) -> u32 {
+        self.created_at[self.index(row, col)]
     }
 
-    pub fn get_mut_cell(&mut self, row: usize, col: usize) -> &mut Cell {
-        &mut self.cells[row * self.size.1 + col]
+    /// Mark every cell as created at `ticks`
+    pub fn set_created_at(&mut self, ticks: u32) {
+        self.created_at.fill(ticks);
+    }
+
+    fn creatures_mut(&mut self) -> &mut [Creature] {
+        if self.creatures.is_empty() {
+            self.creatures = vec![Creature::default(); self.types.len()];
+        }
+        &mut self.creatures
     }
 
+    /// The creature at `row`, `col`, for the automata that have them
+    pub fn creature_mut(&mut self, row: usize, col: usize) -> &mut Creature {
+        let index = self.index(row, col);
+        &mut self.creatures_mut()[index]
+    }
+
+    /// The row and column of the cell at `index`
     pub fn get_cell_x_y(&self, index: usize) -> (usize, usize) {
         (index / self.size.1, index % self.size.1)
     }
+
+    /// Which chunks could change next tick under rules that look `reach` cells away
+    /// and never bring empty space to life: the ones with anything in them and those
+    /// around them
+    pub fn awake_chunks(&self, reach: usize) -> Vec<bool> {
+        let (chunk_rows, chunk_cols) = chunk_size(self.size);
+        // Wrapping around the edges crosses the short chunks there in fewer cells
+        let short_edge =
+            !self.size.0.is_multiple_of(CHUNK_SIZE) || !self.size.1.is_multiple_of(CHUNK_SIZE);
+        let halo = (reach.div_ceil(CHUNK_SIZE) + short_edge as usize) as isize;
+        let mut awake = vec![false; self.occupied.len()];
+        for chunk in (0..self.occupied.len()).filter(|&chunk| self.occupied[chunk]) {
+            let (chunk_row, chunk_col) = (chunk / chunk_cols, chunk % chunk_cols);
+            for d_row in -halo..=halo {
+                for d_col in -halo..=halo {
+                    // The other boundary modes don't wrap, but waking a few more
+                    // chunks than needed does no harm
+                    let row = (chunk_row as isize + d_row).rem_euclid(chunk_rows as isize);
+                    let col = (chunk_col as isize + d_col).rem_euclid(chunk_cols as isize);
+                    awake[row as usize * chunk_cols + col as usize] = true;
+                }
+            }
+        }
+        awake
+    }
+
+    /// Call `update` with every row's number, a range of its columns and the cell types
+    /// and creation ticks in them, spreading bands of rows over rayon's threads. Only
+    /// rules where each cell's next state depends on nothing but the last tick can use
+    /// this, as the rows are updated in any order. Parts of rows in chunks that aren't
+    /// `awake` are skipped, which leaves them empty in a world that was just cleared.
+    pub fn par_rows(
+        &mut self,
+        awake: Option<&[bool]>,
+        update: impl Fn(usize, Range<usize>, &mut [CellType], &mut [u32]) + Sync,
+    ) {
+        let cols = self.size.1.max(1);
+        let chunk_cols = chunk_size(self.size).1.max(1);
+        // Each band is one row of chunks, so only one thread sees each chunk
+        let band_cells = CHUNK_SIZE * cols;
+        self.types
+            .par_chunks_mut(band_cells)
+            .zip(self.created_at.par_chunks_mut(band_cells))
+            .zip(self.occupied.par_chunks_mut(chunk_cols))
+            .enumerate()
+            // Small worlds stay on one thread
+            .with_min_len((PARALLEL_MIN_CELLS / band_cells).max(1))
+            .for_each(|(band, ((types, created_at), occupied))| {
+                let rows = types.chunks_mut(cols).zip(created_at.chunks_mut(cols));
+                for (index, (types, created_at)) in rows.enumerate() {
+                    let row = band * CHUNK_SIZE + index;
+                    for (chunk_col, occupied) in occupied.iter_mut().enumerate() {
+                        if index == 0 {
+                            *occupied = false;
+                        }
+                        let chunk = band * chunk_cols + chunk_col;
+                        if awake.is_some_and(|awake| !awake[chunk]) {
+                            continue;
+                        }
+                        let start = chunk_col * CHUNK_SIZE;
+                        let span = start..(start + CHUNK_SIZE).min(cols);
+                        let types = &mut types[span.clone()];
+                        update(row, span.clone(), types, &mut created_at[span]);
+                        *occupied |= types.iter().any(|&cell_type| occupies(cell_type));
+                    }
+                }
+            });
+    }
+
+    /// The world shrunk down to `width` columns, one string for each row with a
+    /// character for each cell's type: the first byte it's saved as, in base 36
+    pub fn type_rows(&self, width: usize) -> Vec<String> {
+        let (rows, cols) = self.size;
+        if rows == 0 || cols == 0 {
+            return Vec::new();
+        }
+        let width = width.clamp(1, cols);
+        let height = (rows * width / cols).max(1);
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        let cell_type = self.cell_type(y * rows / height, x * cols / width);
+                        let code = cell_type.to_bytes()[0] as u32;
+                        char::from_digit(code, 36).unwrap_or('?')
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The cell (d_row, d_col) away from (row, col), following the boundary mode at the
+    /// edges. None if it is off an absorbing edge.
+    /// The cell `d_row`, `d_col` away from `row`, `col`, or `None` if that is past an edge
+    /// that doesn't wrap
+    pub fn offset(
+        &self,
+        (row, col): (usize, usize),
+        (d_row, d_col): (isize, isize),
+    ) -> Option<(usize, usize)> {
+        Some((
+            self.boundary.resolve(row as isize + d_row, self.size.0)?,
+            self.boundary.resolve(col as isize + d_col, self.size.1)?,
+        ))
+    }
+
+    /// Write the world to `path`, to be loaded again with `load`
+    pub fn save(&self, path: &Path, ticks: u32) -> Result<(), String> {
+        let mut bytes = Vec::with_capacity(18 + self.len() * 6);
+        bytes.extend_from_slice(SAVE_MAGIC);
+        bytes.extend_from_slice(&(self.size.0 as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.size.1 as u32).to_le_bytes());
+        bytes.extend_from_slice(&ticks.to_le_bytes());
+        for (cell_type, created_at) in self.types.iter().zip(&self.created_at) {
+            bytes.extend_from_slice(&cell_type.to_bytes());
+            bytes.extend_from_slice(&ticks.saturating_sub(*created_at).to_le_bytes());
+        }
+
+        std::fs::write(path, bytes).map_err(|e| e.to_string())
+    }
+
+    /// Load a saved world along with the tick it was saved at
+    pub fn load(path: &Path) -> Result<(World, u32), String> {
+        let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+        let Some(header) = bytes.strip_prefix(SAVE_MAGIC.as_slice()) else {
+            return Err("not a saved world".to_string());
+        };
+
+        let mut words = header
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]));
+        let (Some(width), Some(height), Some(ticks)) = (words.next(), words.next(), words.next())
+        else {
+            return Err("the header is cut short".to_string());
+        };
+
+        // Checked before anything is allocated, so a corrupt size can't ask for more
+        // memory than there is
+        let cells = &header[12..];
+        let count = (width as usize).checked_mul(height as usize);
+        match count.and_then(|count| count.checked_mul(6)) {
+            Some(length) if length == cells.len() => {}
+            _ => return Err(format!("expected {}x{} cells", width, height)),
+        }
+        let mut world = World::new((width as usize, height as usize));
+        for (index, bytes) in cells.chunks_exact(6).enumerate() {
+            world.types[index] = CellType::from_bytes([bytes[0], bytes[1]])
+                .ok_or_else(|| format!("unknown cell type {}", bytes[0]))?;
+            let age = u32::from_le_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]);
+            world.created_at[index] = ticks.saturating_sub(age);
+        }
+        world.occupied.fill(true);
+
+        Ok((world, ticks))
+    }
+
+    /// Every (x, y) position starting at the bottom of the screen and working up.
+    /// Each line alternates direction so falling cells don't drift to one side.
+    pub fn bottom_up(&self) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = self.size;
+        (0..height).flat_map(move |y| {
+            (0..width).map(move |i| {
+                if y % 2 == 0 {
+                    (i, y)
+                } else {
+                    (width - 1 - i, y)
+                }
+            })
+        })
+    }
+}
+
+// Number of chunks down and across a world of `size`, counting the short ones at the
+// edges
+fn chunk_size((rows, cols): (usize, usize)) -> (usize, usize) {
+    (rows.div_ceil(CHUNK_SIZE), cols.div_ceil(CHUNK_SIZE))
+}
+
+// Whether a cell of this type counts as something going on in its chunk
+fn occupies(cell_type: CellType) -> bool {
+    !matches!(cell_type, CellType::Empty | CellType::Wall)
+}
+
+/// Rule parameters that can be changed while the simulation is running
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct SimulationConfig {
+    /// Ticks between prey reproducing
+    pub prey_breed_interval: u16,
+    /// Ticks before a preditor dies
+    pub preditor_lifespan: u16,
+    /// Percentage of cells each is placed in when the world is seeded
+    pub preditor_seed_density: f64,
+    pub prey_seed_density: f64,
+    /// Exact numbers to place instead, whatever the size of the world
+    pub preditor_seed_count: Option<usize>,
+    pub prey_seed_count: Option<usize>,
+    /// Ticks for eaten grass to grow back
+    pub grass_regrowth: u16,
+    /// Chance each tick of breeding, used instead of the breed interval when set
+    pub prey_breed_chance: Option<f64>,
+    pub preditor_breed_chance: Option<f64>,
+    /// Chance a preditor catches the prey it pounces on
+    pub catch_chance: f64,
+    /// Where the starting preditors and prey are placed
+    pub seed_layout: SeedLayout,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            prey_breed_interval: 25,
+            preditor_lifespan: 55,
+            preditor_seed_density: 0.1,
+            prey_seed_density: 0.3,
+            preditor_seed_count: None,
+            prey_seed_count: None,
+            grass_regrowth: 30,
+            prey_breed_chance: None,
+            preditor_breed_chance: None,
+            catch_chance: 1.0,
+            seed_layout: SeedLayout::Uniform,
+        }
+    }
 }
 
+/// An automaton stepping a world, along with everything drawn over it and the
+/// history kept of it
 pub struct Simulation {
+    /// The generation being shown and the one the next step is written into
     pub worlds: [World; 2],
+    /// Which of `worlds` holds the most recent generation
     pub active_world: usize,
+    /// The rules the worlds are stepped by
+    pub automaton: Box<dyn Automaton>,
+    /// A flock of boids flying over the top of the automaton
+    pub boids: Option<Boids>,
+    /// A message written over the starting population whenever the worlds are seeded
+    pub text: Option<TextSeed>,
+    /// Walls stamped into both worlds whenever they are seeded
+    pub obstacles: Option<ObstacleMap>,
+    /// Parts of the world no monitor shows, walled off so nothing lives in them
+    pub gaps: Option<ObstacleMap>,
+    /// Desktop icons, walled off so the cells flow around them
+    pub icons: Option<ObstacleMap>,
+    /// Population counts over recent ticks
+    pub statistics: Statistics,
+    /// How often each cell has changed, while the heatmap is shown
+    pub heatmap: Option<Heatmap>,
+    /// Recent generations, kept to notice when the simulation repeats itself
+    pub cycle_detector: CycleDetector,
+    /// Length of the loop the simulation is stuck in, if any
+    pub cycle: Option<usize>,
+    /// Recent generations that can be stepped back through
+    pub rewind: RewindBuffer,
+    /// Writes every tick to a file while recording
+    pub recorder: Option<Recorder>,
+    /// Creatures that died since the renderer last took them, while it wants them
+    pub deaths: Option<Vec<Death>>,
+    // All randomness comes from here, so the same seed always gives the same run
+    rng: StdRng,
+    paused: bool,
     ticks: u32,
 }
 
 impl Simulation {
-    pub fn new(size: (usize, usize)) -> Self {
+    /// A simulation of `size` cells, seeded on its first step. The same `seed` always
+    /// gives the same run.
+    pub fn new(
+        size: (usize, usize),
+        automaton: Box<dyn Automaton>,
+        seed: u64,
+        cycle_window: usize,
+    ) -> Self {
         Self {
             worlds: [World::new(size), World::new(size)],
             active_world: 0,
+            automaton,
+            boids: None,
+            text: None,
+            obstacles: None,
+            gaps: None,
+            icons: None,
+            statistics: Statistics::new(),
+            heatmap: None,
+            cycle_detector: CycleDetector::new(cycle_window),
+            cycle: None,
+            rewind: RewindBuffer::new(0),
+            recorder: None,
+            deaths: None,
+            rng: StdRng::seed_from_u64(seed),
+            paused: false,
             ticks: 0,
         }
     }
 
-    pub fn tick(&mut self) {
+    // Count a tick that has been stepped through. Use `step` to advance.
+    fn tick(&mut self) {
         self.ticks += 1;
     }
 
-    fn get_active_inactive(&mut self) -> (&World, &mut World) {
-        if self.active_world == 0 {
-            let (first, second) = self.worlds.split_at_mut(1);
+    /// How many steps have been taken since the worlds were last seeded
+    pub fn ticks(&self) -> u32 {
+        self.ticks
+    }
+
+    /// The world holding the most recent generation
+    pub fn world(&self) -> &World {
+        &self.worlds[self.active_world]
+    }
+
+    fn get_active_inactive(worlds: &mut [World; 2], active_world: usize) -> (&World, &mut World) {
+        let (first, second) = worlds.split_at_mut(1);
+        if active_world == 0 {
             (&first[0], &mut second[0])
         } else {
-            let (first, second) = self.worlds.split_at_mut(1);
             (&second[0], &mut first[0])
         }
     }
 
+    /// Every agent from the automaton and the boids overlay
+    pub fn agents(&self) -> impl Iterator<Item = &Agent> {
+        self.automaton
+            .agents()
+            .iter()
+            .chain(self.boids.iter().flat_map(|boids| boids.agents.iter()))
+    }
+
+    /// Whether there is nothing left alive worth watching
+    pub fn is_extinct(&self) -> bool {
+        !self.world().is_empty() && self.automaton.is_extinct(self.world())
+    }
+
+    // A hash of everything visible: the cells, the trail and every agent
+    fn fingerprint(&self) -> u64 {
+        let world = self.world();
+        let mut hasher = DefaultHasher::new();
+        world.types().hash(&mut hasher);
+        for trail in &world.trail {
+            trail.to_bits().hash(&mut hasher);
+        }
+        for agent in self.agents() {
+            agent.position.map(f32::to_bits).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Change what happens at the edges of both worlds
+    pub fn set_boundary(&mut self, boundary: BoundaryMode) {
+        for world in &mut self.worlds {
+            world.boundary = boundary;
+        }
+    }
+
+    /// Fit the simulation into a world of a different size, keeping every cell that
+    /// still fits where it was and leaving any new space empty
+    pub fn resize(&mut self, size: (usize, usize)) {
+        if size == self.worlds[0].size {
+            return;
+        }
+
+        for world in &mut self.worlds {
+            let mut resized = World::new(size);
+            resized.boundary = world.boundary;
+            for x in 0..size.0.min(world.size.0) {
+                for y in 0..size.1.min(world.size.1) {
+                    resized.set_cell(x, y, world.get_cell(x, y));
+                    resized.trail[x * size.1 + y] = world.trail[x * world.size.1 + y];
+                }
+            }
+            *world = resized;
+        }
+        if !self.worlds[0].is_empty() {
+            self.automaton.resize(size, &mut self.rng);
+        }
+        // New space between the monitors and around the icons is walled off straight
+        // away
+        for walls in self.gaps.iter().chain(&self.icons) {
+            for world in &mut self.worlds {
+                walls.stamp(world, self.ticks);
+            }
+        }
+
+        self.rewind = RewindBuffer::new(self.rewind.budget_bytes);
+        if let Some(heatmap) = &mut self.heatmap {
+            *heatmap = Heatmap::new(size);
+        }
+        if self.recorder.take().is_some() {
+            tracing::warn!("Stopped recording, the world changed size");
+        }
+        self.cycle_detector.clear();
+        self.cycle = None;
+    }
+
+    /// Start over in a world of a different size. History and recordings of the old
+    /// size no longer fit, so they are dropped.
+    pub fn set_size(&mut self, size: (usize, usize)) {
+        let boundary = self.worlds[0].boundary;
+        self.worlds = [World::new(size), World::new(size)];
+        self.set_boundary(boundary);
+
+        self.rewind = RewindBuffer::new(self.rewind.budget_bytes);
+        if let Some(heatmap) = &mut self.heatmap {
+            *heatmap = Heatmap::new(size);
+        }
+        if self.recorder.take().is_some() {
+            tracing::warn!("Stopped recording, the world changed size");
+        }
+
+        // Tick 0 seeds the new world
+        self.ticks = 0;
+        self.cycle_detector.clear();
+        self.cycle = None;
+    }
+
+    /// Swap in a different automaton and start it from a fresh seed. History from the
+    /// old one doesn't make sense any more, so it's dropped.
+    pub fn set_automaton(&mut self, automaton: Box<dyn Automaton>) {
+        self.automaton = automaton;
+        self.rewind = RewindBuffer::new(self.rewind.budget_bytes);
+        self.statistics = Statistics::new();
+        if self.recorder.take().is_some() {
+            tracing::warn!("Stopped recording, the automaton changed");
+        }
+        self.reset_simulation();
+    }
+
+    /// Change the rule parameters without reseeding
+    pub fn set_config(&mut self, config: &SimulationConfig) {
+        self.automaton.configure(config);
+    }
+
+    // Text and walls go in after seeding, so they cover anything that was placed
+    // under them
+    fn stamp_overlays(&mut self) {
+        if let (Some(text), Some(cell_type)) = (&self.text, self.automaton.seed_cell_type()) {
+            for world in &mut self.worlds {
+                text.stamp(world, cell_type, self.ticks);
+            }
+        }
+        self.stamp_walls();
+    }
+
+    fn stamp_walls(&mut self) {
+        for walls in self.obstacles.iter().chain(&self.gaps).chain(&self.icons) {
+            for world in &mut self.worlds {
+                walls.stamp(world, self.ticks);
+            }
+        }
+    }
+
+    /// Move the walls around the desktop icons to where the icons are now
+    pub fn set_icons(&mut self, icons: Option<ObstacleMap>) {
+        if let Some(old) = &self.icons {
+            for world in &mut self.worlds {
+                old.erase(world, self.ticks);
+            }
+        }
+        self.icons = icons;
+        // Other walls that were under the old icons go back in too
+        self.stamp_walls();
+    }
+
+    /// Fill a circle of `cell_type` cells around x, y of the current world
+    pub fn paint(&mut self, x: usize, y: usize, radius: usize, cell_type: CellType) {
+        let world = &mut self.worlds[self.active_world];
+        let (width, height) = world.size;
+        let radius = radius as isize;
+        for dx in -radius..=radius {
+            for dy in -radius..=radius {
+                if dx * dx + dy * dy > radius * radius {
+                    continue;
+                }
+                let (x, y) = (x as isize + dx, y as isize + dy);
+                if x < 0 || y < 0 || x >= width as isize || y >= height as isize {
+                    continue;
+                }
+                world.set_cell(x as usize, y as usize, Cell::new(cell_type, self.ticks));
+            }
+        }
+    }
+
+    /// Write `text` over the current world for the automaton to carry on from
+    pub fn write_text(&mut self, text: &TextSeed) {
+        let Some(cell_type) = self.automaton.seed_cell_type() else {
+            return;
+        };
+        text.stamp(&mut self.worlds[self.active_world], cell_type, self.ticks);
+        // Letters don't get to knock down walls
+        self.stamp_walls();
+    }
+
+    /// Stamp `pattern` into the current world, in the middle of it or somewhere random
+    pub fn place_pattern(&mut self, pattern: &CellsPattern, centered: bool) {
+        let Some(cell_type) = self.automaton.seed_cell_type() else {
+            return;
+        };
+        let world = &mut self.worlds[self.active_world];
+        let (width, height) = world.size;
+        if width == 0 || height == 0 {
+            return;
+        }
+        let corner = match centered {
+            true => (
+                width.saturating_sub(pattern.width) / 2,
+                height.saturating_sub(pattern.height) / 2,
+            ),
+            false => (self.rng.gen_range(0..width), self.rng.gen_range(0..height)),
+        };
+        pattern.stamp_at(world, corner, cell_type, self.ticks);
+        self.stamp_walls();
+    }
+
+    /// Drop up to `count` `cell_type` cells onto random empty spots of the current world
+    pub fn sprinkle(&mut self, count: usize, cell_type: CellType) {
+        let world = &mut self.worlds[self.active_world];
+        let (width, height) = world.size;
+        if width == 0 || height == 0 {
+            return;
+        }
+        for _ in 0..count {
+            let (x, y) = (self.rng.gen_range(0..width), self.rng.gen_range(0..height));
+            if world.cell_type(x, y) == CellType::Empty {
+                world.set_cell(x, y, Cell::new(cell_type, self.ticks));
+            }
+        }
+    }
+
+    /// Clear the worlds and seed them again from the start
     pub fn reset_simulation(&mut self) {
+        if self.world().is_empty() {
+            return;
+        }
         // reseed the worlds
-        self.worlds[0].seed_preditor_prey(self.ticks);
-        self.worlds[1].seed_preditor_prey(self.ticks);
+        for world in &mut self.worlds {
+            self.automaton.seed(world, self.ticks, &mut self.rng);
+        }
+        self.stamp_overlays();
+        self.cycle_detector.clear();
+        self.cycle = None;
     }
 
-    pub fn update(&mut self) {
-        let active_idx = self.active_world;
-        let inactive_idx = if active_idx == 0 { 1 } else { 0 };
+    /// Go back to the generation before the current one. Returns false once there's
+    /// no history left.
+    pub fn rewind(&mut self) -> bool {
+        let Some((ticks, world)) = self.rewind.pop() else {
+            return false;
+        };
+        self.worlds[self.active_world] = world;
+        self.ticks = ticks;
+        self.cycle_detector.clear();
+        self.cycle = None;
+        true
+    }
 
-        let ticks = self.ticks;
+    /// Replace the current generation with a saved one. Worlds of a different size
+    /// are lined up at the bottom left corner.
+    pub fn restore(&mut self, saved: &World, ticks: u32) {
+        let world = &mut self.worlds[self.active_world];
+        if world.is_empty() {
+            return;
+        }
+        // Only the cells are saved, so the automaton is seeded into a scratch world to
+        // set up everything else, like falling sand's emitters and the boids
+        let mut scratch = World::new(world.size);
+        scratch.boundary = world.boundary;
+        self.automaton.seed(&mut scratch, ticks, &mut self.rng);
+        world.clear_cell_types();
+        for x in 0..world.size.0.min(saved.size.0) {
+            for y in 0..world.size.1.min(saved.size.1) {
+                world.set_cell(x, y, saved.get_cell(x, y));
+            }
+        }
 
-        // Split mutable references to avoid borrow conflicts
-        let (active, inactive) = self.get_active_inactive();
-        // Clear inactive world
-        inactive.clear_cell_types();
+        // Tick 0 would seed over the top of it
+        self.ticks = ticks.max(1);
+        self.cycle_detector.clear();
+        self.cycle = None;
+    }
 
-        if ticks == 0 {
-            inactive.seed_preditor_prey(ticks);
-        }
-
-        for row in 0..active.size.0 {
-            for col in 0..active.size.1 {
-                let cell = active.get_cell(row as usize, col as usize);
-
-                match cell.cell_type {
-                    CellType::Prey => {
-                        let mut found = false;
-                        let mut tries = 0;
-                        let mut neighbor_row = 0;
-                        let mut neighbor_col = 0;
-                        while !found && tries < 9 {
-                            tries += 1;
-                            let rand_row = rand::thread_rng().gen_range(0..3) as i32 - 1;
-                            let rand_col = rand::thread_rng().gen_range(0..3) as i32 - 1;
-
-                            neighbor_row = ((row as i32 + rand_row + active.size.0 as i32)
-                                % active.size.0 as i32)
-                                as usize;
-                            neighbor_col = ((col as i32 + rand_col + active.size.1 as i32)
-                                % active.size.1 as i32)
-                                as usize;
-
-                            if neighbor_row < 0
-                                || neighbor_col < 0
-                                || neighbor_row >= active.size.0
-                                || neighbor_col >= active.size.1
-                            {
-                                continue;
-                            }
-                            match inactive
-                                .get_cell(neighbor_row as usize, neighbor_col as usize)
-                                .cell_type
-                            {
-                                CellType::Empty => {
-                                    found = true;
-                                }
-                                _ => continue,
-                            }
-                        }
+    /// Stop `step` from advancing until `resume` is called
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
 
-                        if !found {
-                            // If it can't find an empty neighbor it will die
-                            // inactive.prey_count -= 1;
-                            continue;
-                        }
+    /// Let `step` advance again after `pause`
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
 
-                        // The prey will try to reproduce itself every 25 ticks since it was created
-                        if (ticks - cell.created_at) % 25 == 0 {
-                            inactive
-                                .get_mut_cell(neighbor_row as usize, neighbor_col as usize)
-                                .cell_type = CellType::Prey;
-                            inactive
-                                .get_mut_cell(neighbor_row as usize, neighbor_col as usize)
-                                .created_at = ticks;
-                            inactive.prey_count += 1;
+    /// Whether `pause` has been called without a `resume`
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
 
-                            // copy itself to the new cell
-                            inactive.get_mut_cell(row, col).cell_type = CellType::Prey;
-                            inactive.get_mut_cell(row, col).created_at = cell.created_at;
+    /// Advance one tick. This still works while paused, so the simulation can be
+    /// stepped through one tick at a time.
+    pub fn step(&mut self) {
+        // A window smaller than one cell leaves nothing to step. The tick doesn't
+        // count either, so the world is seeded once it has room.
+        if self.world().is_empty() {
+            return;
+        }
+        let active_idx = self.active_world;
+        let inactive_idx = if active_idx == 0 { 1 } else { 0 };
 
-                            continue;
-                        }
+        let ticks = self.ticks;
 
-                        // The prey will move to a random empty neighbor
-                        inactive
-                            .get_mut_cell(neighbor_row as usize, neighbor_col as usize)
-                            .cell_type = CellType::Prey;
-                        continue;
-                    }
+        if ticks == 0 {
+            self.automaton
+                .seed(&mut self.worlds[active_idx], ticks, &mut self.rng);
+            self.stamp_overlays();
+        } else {
+            self.rewind.push(ticks, &self.worlds[active_idx]);
+        }
 
-                    CellType::Preditor => {
-                        // If the preditor has been alive for 55 ticks it will die
-                        if (ticks - cell.created_at) > 55 {
-                            // inactive.preditor_count -= 1;
-                            continue;
-                        }
+        // Split mutable references to avoid borrow conflicts
+        let (active, inactive) = Self::get_active_inactive(&mut self.worlds, active_idx);
+        // Clear inactive world
+        inactive.clear_cell_types();
 
-                        // The preditor will look in one spot
-                        // If it sees a prey it will convert it to a predator
-                        // If it sees an empty spot it will move to it
-                        // If it sees a predator it will not move
-                        let mut found = false;
-                        let mut tries = 0;
-                        let mut neighbor_row = 0;
-                        let mut neighbor_col = 0;
-                        while !found && tries < 9 {
-                            tries += 1;
-                            let rand_row = rand::thread_rng().gen_range(0..3) as i32 - 1;
-                            let rand_col = rand::thread_rng().gen_range(0..3) as i32 - 1;
-
-                            neighbor_row = ((row as i32 + rand_row + active.size.0 as i32)
-                                % active.size.0 as i32)
-                                as usize;
-                            neighbor_col = ((col as i32 + rand_col + active.size.1 as i32)
-                                % active.size.1 as i32)
-                                as usize;
-
-                            if neighbor_row < 0
-                                || neighbor_col < 0
-                                || neighbor_row >= active.size.0
-                                || neighbor_col >= active.size.1
-                            {
-                                continue;
-                            }
-                            match inactive
-                                .get_cell(neighbor_row as usize, neighbor_col as usize)
-                                .cell_type
-                            {
-                                CellType::Empty | CellType::Prey => {
-                                    found = true;
-                                }
-                                _ => continue,
-                            }
-                        }
+        self.automaton.step(active, inactive, ticks, &mut self.rng);
+        let deaths = self.automaton.take_deaths();
+        if let Some(recorded) = &mut self.deaths {
+            recorded.extend(deaths);
+        }
 
-                        if !found {
-                            // If it can't find an empty neighbor it will die
-                            // inactive.preditor_count -= 1;
-                            continue;
-                        }
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.record(inactive) {
+                tracing::warn!("Stopped recording: {}", e);
+                self.recorder = None;
+            }
+        }
 
-                        match inactive
-                            .get_cell(neighbor_row as usize, neighbor_col as usize)
-                            .cell_type
-                        {
-                            CellType::Prey => {
-                                // inactive.preditor_count += 1;
-                                // inactive.prey_count -= 1;
-                                inactive
-                                    .get_mut_cell(neighbor_row as usize, neighbor_col as usize)
-                                    .cell_type = CellType::Preditor;
-                                inactive
-                                    .get_mut_cell(neighbor_row as usize, neighbor_col as usize)
-                                    .created_at = ticks;
-
-                                inactive.get_mut_cell(row, col).cell_type = CellType::Preditor;
-                                inactive.get_mut_cell(row, col).created_at = cell.created_at;
-
-                                continue;
-                            }
-                            CellType::Preditor => {
-                                // inactive.preditor_count -= 1;
-                            }
-                            CellType::Empty => {
-                                inactive
-                                    .get_mut_cell(neighbor_row as usize, neighbor_col as usize)
-                                    .cell_type = CellType::Preditor;
-                                inactive
-                                    .get_mut_cell(neighbor_row as usize, neighbor_col as usize)
-                                    .created_at = cell.created_at;
-                            }
-                        }
-                    }
-                    CellType::Empty => continue,
-                }
+        self.statistics.record(inactive, ticks);
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.record(inactive);
+        }
+        if ticks.is_multiple_of(STATS_LOG_INTERVAL) {
+            if let Some(sample) = self.statistics.latest() {
+                tracing::debug!(
+                    tick = sample.tick,
+                    births = sample.births,
+                    deaths = sample.deaths,
+                    "{}",
+                    sample.summary()
+                );
             }
         }
 
+        if let Some(boids) = &mut self.boids {
+            // Respawn the flock once every boid has fallen off the edges
+            if boids.agents.is_empty() {
+                boids.spawn(inactive.size, &mut self.rng);
+            }
+            boids.update(inactive);
+        }
+
         self.active_world = inactive_idx;
 
+        if self.automaton.reseed_on_cycle() {
+            self.cycle = self.cycle_detector.check(self.fingerprint());
+        }
+
         self.tick();
     }
 }
 
+/// Everything about one cell, copied out of the world's arrays
 #[derive(Clone, Copy)]
 pub struct Cell {
     pub cell_type: CellType,
     pub created_at: u32,
+    /// Used by Wa-Tor: ticks a shark can go without eating, and ticks since last breeding
+    pub energy: u16,
+    pub breed_timer: u16,
+    /// Ticks until the grass on this cell has grown back, 0 when it can be eaten
+    pub grass: u16,
+    /// Heritable traits of the creature in this cell
+    pub genome: Genome,
 }
 
-#[derive(Clone, Copy)]
+/// The parts of a cell that only creatures use, stored apart from the rest
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Creature {
+    pub energy: u16,
+    pub breed_timer: u16,
+    pub grass: u16,
+    pub genome: Genome,
+}
+
+impl Cell {
+    /// A cell of `cell_type` created on tick `created_at`, with no creature in it
+    pub fn new(cell_type: CellType, created_at: u32) -> Self {
+        Self {
+            cell_type,
+            created_at,
+            energy: 0,
+            breed_timer: 0,
+            grass: 0,
+            genome: Genome::default(),
+        }
+    }
+}
+
+/// What a cell is. Each automaton only uses a few of these.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum CellType {
     Empty,
     Preditor,
     Prey,
+    Alive,
+    Conductor,
+    ElectronHead,
+    ElectronTail,
+    Sand,
+    Water,
+    Stone,
+    Fire,
+    /// A creature from an ecosystem, indexing its list of species
+    Species(u8),
+    /// An obstacle that nothing can move into or be born in
+    Wall,
+}
+
+impl CellType {
+    /// Two bytes identifying the cell type, used when writing worlds to disk
+    pub fn to_bytes(self) -> [u8; 2] {
+        match self {
+            CellType::Empty => [0, 0],
+            CellType::Preditor => [1, 0],
+            CellType::Prey => [2, 0],
+            CellType::Alive => [3, 0],
+            CellType::Conductor => [4, 0],
+            CellType::ElectronHead => [5, 0],
+            CellType::ElectronTail => [6, 0],
+            CellType::Sand => [7, 0],
+            CellType::Water => [8, 0],
+            CellType::Stone => [9, 0],
+            CellType::Fire => [10, 0],
+            CellType::Species(species) => [11, species],
+            CellType::Wall => [12, 0],
+        }
+    }
+
+    /// The cell type written by `to_bytes`, or `None` if the bytes aren't one
+    pub fn from_bytes(bytes: [u8; 2]) -> Option<CellType> {
+        Some(match bytes {
+            [0, _] => CellType::Empty,
+            [1, _] => CellType::Preditor,
+            [2, _] => CellType::Prey,
+            [3, _] => CellType::Alive,
+            [4, _] => CellType::Conductor,
+            [5, _] => CellType::ElectronHead,
+            [6, _] => CellType::ElectronTail,
+            [7, _] => CellType::Sand,
+            [8, _] => CellType::Water,
+            [9, _] => CellType::Stone,
+            [10, _] => CellType::Fire,
+            [11, species] => CellType::Species(species),
+            [12, _] => CellType::Wall,
+            _ => return None,
+        })
+    }
 }
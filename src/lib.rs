@@ -0,0 +1,22 @@
+//! Cellular automata that run as a live desktop background on Windows.
+//!
+//! The simulations work without the desktop background, so they can be embedded in
+//! other apps. Build an automaton from a [`Config`] with [`AutomatonKind::create`],
+//! hand it to [`Simulation::new`] along with the size of the world in cells, then
+//! call [`Simulation::step`] and read the cells back from [`Simulation::world`].
+//! New automata implement the [`Automaton`] trait.
+//!
+//! On Windows, `renderer::window::run` starts the desktop background itself, and
+//! `ctl::run` sends commands to one that is already running. The rest of the crate
+//! builds on any platform.
+
+pub mod config;
+#[cfg(windows)]
+pub mod ctl;
+pub mod game;
+pub mod logfile;
+pub mod renderer;
+
+pub use config::Config;
+pub use game::automaton::{Automaton, AutomatonKind};
+pub use game::world::{Cell, CellType, Simulation, World};
@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::EnvFilter;
+
+use crate::config::Config;
+
+// Logs are named automata.<date>.log, starting a new one each day
+const PREFIX: &str = "automata";
+const SUFFIX: &str = "log";
+// How many days of logs are kept
+const KEEP: usize = 3;
+
+// The newest log file
+pub fn path() -> PathBuf {
+    let directory = Config::directory();
+    let logs = std::fs::read_dir(&directory)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.starts_with(&format!("{}.", PREFIX)) && name.ends_with(&format!(".{}", SUFFIX))
+        });
+    logs.max_by_key(|entry| {
+        entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    })
+    .map_or_else(
+        || directory.join(format!("{}.{}", PREFIX, SUFFIX)),
+        |entry| entry.path(),
+    )
+}
+
+// Show the log in whatever program opens .log files
+pub fn open() {
+    let path = path();
+    if let Err(e) = std::process::Command::new("explorer").arg(&path).spawn() {
+        tracing::warn!("Failed to open {}: {}", path.display(), e);
+    }
+}
+
+// Send every event and span at info level and up (or whatever RUST_LOG asks for) to
+// the log file, along with what the libraries write with `log`, falling back to
+// stderr if it can't be opened
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_ansi(false);
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(PREFIX)
+        .filename_suffix(SUFFIX)
+        .max_log_files(KEEP)
+        .build(Config::directory());
+    let result = match appender {
+        Ok(appender) => subscriber.with_writer(appender).try_init(),
+        Err(e) => {
+            eprintln!(
+                "Failed to open the log in {}: {}",
+                Config::directory().display(),
+                e
+            );
+            subscriber.with_writer(std::io::stderr).try_init()
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to start logging: {}", e);
+    }
+}
@@ -1,10 +1,29 @@
 #![windows_subsystem = "windows"]
 
-use renderer::window::run;
+#[cfg(windows)]
+use automata_background::ctl;
+#[cfg(windows)]
+use automata_background::renderer::window::{run, Mode};
 
-mod renderer;
-mod game;
+#[cfg(windows)]
+fn main() {
+    // `automata-background ctl <command>` controls the instance that's already running
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(String::as_str) == Some("ctl") {
+        std::process::exit(ctl::run(&args[1..]));
+    }
+    // `--preset <string>` adds a preset someone shared and switches to it
+    let preset = args
+        .iter()
+        .position(|arg| arg == "--preset")
+        .and_then(|index| args.get(index + 1))
+        .cloned();
+    pollster::block_on(run(Mode::from_args(&args), preset));
+}
 
+// The library's simulations build anywhere, but the background needs the desktop
+#[cfg(not(windows))]
 fn main() {
-    pollster::block_on(run());
+    eprintln!("Automata Background only runs on Windows");
+    std::process::exit(1);
 }
@@ -0,0 +1,38 @@
+use std::ptr;
+
+use winapi::shared::minwindef::DWORD;
+use winapi::shared::winerror::ERROR_SUCCESS;
+use winapi::um::winreg::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+
+// A null terminated UTF-16 copy of `text` for passing to Windows
+pub fn wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+// The accent color picked in the Windows personalization settings, or None if it
+// can't be read
+pub fn accent_color() -> Option<[f32; 3]> {
+    let key = wide("Software\\Microsoft\\Windows\\DWM");
+    let value = wide("AccentColor");
+    let mut color: DWORD = 0;
+    let mut size = std::mem::size_of::<DWORD>() as DWORD;
+
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            key.as_ptr(),
+            value.as_ptr(),
+            RRF_RT_REG_DWORD,
+            ptr::null_mut(),
+            &mut color as *mut DWORD as *mut _,
+            &mut size,
+        )
+    };
+    if status != ERROR_SUCCESS as i32 {
+        return None;
+    }
+
+    // Stored as 0xAABBGGRR
+    let channel = |shift: u32| ((color >> shift) & 0xFF) as f32 / 255.0;
+    Some([channel(0), channel(8), channel(16)])
+}
@@ -0,0 +1,68 @@
+use wgpu::{Adapter, Backends, DeviceType, Instance, PowerPreference, Surface};
+
+// Picks the graphics adapter to draw with. `choice` is "integrated", "discrete" or
+// part of an adapter's name, and without one wgpu picks as it normally would
+pub async fn pick_adapter(
+    instance: &Instance,
+    surface: &Surface<'_>,
+    choice: Option<&str>,
+) -> Option<Adapter> {
+    let adapters = instance
+        .enumerate_adapters(Backends::PRIMARY)
+        .into_iter()
+        .filter(|adapter| adapter.is_surface_supported(surface))
+        .collect::<Vec<_>>();
+    for adapter in &adapters {
+        let info = adapter.get_info();
+        tracing::info!("Found graphics adapter {} ({:?})", info.name, info.backend);
+    }
+
+    let power_preference = match choice {
+        Some(choice) => {
+            let choice = choice.to_lowercase();
+            let found = adapters.into_iter().find(|adapter| {
+                let info = adapter.get_info();
+                match choice.as_str() {
+                    "integrated" => info.device_type == DeviceType::IntegratedGpu,
+                    "discrete" => info.device_type == DeviceType::DiscreteGpu,
+                    name => info.name.to_lowercase().contains(name),
+                }
+            });
+            if found.is_some() {
+                return found;
+            }
+            tracing::warn!(
+                "No graphics adapter matches \"{}\", using the default",
+                choice
+            );
+            // Still lean the way that was asked for
+            match choice.as_str() {
+                "integrated" => PowerPreference::LowPower,
+                "discrete" => PowerPreference::HighPerformance,
+                _ => PowerPreference::default(),
+            }
+        }
+        None => PowerPreference::default(),
+    };
+
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference,
+            compatible_surface: Some(surface),
+            force_fallback_adapter: false,
+        })
+        .await;
+    if adapter.is_some() {
+        return adapter;
+    }
+
+    // Windows' software renderer still runs the shaders, just more slowly
+    tracing::warn!("No graphics card can draw the background, trying the software renderer");
+    instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference,
+            compatible_surface: Some(surface),
+            force_fallback_adapter: true,
+        })
+        .await
+}
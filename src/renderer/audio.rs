@@ -0,0 +1,364 @@
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use windows::Win32::Media::Audio::{
+    eConsole, eRender, IAudioCaptureClient, IAudioClient, IMMDeviceEnumerator, MMDeviceEnumerator,
+    AUDCLNT_BUFFERFLAGS_SILENT, AUDCLNT_SHAREMODE_SHARED, AUDCLNT_STREAMFLAGS_LOOPBACK,
+    WAVEFORMATEX, WAVEFORMATEXTENSIBLE,
+};
+use windows::Win32::Media::KernelStreaming::WAVE_FORMAT_EXTENSIBLE;
+use windows::Win32::Media::Multimedia::{KSDATAFORMAT_SUBTYPE_IEEE_FLOAT, WAVE_FORMAT_IEEE_FLOAT};
+use windows::Win32::System::Com::{
+    CoCreateInstance, CoInitializeEx, CoTaskMemFree, CLSCTX_ALL, COINIT_MULTITHREADED,
+};
+
+use crate::config::AudioConfig;
+
+// Samples in each FFT, about 20ms at the usual 48kHz
+const FFT_SIZE: usize = 1024;
+// How often new audio is picked up and analyzed
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+// Loopback capture stops getting packets while nothing is playing
+const SILENCE_TIMEOUT: Duration = Duration::from_millis(100);
+// How long to wait before listening again after the audio device goes away
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+// Frequencies in Hz of the bass, mid and treble bands
+const BASS: (f32, f32) = (20.0, 250.0);
+const MID: (f32, f32) = (250.0, 2000.0);
+const TREBLE: (f32, f32) = (2000.0, 8000.0);
+// The loudest each band has been fades by this much every analysis, so the levels
+// follow the volume being turned up or down over a few seconds
+const PEAK_DECAY: f32 = 0.999;
+// Quieter than this counts as silence instead of being turned up to full
+const MIN_PEAK: f32 = 0.01;
+// How much of the last level is kept when a band gets quieter
+const RELEASE: f32 = 0.9;
+// A beat is bass this many times louder than it has been lately
+const BEAT_THRESHOLD: f32 = 1.4;
+const MIN_BEAT_LEVEL: f32 = 0.3;
+const MIN_BEAT_INTERVAL: Duration = Duration::from_millis(250);
+
+// How loud the music is overall and in each band, from 0 to 1 relative to how loud
+// it has been lately
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Levels {
+    pub energy: f32,
+    pub bass: f32,
+    pub mid: f32,
+    pub treble: f32,
+    // Beats heard since listening started
+    pub beats: u32,
+}
+
+#[derive(Clone, Copy)]
+enum SampleFormat {
+    Float,
+    Int16,
+}
+
+// In-place radix-2 FFT of `re` and `im`, whose length has to be a power of two
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    // Put the samples in bit reversed order
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let (tr, ti) = (re[b] * cos - im[b] * sin, re[b] * sin + im[b] * cos);
+                (re[b], im[b]) = (re[a] - tr, im[a] - ti);
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+// Level between 0 and 1 of `value` against the loudest it has been, which fades
+fn normalize(value: f32, peak: &mut f32) -> f32 {
+    *peak = (*peak * PEAK_DECAY).max(value).max(MIN_PEAK);
+    value / *peak
+}
+
+// Jumps straight up to louder levels and falls back down gradually
+fn smooth(old: f32, new: f32) -> f32 {
+    if new > old {
+        new
+    } else {
+        old * RELEASE + new * (1.0 - RELEASE)
+    }
+}
+
+// Turns the last few milliseconds of audio into levels
+struct Analyzer {
+    sample_rate: f32,
+    samples: VecDeque<f32>,
+    levels: Levels,
+    // Loudest energy, bass, mid and treble lately
+    peaks: [f32; 4],
+    bass_average: f32,
+    last_beat: Instant,
+}
+
+impl Analyzer {
+    fn new(sample_rate: f32, beats: u32) -> Self {
+        Self {
+            sample_rate,
+            samples: VecDeque::from(vec![0.0; FFT_SIZE]),
+            levels: Levels {
+                beats,
+                ..Levels::default()
+            },
+            peaks: [MIN_PEAK; 4],
+            bass_average: 0.0,
+            last_beat: Instant::now(),
+        }
+    }
+
+    fn push(&mut self, sample: f32) {
+        self.samples.pop_front();
+        self.samples.push_back(sample);
+    }
+
+    fn silence(&mut self) {
+        self.samples.iter_mut().for_each(|sample| *sample = 0.0);
+    }
+
+    fn analyze(&mut self) -> Levels {
+        let energy = (self.samples.iter().map(|s| s * s).sum::<f32>() / FFT_SIZE as f32).sqrt();
+
+        // A Hann window stops the edges of the samples showing up as noise
+        let mut re = self
+            .samples
+            .iter()
+            .enumerate()
+            .map(|(i, s)| s * (0.5 - 0.5 * (2.0 * PI * i as f32 / FFT_SIZE as f32).cos()))
+            .collect::<Vec<_>>();
+        let mut im = vec![0.0; FFT_SIZE];
+        fft(&mut re, &mut im);
+
+        let bin_width = self.sample_rate / FFT_SIZE as f32;
+        let band = |(low, high): (f32, f32)| {
+            let first = ((low / bin_width) as usize).max(1);
+            let last = ((high / bin_width) as usize).clamp(first + 1, FFT_SIZE / 2);
+            let total: f32 = (first..last)
+                .map(|k| (re[k] * re[k] + im[k] * im[k]).sqrt())
+                .sum();
+            total / (last - first) as f32 / (FFT_SIZE / 2) as f32
+        };
+        let (bass, mid, treble) = (band(BASS), band(MID), band(TREBLE));
+
+        let [energy_peak, bass_peak, mid_peak, treble_peak] = &mut self.peaks;
+        let energy = normalize(energy, energy_peak);
+        let bass = normalize(bass, bass_peak);
+        let mid = normalize(mid, mid_peak);
+        let treble = normalize(treble, treble_peak);
+
+        let beat = bass > self.bass_average * BEAT_THRESHOLD
+            && bass > MIN_BEAT_LEVEL
+            && self.last_beat.elapsed() >= MIN_BEAT_INTERVAL;
+        if beat {
+            self.levels.beats = self.levels.beats.wrapping_add(1);
+            self.last_beat = Instant::now();
+        }
+        self.bass_average = self.bass_average * 0.98 + bass * 0.02;
+
+        let levels = &mut self.levels;
+        levels.energy = smooth(levels.energy, energy);
+        levels.bass = smooth(levels.bass, bass);
+        levels.mid = smooth(levels.mid, mid);
+        levels.treble = smooth(levels.treble, treble);
+        *levels
+    }
+}
+
+// Average of every channel in `frame`
+unsafe fn mono(data: *const u8, frame: usize, channels: usize, format: SampleFormat) -> f32 {
+    let first = frame * channels;
+    let total: f32 = match format {
+        SampleFormat::Float => (0..channels)
+            .map(|c| *(data as *const f32).add(first + c))
+            .sum(),
+        SampleFormat::Int16 => (0..channels)
+            .map(|c| *(data as *const i16).add(first + c) as f32 / 32768.0)
+            .sum(),
+    };
+    total / channels as f32
+}
+
+unsafe fn sample_format(format: *const WAVEFORMATEX) -> Option<SampleFormat> {
+    let (tag, bits) = ((*format).wFormatTag as u32, (*format).wBitsPerSample);
+    let float = match tag {
+        WAVE_FORMAT_IEEE_FLOAT => true,
+        WAVE_FORMAT_EXTENSIBLE => {
+            let sub_format = (*(format as *const WAVEFORMATEXTENSIBLE)).SubFormat;
+            sub_format == KSDATAFORMAT_SUBTYPE_IEEE_FLOAT
+        }
+        _ => false,
+    };
+    match (float, bits) {
+        (true, 32) => Some(SampleFormat::Float),
+        (false, 16) => Some(SampleFormat::Int16),
+        _ => None,
+    }
+}
+
+// Record whatever is playing on the default output device until `stop` is set or
+// the device goes away
+fn capture(levels: &Mutex<Levels>, stop: &AtomicBool) -> Result<(), String> {
+    unsafe {
+        let enumerator: IMMDeviceEnumerator =
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL).map_err(|e| e.to_string())?;
+        let device = enumerator
+            .GetDefaultAudioEndpoint(eRender, eConsole)
+            .map_err(|e| e.to_string())?;
+        let client: IAudioClient = device
+            .Activate(CLSCTX_ALL, None)
+            .map_err(|e| e.to_string())?;
+
+        let format = client.GetMixFormat().map_err(|e| e.to_string())?;
+        let (channels, sample_rate) = ((*format).nChannels as usize, (*format).nSamplesPerSec);
+        let sample_format = sample_format(format);
+        // Loopback records the output in the same format it's mixed in
+        let initialized = client.Initialize(
+            AUDCLNT_SHAREMODE_SHARED,
+            AUDCLNT_STREAMFLAGS_LOOPBACK,
+            1_000_000,
+            0,
+            format,
+            None,
+        );
+        CoTaskMemFree(Some(format as *const _));
+        initialized.map_err(|e| e.to_string())?;
+        let Some(sample_format) = sample_format.filter(|_| channels > 0) else {
+            return Err("The audio device mixes in a format that can't be read".to_string());
+        };
+
+        let capture_client: IAudioCaptureClient = client.GetService().map_err(|e| e.to_string())?;
+        client.Start().map_err(|e| e.to_string())?;
+
+        let beats = levels.lock().unwrap().beats;
+        let mut analyzer = Analyzer::new(sample_rate as f32, beats);
+        let mut last_packet = Instant::now();
+        while !stop.load(Ordering::Relaxed) {
+            thread::sleep(POLL_INTERVAL);
+            loop {
+                let packet = capture_client
+                    .GetNextPacketSize()
+                    .map_err(|e| e.to_string())?;
+                if packet == 0 {
+                    break;
+                }
+                let (mut data, mut frames, mut flags) = (ptr::null_mut(), 0, 0);
+                capture_client
+                    .GetBuffer(&mut data, &mut frames, &mut flags, None, None)
+                    .map_err(|e| e.to_string())?;
+                let silent = flags & AUDCLNT_BUFFERFLAGS_SILENT.0 as u32 != 0;
+                for frame in 0..frames as usize {
+                    let sample = match silent {
+                        true => 0.0,
+                        false => mono(data, frame, channels, sample_format),
+                    };
+                    analyzer.push(sample);
+                }
+                capture_client
+                    .ReleaseBuffer(frames)
+                    .map_err(|e| e.to_string())?;
+                last_packet = Instant::now();
+            }
+            if last_packet.elapsed() > SILENCE_TIMEOUT {
+                analyzer.silence();
+            }
+            *levels.lock().unwrap() = analyzer.analyze();
+        }
+        let _ = client.Stop();
+    }
+    Ok(())
+}
+
+// Listens to whatever is playing on the default output device on its own thread,
+// for as long as it's kept
+pub struct AudioListener {
+    pub config: AudioConfig,
+    levels: Arc<Mutex<Levels>>,
+    stop: Arc<AtomicBool>,
+    beats_seen: u32,
+}
+
+impl AudioListener {
+    pub fn start(config: AudioConfig) -> Self {
+        let levels = Arc::new(Mutex::new(Levels::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (thread_levels, thread_stop) = (levels.clone(), stop.clone());
+        thread::spawn(move || {
+            if let Err(e) = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) } {
+                tracing::warn!("Failed to listen to audio: {}", e);
+                return;
+            }
+            // Switching output devices ends the capture, so start over on the new one
+            let mut last_error = None;
+            while !thread_stop.load(Ordering::Relaxed) {
+                if let Err(e) = capture(&thread_levels, &thread_stop) {
+                    if last_error.as_ref() != Some(&e) {
+                        tracing::warn!("Failed to listen to audio: {}", e);
+                    }
+                    last_error = Some(e);
+                    // Let the simulation settle back down while there's nothing to hear
+                    let mut levels = thread_levels.lock().unwrap();
+                    *levels = Levels {
+                        beats: levels.beats,
+                        ..Levels::default()
+                    };
+                    drop(levels);
+                    thread::sleep(RETRY_INTERVAL);
+                }
+            }
+        });
+        Self {
+            config,
+            levels,
+            stop,
+            beats_seen: 0,
+        }
+    }
+
+    pub fn levels(&self) -> Levels {
+        *self.levels.lock().unwrap()
+    }
+
+    // Beats heard since the last time this was called
+    pub fn take_beats(&mut self) -> u32 {
+        let beats = self.levels().beats;
+        let new = beats.wrapping_sub(self.beats_seen);
+        self.beats_seen = beats;
+        new
+    }
+}
+
+impl Drop for AudioListener {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,56 @@
+use std::ptr;
+
+use winapi::shared::minwindef::DWORD;
+use winapi::shared::winerror::ERROR_SUCCESS;
+use winapi::um::winnt::REG_SZ;
+use winapi::um::winreg::{
+    RegDeleteKeyValueW, RegGetValueW, RegSetKeyValueW, HKEY_CURRENT_USER, RRF_RT_REG_SZ,
+};
+
+use crate::renderer::accent::wide;
+
+const RUN_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+const VALUE_NAME: &str = "AutomataBackground";
+
+// Whether the app is set to start when the user logs in
+pub fn is_enabled() -> bool {
+    let (key, value) = (wide(RUN_KEY), wide(VALUE_NAME));
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            key.as_ptr(),
+            value.as_ptr(),
+            RRF_RT_REG_SZ,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+    };
+    status == ERROR_SUCCESS as i32
+}
+
+// Add or remove the app from the programs Windows runs when the user logs in
+pub fn set_enabled(enabled: bool) -> Result<(), String> {
+    let (key, value) = (wide(RUN_KEY), wide(VALUE_NAME));
+    let status = if enabled {
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        let command = wide(&format!("\"{}\"", exe.display()));
+        unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                key.as_ptr(),
+                value.as_ptr(),
+                REG_SZ,
+                command.as_ptr() as *const _,
+                (command.len() * std::mem::size_of::<u16>()) as DWORD,
+            )
+        }
+    } else {
+        unsafe { RegDeleteKeyValueW(HKEY_CURRENT_USER, key.as_ptr(), value.as_ptr()) }
+    };
+
+    if status != ERROR_SUCCESS as i32 {
+        return Err(format!("registry error {}", status));
+    }
+    Ok(())
+}
@@ -0,0 +1,55 @@
+// Which 64 characters stand for each 6 bits, and whether the text is padded out
+// with '=' to a multiple of 4 characters
+pub struct Alphabet {
+    letters: &'static [u8; 64],
+    padded: bool,
+}
+
+// The usual alphabet, which the WebSocket handshake uses
+#[cfg(feature = "http")]
+pub const STANDARD: Alphabet = Alphabet {
+    letters: b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/",
+    padded: true,
+};
+
+// Safe to put in a URL as it is
+pub const URL_SAFE: Alphabet = Alphabet {
+    letters: b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_",
+    padded: false,
+};
+
+pub fn encode(data: &[u8], alphabet: &Alphabet) -> String {
+    let mut encoded = String::new();
+    for chunk in data.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, &byte)| bits | (byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            let letter = alphabet.letters[(bits >> (18 - 6 * i) & 63) as usize] as char;
+            match i <= chunk.len() {
+                true => encoded.push(letter),
+                false if alphabet.padded => encoded.push('='),
+                false => {}
+            }
+        }
+    }
+    encoded
+}
+
+// The bytes in `text`, or None if it has characters outside the alphabet. Padding
+// is allowed either way.
+pub fn decode(text: &str, alphabet: &Alphabet) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let (mut bits, mut count) = (0u32, 0);
+    for c in text.trim_end_matches('=').bytes() {
+        bits = bits << 6 | alphabet.letters.iter().position(|&letter| letter == c)? as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+            bits &= (1 << count) - 1;
+        }
+    }
+    Some(bytes)
+}
@@ -0,0 +1,80 @@
+use std::time::Instant;
+
+use serde::Deserialize;
+
+// Longest time drifted in one go, so the view doesn't jump after the app has been
+// asleep
+const MAX_STEP_SECONDS: f32 = 0.25;
+
+// Settings for simulating a world bigger than the window and showing part of it
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct CameraConfig {
+    // How many times wider and taller than the window the world is
+    pub world_scale: u32,
+    // Cells per second the view slides across the world, to the right and up
+    pub drift: [f32; 2],
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self {
+            world_scale: 3,
+            drift: [2.0, 1.0],
+        }
+    }
+}
+
+// Which part of the world the window shows. Going off one edge of the world comes
+// back on at the other.
+pub struct Camera {
+    pub config: CameraConfig,
+    // Stopped from the tray menu
+    pub drifting: bool,
+    // Cells from the bottom left of the world to the bottom left of the window
+    position: [f32; 2],
+    last_update: Instant,
+}
+
+impl Camera {
+    pub fn new(config: CameraConfig) -> Self {
+        Self {
+            config,
+            drifting: true,
+            position: [0.0; 2],
+            last_update: Instant::now(),
+        }
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.config.world_scale.max(1)
+    }
+
+    pub fn position(&self) -> [f32; 2] {
+        self.position
+    }
+
+    // Drift for as long as it's been since the last update, over a world `size` cells
+    // across. Returns whether the view moved.
+    pub fn update(&mut self, size: (usize, usize)) -> bool {
+        let elapsed = self
+            .last_update
+            .elapsed()
+            .as_secs_f32()
+            .min(MAX_STEP_SECONDS);
+        self.last_update = Instant::now();
+        if !self.drifting || self.config.drift == [0.0; 2] {
+            return false;
+        }
+        self.pan(self.config.drift.map(|speed| speed * elapsed), size);
+        true
+    }
+
+    // Move the view `cells` to the right and up over a world `size` cells across
+    pub fn pan(&mut self, cells: [f32; 2], size: (usize, usize)) {
+        let size = [size.0, size.1].map(|size| size.max(1) as f32);
+        for axis in 0..2 {
+            self.position[axis] = (self.position[axis] + cells[axis]).rem_euclid(size[axis]);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::ptr;
+
+use winapi::um::winbase::{GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+use winapi::um::winuser::{
+    CloseClipboard, EmptyClipboard, GetClipboardData, OpenClipboard, SetClipboardData,
+    CF_UNICODETEXT,
+};
+
+use crate::renderer::accent::wide;
+
+// Put `text` on the clipboard in place of whatever was there
+pub fn copy(text: &str) -> Result<(), String> {
+    let text = wide(text);
+    unsafe {
+        if OpenClipboard(ptr::null_mut()) == 0 {
+            return Err("Couldn't open the clipboard".to_string());
+        }
+        EmptyClipboard();
+        let memory = GlobalAlloc(GMEM_MOVEABLE, text.len() * std::mem::size_of::<u16>());
+        let locked = match memory.is_null() {
+            true => ptr::null_mut(),
+            false => GlobalLock(memory) as *mut u16,
+        };
+        if locked.is_null() {
+            if !memory.is_null() {
+                GlobalFree(memory);
+            }
+            CloseClipboard();
+            return Err("Couldn't make room for the text".to_string());
+        }
+        ptr::copy_nonoverlapping(text.as_ptr(), locked, text.len());
+        GlobalUnlock(memory);
+        // The clipboard owns the memory once it has taken it
+        let taken = !SetClipboardData(CF_UNICODETEXT, memory).is_null();
+        if !taken {
+            GlobalFree(memory);
+        }
+        CloseClipboard();
+        match taken {
+            true => Ok(()),
+            false => Err("Couldn't put the text on the clipboard".to_string()),
+        }
+    }
+}
+
+// The text on the clipboard
+pub fn paste() -> Result<String, String> {
+    unsafe {
+        if OpenClipboard(ptr::null_mut()) == 0 {
+            return Err("Couldn't open the clipboard".to_string());
+        }
+        let memory = GetClipboardData(CF_UNICODETEXT);
+        let locked = match memory.is_null() {
+            true => ptr::null(),
+            false => GlobalLock(memory) as *const u16,
+        };
+        if locked.is_null() {
+            CloseClipboard();
+            return Err("There's no text on the clipboard".to_string());
+        }
+        let length = (0..).take_while(|&i| *locked.add(i) != 0).count();
+        let text = String::from_utf16_lossy(std::slice::from_raw_parts(locked, length));
+        GlobalUnlock(memory);
+        CloseClipboard();
+        Ok(text)
+    }
+}
@@ -0,0 +1,27 @@
+use winapi::um::minwinbase::SYSTEMTIME;
+use winapi::um::sysinfoapi::GetLocalTime;
+
+fn local_time() -> SYSTEMTIME {
+    unsafe {
+        let mut time: SYSTEMTIME = std::mem::zeroed();
+        GetLocalTime(&mut time);
+        time
+    }
+}
+
+// The local time of day in hours, from 0 up to 24
+pub fn local_hour() -> f32 {
+    let time = local_time();
+    time.wHour as f32 + time.wMinute as f32 / 60.0 + time.wSecond as f32 / 3600.0
+}
+
+// The local time as hours and minutes, like "9:41" or "21:41"
+pub fn time_text(twenty_four_hour: bool) -> String {
+    let time = local_time();
+    let hour = match (twenty_four_hour, time.wHour % 12) {
+        (true, _) => time.wHour,
+        (false, 0) => 12,
+        (false, hour) => hour,
+    };
+    format!("{}:{:02}", hour, time.wMinute)
+}
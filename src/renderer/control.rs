@@ -0,0 +1,308 @@
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::ptr;
+use std::sync::mpsc::{self, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use winapi::shared::winerror::{ERROR_PIPE_BUSY, ERROR_PIPE_CONNECTED};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::fileapi::{FlushFileBuffers, ReadFile, WriteFile};
+use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+use winapi::um::namedpipeapi::{ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe};
+use winapi::um::winbase::{
+    FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX, PIPE_READMODE_BYTE,
+    PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE, PIPE_WAIT,
+};
+use winapi::um::winnt::HANDLE;
+
+use crate::game::automaton::AutomatonKind;
+use crate::renderer::accent::wide;
+
+// Scripts connect here and write one JSON command per line, getting one JSON
+// response per line back
+pub const PIPE_NAME: &str = r"\\.\pipe\automata-background";
+const BUFFER_SIZE: u32 = 4096;
+// Longest command line a client can send before it's hung up on
+const MAX_LINE_BYTES: usize = 64 * 1024;
+// How long a command waits for the event loop to get to it
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+// The fastest speed that can be asked for, the same as the tray menu's fastest
+const MAX_SPEED: u32 = 400;
+
+// Read from JSON like {"command": "set_speed", "percent": 200}
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    Pause,
+    Resume,
+    Reseed,
+    // B/S rulestring for the life automaton, which is switched to
+    SetRule {
+        rule: String,
+    },
+    // Percentage of ticks_per_second to run at
+    SetSpeed {
+        percent: u32,
+    },
+    SetAutomaton {
+        automaton: AutomatonKind,
+    },
+    SetCellSize {
+        size: u32,
+    },
+    Stats,
+    // The settings that can be changed while running
+    Settings,
+    // The current frame as a PNG, which only the HTTP server can send back
+    #[serde(skip)]
+    Snapshot,
+}
+
+pub enum Reply {
+    Json(String),
+    Png(Vec<u8>),
+}
+
+// A command from the pipe or the HTTP server, waiting for the event loop to carry it
+// out and answer
+pub struct Request {
+    pub command: Command,
+    reply: Sender<Reply>,
+}
+
+impl Request {
+    pub fn respond(self, response: String) {
+        let _ = self.reply.send(Reply::Json(response));
+    }
+
+    pub fn respond_png(self, png: Vec<u8>) {
+        let _ = self.reply.send(Reply::Png(png));
+    }
+}
+
+// A client of the HTTP server's /stream WebSocket, sent the state after every tick
+pub struct Subscriber {
+    // Columns in the shrunk down grid sent along with the stats, if it wants one
+    pub grid: Option<usize>,
+    pub sender: SyncSender<String>,
+}
+
+pub type Subscribers = Arc<Mutex<Vec<Subscriber>>>;
+
+// `text` as a quoted JSON string
+pub fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// A successful response, with `fields` holding values already written as JSON
+pub fn ok_response(fields: &[(&str, String)]) -> String {
+    let mut response = String::from("{\"ok\":true");
+    for (name, value) in fields {
+        response.push_str(&format!(",{}:{}", json_string(name), value));
+    }
+    response.push('}');
+    response
+}
+
+pub fn error_response(error: &str) -> String {
+    format!("{{\"ok\":false,\"error\":{}}}", json_string(error))
+}
+
+// Commands asking for something the app can't run at
+fn check(command: Command) -> Result<Command, String> {
+    match command {
+        Command::SetSpeed { percent } if percent > MAX_SPEED => Err(format!(
+            "The speed needs to be a percentage from 0 to {}",
+            MAX_SPEED
+        )),
+        Command::SetCellSize { size: 0 } => Err("The cell size needs to be 1 or more".to_string()),
+        command => Ok(command),
+    }
+}
+
+// Read a command like {"command": "set_speed", "percent": 200}
+pub fn parse_command(line: &str) -> Result<Command, String> {
+    serde_json::from_str(line)
+        .map_err(|e| e.to_string())
+        .and_then(check)
+}
+
+// Settings that can be changed in one go over HTTP
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Settings {
+    automaton: Option<AutomatonKind>,
+    rule: Option<String>,
+    speed: Option<u32>,
+    cell_size: Option<u32>,
+}
+
+// Read settings to change like {"automaton": "life", "speed": 200} into the commands
+// that change them
+pub fn parse_settings(body: &str) -> Result<Vec<Command>, String> {
+    let settings: Settings = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    // The automaton goes first, so a rule set along with it isn't switched away from
+    let commands = [
+        settings
+            .automaton
+            .map(|automaton| Command::SetAutomaton { automaton }),
+        settings.rule.map(|rule| Command::SetRule { rule }),
+        settings.speed.map(|percent| Command::SetSpeed { percent }),
+        settings.cell_size.map(|size| Command::SetCellSize { size }),
+    ];
+    commands.into_iter().flatten().map(check).collect()
+}
+
+// Hand the command to the event loop and wait for its answer
+pub fn ask(command: Command, requests: &Sender<Request>, wake: &dyn Fn()) -> Reply {
+    let (reply, response) = mpsc::channel();
+    if requests.send(Request { command, reply }).is_err() {
+        return Reply::Json(error_response("The app is closing"));
+    }
+    wake();
+    response
+        .recv_timeout(RESPONSE_TIMEOUT)
+        .unwrap_or_else(|_| Reply::Json(error_response("The app didn't answer")))
+}
+
+fn write_line(pipe: HANDLE, line: &str) -> bool {
+    let line = format!("{}\n", line);
+    let mut written = 0;
+    unsafe {
+        WriteFile(
+            pipe,
+            line.as_ptr() as *const _,
+            line.len() as u32,
+            &mut written,
+            ptr::null_mut(),
+        ) != 0
+    }
+}
+
+// Answer commands from one client until it hangs up
+fn serve_client(pipe: HANDLE, requests: &Sender<Request>, wake: &dyn Fn()) {
+    let mut pending = Vec::new();
+    let mut buffer = [0u8; BUFFER_SIZE as usize];
+    loop {
+        let mut read = 0;
+        let ok = unsafe {
+            ReadFile(
+                pipe,
+                buffer.as_mut_ptr() as *mut _,
+                BUFFER_SIZE,
+                &mut read,
+                ptr::null_mut(),
+            )
+        };
+        if ok == 0 || read == 0 {
+            return;
+        }
+        pending.extend_from_slice(&buffer[..read as usize]);
+        if !pending.contains(&b'\n') && pending.len() > MAX_LINE_BYTES {
+            write_line(pipe, &error_response("The command is too long"));
+            return;
+        }
+
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let line = pending.drain(..=end).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line);
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match parse_command(&line).map(|command| ask(command, requests, wake)) {
+                Ok(Reply::Json(response)) => response,
+                Ok(Reply::Png(_)) => error_response("Images can't be sent over the pipe"),
+                Err(e) => error_response(&e),
+            };
+            if !write_line(pipe, &response) {
+                return;
+            }
+        }
+        unsafe { FlushFileBuffers(pipe) };
+    }
+}
+
+// Listen for commands on the named pipe, one client at a time. Each command is sent
+// down `sender` and `wake` is called to get the event loop to look at it.
+pub fn serve(sender: Sender<Request>, wake: impl Fn() + Send + 'static) {
+    thread::spawn(move || {
+        let name = wide(PIPE_NAME);
+        // Only the first instance of the app gets to make the pipe
+        let mut first = FILE_FLAG_FIRST_PIPE_INSTANCE;
+        loop {
+            let pipe = unsafe {
+                CreateNamedPipeW(
+                    name.as_ptr(),
+                    PIPE_ACCESS_DUPLEX | first,
+                    PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                    1,
+                    BUFFER_SIZE,
+                    BUFFER_SIZE,
+                    0,
+                    ptr::null_mut(),
+                )
+            };
+            if pipe == INVALID_HANDLE_VALUE {
+                tracing::warn!("Couldn't open the control pipe {}", PIPE_NAME);
+                return;
+            }
+            first = 0;
+
+            unsafe {
+                // The client can get in between making the pipe and waiting for it
+                let connected = ConnectNamedPipe(pipe, ptr::null_mut()) != 0
+                    || GetLastError() == ERROR_PIPE_CONNECTED;
+                if connected {
+                    serve_client(pipe, &sender, &wake);
+                }
+                DisconnectNamedPipe(pipe);
+                CloseHandle(pipe);
+            }
+        }
+    });
+}
+
+// Send one command line to the running app and wait for its response line
+pub fn send(line: &str) -> Result<String, String> {
+    // The app answers one client at a time, so wait for whoever is ahead
+    let started = Instant::now();
+    let pipe = loop {
+        match OpenOptions::new().read(true).write(true).open(PIPE_NAME) {
+            Ok(pipe) => break pipe,
+            Err(e)
+                if e.raw_os_error() == Some(ERROR_PIPE_BUSY as i32)
+                    && started.elapsed() < RESPONSE_TIMEOUT =>
+            {
+                thread::sleep(Duration::from_millis(50))
+            }
+            Err(e) => return Err(format!("Couldn't connect to the background: {}", e)),
+        }
+    };
+    let mut writer = &pipe;
+    writeln!(writer, "{}", line).map_err(|e| format!("Couldn't send the command: {}", e))?;
+
+    let mut response = String::new();
+    BufReader::new(&pipe)
+        .read_line(&mut response)
+        .map_err(|e| format!("Couldn't read the response: {}", e))?;
+    match response.trim_end() {
+        "" => Err("The background hung up without answering".to_string()),
+        response => Ok(response.to_string()),
+    }
+}
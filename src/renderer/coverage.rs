@@ -0,0 +1,103 @@
+use std::ptr;
+
+use winapi::shared::minwindef::{BOOL, DWORD, LPARAM, TRUE};
+use winapi::shared::windef::{HDC, HMONITOR, HWND, LPRECT, RECT};
+use winapi::um::dwmapi::{DwmGetWindowAttribute, DWMWA_CLOAKED};
+use winapi::um::winuser::{
+    EnumDisplayMonitors, EnumWindows, GetClassNameW, GetMonitorInfoW, GetWindowLongW,
+    GetWindowRect, IsIconic, IsWindowVisible, GWL_EXSTYLE, MONITORINFO, WS_EX_NOACTIVATE,
+    WS_EX_TRANSPARENT,
+};
+
+// Windows the shell puts behind everything else, which always cover the desktop
+const DESKTOP_CLASSES: [&str; 2] = ["Progman", "WorkerW"];
+
+unsafe extern "system" fn add_work_area(
+    monitor: HMONITOR,
+    _hdc: HDC,
+    _rect: LPRECT,
+    data: LPARAM,
+) -> BOOL {
+    let work_areas = &mut *(data as *mut Vec<RECT>);
+    let mut info: MONITORINFO = std::mem::zeroed();
+    info.cbSize = std::mem::size_of::<MONITORINFO>() as DWORD;
+    if GetMonitorInfoW(monitor, &mut info) != 0 {
+        work_areas.push(info.rcWork);
+    }
+    TRUE
+}
+
+fn class_name(hwnd: HWND) -> String {
+    let mut name = [0u16; 64];
+    let length = unsafe { GetClassNameW(hwnd, name.as_mut_ptr(), name.len() as i32) };
+    String::from_utf16_lossy(&name[..length.max(0) as usize])
+}
+
+// Whether the window is one the shell puts behind everything else
+pub fn is_desktop_window(hwnd: HWND) -> bool {
+    DESKTOP_CLASSES.contains(&class_name(hwnd).as_str())
+}
+
+// Windows on another virtual desktop or suspended apps are still "visible", but
+// DWM has hidden them
+fn is_cloaked(hwnd: HWND) -> bool {
+    let mut cloaked: DWORD = 0;
+    let result = unsafe {
+        DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_CLOAKED,
+            &mut cloaked as *mut DWORD as *mut _,
+            std::mem::size_of::<DWORD>() as DWORD,
+        )
+    };
+    result == 0 && cloaked != 0
+}
+
+unsafe extern "system" fn add_window(hwnd: HWND, data: LPARAM) -> BOOL {
+    let windows = &mut *(data as *mut Vec<RECT>);
+    if IsWindowVisible(hwnd) == 0 || IsIconic(hwnd) != 0 || is_cloaked(hwnd) {
+        return TRUE;
+    }
+    // Overlays that clicks go straight through don't hide anything
+    let style = GetWindowLongW(hwnd, GWL_EXSTYLE) as DWORD;
+    if style & (WS_EX_TRANSPARENT | WS_EX_NOACTIVATE) != 0 {
+        return TRUE;
+    }
+    if is_desktop_window(hwnd) {
+        return TRUE;
+    }
+
+    let mut rect: RECT = std::mem::zeroed();
+    if GetWindowRect(hwnd, &mut rect) != 0 {
+        windows.push(rect);
+    }
+    TRUE
+}
+
+fn contains(outer: &RECT, inner: &RECT) -> bool {
+    outer.left <= inner.left
+        && outer.top <= inner.top
+        && outer.right >= inner.right
+        && outer.bottom >= inner.bottom
+}
+
+// Whether every monitor is filled by a maximized or fullscreen window, so none of
+// the desktop can be seen
+pub fn desktop_covered() -> bool {
+    let mut work_areas: Vec<RECT> = Vec::new();
+    let mut windows: Vec<RECT> = Vec::new();
+    unsafe {
+        EnumDisplayMonitors(
+            ptr::null_mut(),
+            ptr::null(),
+            Some(add_work_area),
+            &mut work_areas as *mut Vec<RECT> as LPARAM,
+        );
+        EnumWindows(Some(add_window), &mut windows as *mut Vec<RECT> as LPARAM);
+    }
+
+    !work_areas.is_empty()
+        && work_areas
+            .iter()
+            .all(|work_area| windows.iter().any(|window| contains(window, work_area)))
+}
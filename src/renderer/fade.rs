@@ -0,0 +1,60 @@
+use std::time::{Duration, Instant};
+
+// Brings everything in gradually when the app starts, instead of it appearing all
+// at once, and takes it away again the same way when the app is closed
+pub struct Fade {
+    duration: Duration,
+    started: Instant,
+    // When fading out began and how far in it was at the time
+    leaving: Option<(Instant, f32)>,
+}
+
+impl Fade {
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            duration: Duration::from_secs_f32(seconds.max(0.0)),
+            started: Instant::now(),
+            leaving: None,
+        }
+    }
+
+    // How far faded in, from 0 for nothing showing to 1 for everything
+    pub fn level(&self) -> f32 {
+        match self.leaving {
+            Some((since, from)) => from * (1.0 - self.progress(since)),
+            None => self.progress(self.started),
+        }
+    }
+
+    // Smoothed fraction of the fade's duration that has gone by since `since`
+    fn progress(&self, since: Instant) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        let t = (since.elapsed().as_secs_f32() / self.duration.as_secs_f32()).min(1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    // Whether the level is still on its way up or down
+    pub fn is_changing(&self) -> bool {
+        let since = self.leaving.map_or(self.started, |(since, _)| since);
+        since.elapsed() < self.duration
+    }
+
+    // Start fading out from wherever it's got to. Returns false when there's no fade
+    // to wait for.
+    pub fn leave(&mut self) -> bool {
+        if self.duration.is_zero() {
+            return false;
+        }
+        if self.leaving.is_none() {
+            self.leaving = Some((Instant::now(), self.level()));
+        }
+        true
+    }
+
+    // Whether it has finished fading out
+    pub fn is_gone(&self) -> bool {
+        self.leaving.is_some() && !self.is_changing()
+    }
+}
@@ -0,0 +1,114 @@
+use winapi::shared::basetsd::{DWORD_PTR, UINT_PTR};
+use winapi::shared::minwindef::{LPARAM, LRESULT, UINT, WPARAM};
+use winapi::shared::windef::HWND;
+use winapi::um::commctrl::{DefSubclassProc, SetWindowSubclass};
+use winapi::um::winuser::{
+    RegisterHotKey, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, VK_DOWN, VK_LEFT, VK_OEM_MINUS,
+    VK_OEM_PLUS, VK_RIGHT, VK_UP, WM_HOTKEY,
+};
+
+// Things that can be done from anywhere with Ctrl+Alt and a key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hotkey {
+    Pause,
+    Reseed,
+    NextAutomaton,
+    Screenshot,
+    // Move the camera with the arrow keys
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    // Zoom with plus and minus
+    ZoomIn,
+    ZoomOut,
+}
+
+impl Hotkey {
+    const ALL: [Hotkey; 10] = [
+        Hotkey::Pause,
+        Hotkey::Reseed,
+        Hotkey::NextAutomaton,
+        Hotkey::Screenshot,
+        Hotkey::PanLeft,
+        Hotkey::PanRight,
+        Hotkey::PanUp,
+        Hotkey::PanDown,
+        Hotkey::ZoomIn,
+        Hotkey::ZoomOut,
+    ];
+
+    // The virtual key code pressed along with Ctrl+Alt
+    fn key(self) -> UINT {
+        match self {
+            Hotkey::Pause => b'P' as UINT,
+            Hotkey::Reseed => b'R' as UINT,
+            Hotkey::NextAutomaton => b'N' as UINT,
+            Hotkey::Screenshot => b'S' as UINT,
+            Hotkey::PanLeft => VK_LEFT as UINT,
+            Hotkey::PanRight => VK_RIGHT as UINT,
+            Hotkey::PanUp => VK_UP as UINT,
+            Hotkey::PanDown => VK_DOWN as UINT,
+            Hotkey::ZoomIn => VK_OEM_PLUS as UINT,
+            Hotkey::ZoomOut => VK_OEM_MINUS as UINT,
+        }
+    }
+
+    fn is_pan(self) -> bool {
+        matches!(
+            self,
+            Hotkey::PanLeft | Hotkey::PanRight | Hotkey::PanUp | Hotkey::PanDown
+        )
+    }
+
+    // Holding these down keeps the camera moving or zooming
+    fn repeats(self) -> bool {
+        self.is_pan() || matches!(self, Hotkey::ZoomIn | Hotkey::ZoomOut)
+    }
+}
+
+type HotkeyCallback = Box<dyn Fn(Hotkey)>;
+
+unsafe extern "system" fn subclass_proc(
+    hwnd: HWND,
+    message: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+    _id: UINT_PTR,
+    data: DWORD_PTR,
+) -> LRESULT {
+    if message == WM_HOTKEY {
+        let on_hotkey = &*(data as *const HotkeyCallback);
+        if let Some(&hotkey) = Hotkey::ALL.get(wparam) {
+            on_hotkey(hotkey);
+        }
+        return 0;
+    }
+    DefSubclassProc(hwnd, message, wparam, lparam)
+}
+
+// Call `on_hotkey` whenever one of the hotkeys is pressed, whatever window has focus.
+// The arrow keys are only taken when there's a camera to `pan`.
+pub fn register(hwnd: HWND, pan: bool, on_hotkey: impl Fn(Hotkey) + 'static) {
+    // The callback lives as long as the app does
+    let on_hotkey: *mut HotkeyCallback = Box::into_raw(Box::new(Box::new(on_hotkey)));
+    unsafe {
+        if SetWindowSubclass(hwnd, Some(subclass_proc), 0, on_hotkey as DWORD_PTR) == 0 {
+            tracing::warn!("Failed to listen for hotkeys");
+            return;
+        }
+        for (id, hotkey) in Hotkey::ALL.iter().enumerate() {
+            if hotkey.is_pan() && !pan {
+                continue;
+            }
+            let modifiers = match hotkey.repeats() {
+                true => MOD_CONTROL | MOD_ALT,
+                false => MOD_CONTROL | MOD_ALT | MOD_NOREPEAT,
+            };
+            if RegisterHotKey(hwnd, id as i32, modifiers as UINT, hotkey.key()) == 0 {
+                // Another app got there first
+                tracing::warn!("Couldn't register the {:?} hotkey", hotkey);
+            }
+        }
+    }
+}
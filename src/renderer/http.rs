@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+
+use crate::renderer::control::{
+    ask, error_response, parse_settings, Command, Reply, Request, Subscribers,
+};
+use crate::renderer::websocket;
+
+// Longest a client gets to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_BODY_BYTES: usize = 64 * 1024;
+// Longest request or header line, and most headers, a client can send
+const MAX_LINE_BYTES: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: String,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+// Read one line into `line`, giving up on lines that run past MAX_LINE_BYTES
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> Result<(), String> {
+    line.clear();
+    reader
+        .take(MAX_LINE_BYTES as u64 + 1)
+        .read_line(line)
+        .map_err(|e| e.to_string())?;
+    if line.len() > MAX_LINE_BYTES {
+        return Err("The line is too long".to_string());
+    }
+    Ok(())
+}
+
+fn read_request(stream: &TcpStream) -> Result<HttpRequest, String> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    read_line(&mut reader, &mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err("The request line is missing".to_string());
+    };
+    let method = method.to_string();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let (path, query) = (path.to_string(), query.to_string());
+
+    let mut headers = HashMap::new();
+    for count in 0.. {
+        read_line(&mut reader, &mut line)?;
+        let Some((name, value)) = line.trim_end().split_once(':') else {
+            break;
+        };
+        if count == MAX_HEADERS {
+            return Err("There are too many headers".to_string());
+        }
+        headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+    }
+
+    let length = match headers.get("content-length") {
+        Some(length) => length
+            .parse()
+            .map_err(|_| "Bad Content-Length".to_string())?,
+        None => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        return Err("The body is too big".to_string());
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+    let body = String::from_utf8(body).map_err(|_| "The body isn't UTF-8".to_string())?;
+
+    Ok(HttpRequest {
+        method,
+        path,
+        query,
+        headers,
+        body,
+    })
+}
+
+fn write_response(mut stream: &TcpStream, status: &str, content_type: &str, body: &[u8]) {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    let _ = stream.write_all(head.as_bytes());
+    let _ = stream.write_all(body);
+}
+
+fn write_error(stream: &TcpStream, status: &str, error: &str) {
+    let json = error_response(error);
+    write_response(stream, status, "application/json", json.as_bytes());
+}
+
+fn write_reply(stream: &TcpStream, reply: Reply) {
+    match reply {
+        Reply::Json(json) => {
+            // Every response that worked starts with "ok": true
+            let status = match json.starts_with("{\"ok\":true") {
+                true => "200 OK",
+                false => "400 Bad Request",
+            };
+            write_response(stream, status, "application/json", json.as_bytes());
+        }
+        Reply::Png(png) => write_response(stream, "200 OK", "image/png", &png),
+    }
+}
+
+// Pages on other sites can send requests to localhost too, so only let changes
+// through from pages served by this computer, or from things that aren't browsers
+fn allowed_origin(request: &HttpRequest) -> bool {
+    let Some(origin) = request.headers.get("origin") else {
+        return true;
+    };
+    let host = origin
+        .strip_prefix("http://")
+        .or(origin.strip_prefix("https://"))
+        .unwrap_or_default();
+    let host = host.rsplit_once(':').map_or(host, |(host, _)| host);
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+}
+
+// A page on another site can point its own domain at 127.0.0.1 and read the API
+// from there, so the Host has to be this computer too
+fn allowed_host(request: &HttpRequest, port: u16) -> bool {
+    let Some(host) = request.headers.get("host") else {
+        return false;
+    };
+    // Browsers leave the port out when it's the default one
+    let host = match port {
+        80 => host.strip_suffix(":80").unwrap_or(host),
+        _ => host.strip_suffix(&format!(":{}", port)).unwrap_or_default(),
+    };
+    host.eq_ignore_ascii_case("localhost") || host == "127.0.0.1"
+}
+
+// A query parameter like the 80 in ?grid=80
+fn parameter<'a>(request: &'a HttpRequest, name: &str) -> Option<&'a str> {
+    request
+        .query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find_map(|(key, value)| (key == name).then_some(value))
+}
+
+fn handle(
+    stream: TcpStream,
+    port: u16,
+    requests: &Sender<Request>,
+    wake: &dyn Fn(),
+    subscribers: &Subscribers,
+) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let request = match read_request(&stream) {
+        Ok(request) => request,
+        Err(e) => return write_error(&stream, "400 Bad Request", &e),
+    };
+    if !allowed_host(&request, port) {
+        let error = format!("Use http://localhost:{}", port);
+        return write_error(&stream, "421 Misdirected Request", &error);
+    }
+    if request.method != "GET" && !allowed_origin(&request) {
+        let error = "Changes can only come from this computer";
+        return write_error(&stream, "403 Forbidden", error);
+    }
+
+    if request.path == "/stream" {
+        // Browsers let any page open a WebSocket to localhost, and the stream shows
+        // what's on the desktop
+        if !allowed_origin(&request) {
+            let error = "The stream can only be read from this computer";
+            return write_error(&stream, "403 Forbidden", error);
+        }
+        let upgrade = request.headers.get("upgrade");
+        let Some(key) = request.headers.get("sec-websocket-key") else {
+            return write_error(&stream, "426 Upgrade Required", "/stream is a WebSocket");
+        };
+        if !upgrade.is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket")) {
+            return write_error(&stream, "426 Upgrade Required", "/stream is a WebSocket");
+        }
+        let grid = match parameter(&request, "grid").map(str::parse) {
+            Some(Ok(0)) | Some(Err(_)) => {
+                return write_error(&stream, "400 Bad Request", "grid needs to be 1 or more");
+            }
+            Some(Ok(grid)) => Some(grid),
+            None => None,
+        };
+        return websocket::accept(stream, key, grid, subscribers);
+    }
+
+    let command = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/stats") => Command::Stats,
+        ("GET", "/config") => Command::Settings,
+        ("GET", "/snapshot") => Command::Snapshot,
+        ("POST", "/pause") => Command::Pause,
+        ("POST", "/resume") => Command::Resume,
+        ("POST", "/reseed") => Command::Reseed,
+        ("POST", "/config") => {
+            // Change each setting in turn, stopping at the first that can't be,
+            // then answer with all of them
+            let commands = match parse_settings(&request.body) {
+                Ok(commands) => commands,
+                Err(e) => return write_error(&stream, "400 Bad Request", &e),
+            };
+            for command in commands {
+                let reply = ask(command, requests, wake);
+                if !matches!(&reply, Reply::Json(json) if json.starts_with("{\"ok\":true")) {
+                    return write_reply(&stream, reply);
+                }
+            }
+            Command::Settings
+        }
+        (_, "/stats" | "/config" | "/snapshot" | "/pause" | "/resume" | "/reseed") => {
+            let error = format!("{} isn't allowed here", request.method);
+            return write_error(&stream, "405 Method Not Allowed", &error);
+        }
+        (_, path) => {
+            let error = format!("There is nothing at {}", path);
+            return write_error(&stream, "404 Not Found", &error);
+        }
+    };
+    write_reply(&stream, ask(command, requests, wake));
+}
+
+// Serve the HTTP API on localhost, each connection on a thread of its own so a slow
+// client doesn't hold up the rest. Like the control pipe, each command is sent down
+// `sender` and `wake` gets the event loop to look at it. WebSocket clients of
+// /stream are added to `subscribers`.
+pub fn serve(
+    port: u16,
+    sender: Sender<Request>,
+    wake: impl Fn() + Clone + Send + 'static,
+    subscribers: Subscribers,
+) {
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::warn!("Couldn't start the HTTP server on port {}: {}", port, e);
+            return;
+        }
+    };
+    tracing::info!("Serving the HTTP API on http://localhost:{}", port);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let (sender, wake) = (sender.clone(), wake.clone());
+            let subscribers = subscribers.clone();
+            thread::spawn(move || handle(stream, port, &sender, &wake, &subscribers));
+        }
+    });
+}
@@ -0,0 +1,128 @@
+use std::mem;
+use std::ptr;
+
+use winapi::shared::minwindef::{FALSE, LPARAM};
+use winapi::shared::windef::{HWND, POINT, RECT};
+use winapi::um::commctrl::{LVIR_BOUNDS, LVM_GETITEMCOUNT, LVM_GETITEMRECT};
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::memoryapi::{ReadProcessMemory, VirtualAllocEx, VirtualFreeEx, WriteProcessMemory};
+use winapi::um::processthreadsapi::OpenProcess;
+use winapi::um::winnt::{
+    MEM_COMMIT, MEM_RELEASE, PAGE_READWRITE, PROCESS_VM_OPERATION, PROCESS_VM_READ,
+    PROCESS_VM_WRITE,
+};
+use winapi::um::winuser::{
+    EnumWindows, FindWindowExW, GetWindowThreadProcessId, IsWindowVisible, MapWindowPoints,
+    SendMessageW,
+};
+
+use crate::renderer::accent::wide;
+use crate::renderer::screen::PixelRect;
+
+unsafe extern "system" fn find_list_view(hwnd: HWND, lparam: LPARAM) -> i32 {
+    let def_view = FindWindowExW(
+        hwnd,
+        ptr::null_mut(),
+        wide("SHELLDLL_DefView").as_ptr(),
+        ptr::null(),
+    );
+    if def_view.is_null() {
+        return 1;
+    }
+    *(lparam as *mut HWND) = FindWindowExW(
+        def_view,
+        ptr::null_mut(),
+        wide("SysListView32").as_ptr(),
+        ptr::null(),
+    );
+    0
+}
+
+// The list view explorer shows the desktop icons in, which lives under Progman or
+// the WorkerW window it was moved to
+fn desktop_list_view() -> Option<HWND> {
+    let mut list_view: HWND = ptr::null_mut();
+    unsafe {
+        EnumWindows(Some(find_list_view), &mut list_view as *mut HWND as LPARAM);
+    }
+    (!list_view.is_null()).then_some(list_view)
+}
+
+// Where the desktop icons and their labels are, in pixels from the top left of
+// `hwnd`. Empty when the icons are hidden or can't be read.
+pub fn icon_rects(hwnd: HWND) -> Vec<PixelRect> {
+    let Some(list_view) = desktop_list_view() else {
+        return Vec::new();
+    };
+    let mut rects = Vec::new();
+    unsafe {
+        if IsWindowVisible(list_view) == 0 {
+            return rects;
+        }
+        let count = SendMessageW(list_view, LVM_GETITEMCOUNT, 0, 0).max(0) as usize;
+
+        // The list view belongs to explorer, so the rect it fills in has to be in
+        // explorer's memory
+        let mut process_id = 0;
+        GetWindowThreadProcessId(list_view, &mut process_id);
+        let process = OpenProcess(
+            PROCESS_VM_OPERATION | PROCESS_VM_READ | PROCESS_VM_WRITE,
+            FALSE,
+            process_id,
+        );
+        if process.is_null() {
+            return rects;
+        }
+        let remote = VirtualAllocEx(
+            process,
+            ptr::null_mut(),
+            mem::size_of::<RECT>(),
+            MEM_COMMIT,
+            PAGE_READWRITE,
+        );
+        if !remote.is_null() {
+            for i in 0..count {
+                // Which part of the item to measure goes in the rect's left edge
+                let mut rect = RECT {
+                    left: LVIR_BOUNDS,
+                    top: 0,
+                    right: 0,
+                    bottom: 0,
+                };
+                let size = mem::size_of::<RECT>();
+                let copied = WriteProcessMemory(
+                    process,
+                    remote,
+                    &rect as *const RECT as *const _,
+                    size,
+                    ptr::null_mut(),
+                ) != 0
+                    && SendMessageW(list_view, LVM_GETITEMRECT, i, remote as LPARAM) != 0
+                    && ReadProcessMemory(
+                        process,
+                        remote,
+                        &mut rect as *mut RECT as *mut _,
+                        size,
+                        ptr::null_mut(),
+                    ) != 0;
+                if !copied {
+                    continue;
+                }
+
+                MapWindowPoints(list_view, hwnd, &mut rect as *mut RECT as *mut POINT, 2);
+                let (left, top) = (rect.left.max(0), rect.top.max(0));
+                if rect.right > left && rect.bottom > top {
+                    rects.push(PixelRect {
+                        x: left as u32,
+                        y: top as u32,
+                        width: (rect.right - left) as u32,
+                        height: (rect.bottom - top) as u32,
+                    });
+                }
+            }
+            VirtualFreeEx(process, remote, 0, MEM_RELEASE);
+        }
+        CloseHandle(process);
+    }
+    rects
+}
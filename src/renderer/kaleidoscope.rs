@@ -0,0 +1,58 @@
+// The kaleidoscope mirrors one wedge of the window around its middle, the way
+// shader.wgsl does. Points are measured in cells from the middle of the window.
+
+// Most mirror images the kaleidoscope makes
+pub const MAX_MIRRORS: u32 = 8;
+
+// How many ways the picture is mirrored for the config's kaleidoscope setting
+pub fn mirrors(setting: u32) -> u32 {
+    match setting {
+        0 | 1 => 1,
+        2 | 4 | 8 => setting,
+        _ => {
+            tracing::warn!("kaleidoscope has to be 2, 4 or 8, not {}", setting);
+            1
+        }
+    }
+}
+
+// The point in the mirrored wedge that shows at `point`: the right half with 2
+// mirrors, the top right quarter with 4 and the part of that below the diagonal
+// with 8
+pub fn fold(point: [f32; 2], mirrors: u32) -> [f32; 2] {
+    let [mut x, mut y] = point;
+    if mirrors >= 2 {
+        x = x.abs();
+    }
+    if mirrors >= 4 {
+        y = y.abs();
+    }
+    if mirrors >= 8 && y > x {
+        (x, y) = (y, x);
+    }
+    [x, y]
+}
+
+// Everywhere a point in the mirrored wedge shows up, or nowhere if it's outside it
+pub fn images(point: [f32; 2], mirrors: u32) -> Vec<[f32; 2]> {
+    if fold(point, mirrors) != point {
+        return Vec::new();
+    }
+    (0..mirrors).map(|image| mirror(point, image)).collect()
+}
+
+// Mirror image number `image` of a point, flipped across, up and along the diagonal
+// by its bits
+fn mirror(point: [f32; 2], image: u32) -> [f32; 2] {
+    let [mut x, mut y] = point;
+    if image & 1 != 0 {
+        x = -x;
+    }
+    if image & 2 != 0 {
+        y = -y;
+    }
+    if image & 4 != 0 {
+        (x, y) = (y, x);
+    }
+    [x, y]
+}
@@ -0,0 +1,48 @@
+use std::cell::{Cell, RefCell};
+use std::ptr;
+
+use winapi::shared::minwindef::{LPARAM, LRESULT, WPARAM};
+use winapi::um::winuser::{
+    CallNextHookEx, SetWindowsHookExW, KBDLLHOOKSTRUCT, WH_KEYBOARD_LL, WM_KEYDOWN, WM_KEYUP,
+    WM_SYSKEYDOWN, WM_SYSKEYUP,
+};
+
+type KeyCallback = Box<dyn Fn()>;
+
+// Like the mouse hook, this is called on the event loop's thread
+thread_local! {
+    static ON_KEY: RefCell<Option<KeyCallback>> = const { RefCell::new(None) };
+    // The key last pressed and not yet let go, so holding it down counts once
+    static HELD: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+unsafe extern "system" fn keyboard_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code >= 0 {
+        // Only whether a key went down is used, never which one
+        let key = (*(lparam as *const KBDLLHOOKSTRUCT)).vkCode;
+        match wparam as u32 {
+            WM_KEYDOWN | WM_SYSKEYDOWN if HELD.get() != Some(key) => {
+                HELD.set(Some(key));
+                ON_KEY.with_borrow(|on_key| {
+                    if let Some(on_key) = on_key {
+                        on_key();
+                    }
+                });
+            }
+            WM_KEYUP | WM_SYSKEYUP if HELD.get() == Some(key) => HELD.set(None),
+            _ => {}
+        }
+    }
+    CallNextHookEx(ptr::null_mut(), code, wparam, lparam)
+}
+
+// Call `on_key` every time a key is pressed, whatever window has focus. Keys are
+// counted, not recorded.
+pub fn watch_typing(on_key: impl Fn() + 'static) {
+    ON_KEY.set(Some(Box::new(on_key)));
+    let hook =
+        unsafe { SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_proc), ptr::null_mut(), 0) };
+    if hook.is_null() {
+        tracing::warn!("Failed to watch the keyboard for typing");
+    }
+}
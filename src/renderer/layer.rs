@@ -0,0 +1,30 @@
+use crate::game::world::Simulation;
+use crate::renderer::scheduler::TickScheduler;
+
+// Another simulation drawn over the top of the screens, covering the whole window
+// and ticking at its own speed
+pub struct Layer {
+    pub simulation: Simulation,
+    pub scheduler: TickScheduler,
+    // How much its cells hide what's underneath, from 0 to 1
+    pub opacity: f32,
+    // Index of its own theme, or None to follow the one picked for the screens
+    pub theme: Option<usize>,
+}
+
+impl Layer {
+    // Run the ticks that are due this frame, returning whether there were any
+    pub fn update(&mut self) -> bool {
+        self.scheduler.start_frame();
+        let mut ticked = false;
+        while self.scheduler.tick() {
+            self.simulation.step();
+            // Layers start over quietly, without the notifications the screens send
+            if self.simulation.cycle.is_some() || self.simulation.is_extinct() {
+                self.simulation.reset_simulation();
+            }
+            ticked = true;
+        }
+        ticked
+    }
+}
@@ -2,7 +2,9 @@ extern crate winapi;
 
 use std::ptr;
 use winapi::shared::windef::HWND;
-use winapi::um::winuser::{EnumWindows, FindWindowW, FindWindowExW, SendMessageTimeoutA};
+use winapi::um::winuser::{
+    EnumWindows, FindWindowExW, FindWindowW, GetParent, IsWindow, SendMessageTimeoutA,
+};
 
 pub fn get_worker_window_handle() -> Result<HWND, ()> {
     unsafe {
@@ -21,32 +23,46 @@ pub fn get_worker_window_handle() -> Result<HWND, ()> {
     }
 }
 
+// Whether the window `hwnd` was put in has gone, which happens when explorer crashes
+// or is restarted
+pub fn parent_lost(hwnd: HWND) -> bool {
+    unsafe {
+        let parent = GetParent(hwnd);
+        parent.is_null() || IsWindow(parent) == 0
+    }
+}
+
 pub fn send_cleanup_message() {
+    let Ok(workerw) = get_worker_window_handle() else {
+        return;
+    };
     unsafe {
-        SendMessageTimeoutA(
-            get_worker_window_handle().unwrap(),
-            0xC107,
-            0,
-            0,
-            0,
-            1000,
-            ptr::null_mut(),
-        );
+        SendMessageTimeoutA(workerw, 0xC107, 0, 0, 0, 1000, ptr::null_mut());
     }
 }
 
 // EnumWindows callback function
 unsafe extern "system" fn enum_windows_proc(hwnd: HWND, lparam: isize) -> i32 {
     let mut workerw: HWND = ptr::null_mut();
-    
+
     // Find SHELLDLL_DefView child window
     let class_name_def_view = to_wstring("SHELLDLL_DefView");
-    let p = FindWindowExW(hwnd, ptr::null_mut(), class_name_def_view.as_ptr(), ptr::null());
+    let p = FindWindowExW(
+        hwnd,
+        ptr::null_mut(),
+        class_name_def_view.as_ptr(),
+        ptr::null(),
+    );
 
     if !p.is_null() {
         // Find next sibling WorkerW window
         let class_name_workerw = to_wstring("WorkerW");
-        workerw = FindWindowExW(ptr::null_mut(), hwnd, class_name_workerw.as_ptr(), ptr::null());
+        workerw = FindWindowExW(
+            ptr::null_mut(),
+            hwnd,
+            class_name_workerw.as_ptr(),
+            ptr::null(),
+        );
         *(lparam as *mut HWND) = workerw;
     }
 
@@ -96,5 +112,8 @@ unsafe fn find_window(class_name: &str) -> HWND {
 // Helper function to convert Rust strings to wide strings
 fn to_wstring(str: &str) -> Vec<u16> {
     use std::os::windows::ffi::OsStrExt;
-    std::ffi::OsStr::new(str).encode_wide().chain(std::iter::once(0)).collect()
+    std::ffi::OsStr::new(str)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect()
 }
@@ -1,2 +1,44 @@
-pub mod window;
-mod layeredwindow;
\ No newline at end of file
+pub mod camera;
+pub mod particles;
+pub mod postprocess;
+pub mod screen;
+pub mod shape;
+
+// The rest draws on and listens to the Windows desktop
+cfg_if::cfg_if! {
+    if #[cfg(windows)] {
+        mod accent;
+        mod adapter;
+        pub mod audio;
+        mod autostart;
+        mod base64;
+        mod clipboard;
+        mod clock;
+        pub mod control;
+        mod coverage;
+        mod fade;
+        mod hotkeys;
+        #[cfg(feature = "http")]
+        mod http;
+        mod icons;
+        mod kaleidoscope;
+        mod keyboard;
+        mod layer;
+        mod layeredwindow;
+        mod mouse;
+        mod network;
+        mod notify;
+        mod presets;
+        mod scheduler;
+        mod screensaver;
+        mod session;
+        mod shaders;
+        mod software;
+        mod throttle;
+        pub mod usage;
+        mod wallpaper;
+        #[cfg(feature = "http")]
+        mod websocket;
+        pub mod window;
+    }
+}
@@ -0,0 +1,71 @@
+use std::cell::{Cell, RefCell};
+use std::ptr;
+
+use winapi::shared::minwindef::{LPARAM, LRESULT, WPARAM};
+use winapi::shared::windef::POINT;
+use winapi::um::winuser::{
+    CallNextHookEx, GetAncestor, GetAsyncKeyState, SetWindowsHookExW, WindowFromPoint, GA_ROOT,
+    MSLLHOOKSTRUCT, VK_MENU, WH_MOUSE_LL, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MOUSEMOVE,
+};
+
+use crate::renderer::coverage::is_desktop_window;
+
+type PaintCallback = Box<dyn Fn(i32, i32)>;
+
+// The hook is called on the thread that set it, which is the one running the event
+// loop
+thread_local! {
+    static ON_PAINT: RefCell<Option<PaintCallback>> = const { RefCell::new(None) };
+    static PAINTING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn alt_held() -> bool {
+    unsafe { GetAsyncKeyState(VK_MENU) < 0 }
+}
+
+// Whether the point is over the desktop rather than some window in front of it
+fn over_desktop(point: POINT) -> bool {
+    unsafe {
+        let hwnd = WindowFromPoint(point);
+        !hwnd.is_null() && is_desktop_window(GetAncestor(hwnd, GA_ROOT))
+    }
+}
+
+unsafe extern "system" fn mouse_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code >= 0 {
+        let point = (*(lparam as *const MSLLHOOKSTRUCT)).pt;
+        let message = wparam as u32;
+        let painting = match message {
+            WM_LBUTTONDOWN => {
+                PAINTING.set(alt_held() && over_desktop(point));
+                PAINTING.get()
+            }
+            WM_MOUSEMOVE => PAINTING.get(),
+            WM_LBUTTONUP => PAINTING.replace(false),
+            _ => false,
+        };
+        if painting {
+            ON_PAINT.with_borrow(|on_paint| {
+                if let Some(on_paint) = on_paint {
+                    on_paint(point.x, point.y);
+                }
+            });
+            // The desktop doesn't get the click, so it doesn't start selecting icons
+            if message != WM_MOUSEMOVE {
+                return 1;
+            }
+        }
+    }
+    CallNextHookEx(ptr::null_mut(), code, wparam, lparam)
+}
+
+// Call `on_paint` with the screen position of the mouse while it's dragged over the
+// desktop with Alt held down. The window lets clicks through to the desktop, so it
+// never sees them itself.
+pub fn watch_painting(on_paint: impl Fn(i32, i32) + 'static) {
+    ON_PAINT.set(Some(Box::new(on_paint)));
+    let hook = unsafe { SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_proc), ptr::null_mut(), 0) };
+    if hook.is_null() {
+        tracing::warn!("Failed to watch the mouse for painting");
+    }
+}
@@ -0,0 +1,68 @@
+use std::ptr;
+use std::time::{Duration, Instant};
+
+use winapi::shared::ipifcons::IF_TYPE_SOFTWARE_LOOPBACK;
+use winapi::shared::netioapi::{FreeMibTable, GetIfTable2, PMIB_IF_TABLE2};
+use winapi::shared::winerror::NO_ERROR;
+
+// How often the traffic is measured
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Bytes received and sent by every network adapter since they started
+fn total_bytes() -> Option<(u64, u64)> {
+    let mut table: PMIB_IF_TABLE2 = ptr::null_mut();
+    unsafe {
+        if GetIfTable2(&mut table) != NO_ERROR || table.is_null() {
+            return None;
+        }
+        let rows =
+            std::slice::from_raw_parts((*table).Table.as_ptr(), (*table).NumEntries as usize);
+        // Filters and virtual adapters count the same traffic again, so only the
+        // physical adapters are added up
+        let (received, sent) = rows
+            .iter()
+            .filter(|row| {
+                row.Type != IF_TYPE_SOFTWARE_LOOPBACK
+                    && row.InterfaceAndOperStatusFlags.HardwareInterface() != 0
+            })
+            .fold((0, 0), |(received, sent), row| {
+                (received + row.InOctets, sent + row.OutOctets)
+            });
+        FreeMibTable(table as *mut _);
+        Some((received, sent))
+    }
+}
+
+// Measures how fast data is coming in and going out over the network
+pub struct NetworkMeter {
+    previous: Option<(u64, u64)>,
+    last_sample: Instant,
+}
+
+impl NetworkMeter {
+    pub fn new() -> Self {
+        Self {
+            previous: total_bytes(),
+            last_sample: Instant::now(),
+        }
+    }
+
+    // Every so often, the bytes per second received and sent since the last time
+    pub fn update(&mut self) -> Option<(f64, f64)> {
+        let seconds = self.last_sample.elapsed();
+        if seconds < SAMPLE_INTERVAL {
+            return None;
+        }
+        self.last_sample = Instant::now();
+
+        let current = total_bytes();
+        let previous = std::mem::replace(&mut self.previous, current);
+        let ((received, sent), (previous_received, previous_sent)) = current.zip(previous)?;
+        // Counters start over when an adapter is reconnected
+        let seconds = seconds.as_secs_f64();
+        Some((
+            received.saturating_sub(previous_received) as f64 / seconds,
+            sent.saturating_sub(previous_sent) as f64 / seconds,
+        ))
+    }
+}
@@ -0,0 +1,84 @@
+use std::time::{Duration, Instant};
+
+use winapi::shared::minwindef::DWORD;
+use winapi::um::winnt::REG_SZ;
+use winapi::um::winreg::{RegSetKeyValueW, HKEY_CURRENT_USER};
+use windows::core::HSTRING;
+use windows::Data::Xml::Dom::XmlDocument;
+use windows::UI::Notifications::{ToastNotification, ToastNotificationManager};
+
+use crate::renderer::accent::wide;
+
+// Windows only shows toasts from apps it knows the name of
+const APP_ID: &str = "AutomataBackground";
+const APP_NAME: &str = "Automata Background";
+// Never show toasts closer together than this, so a busy simulation can't flood
+// the notification center
+const MIN_INTERVAL: Duration = Duration::from_secs(60);
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// Give the app ID a display name, which lets an app without an installer show toasts
+fn register() {
+    let key = wide(&format!("Software\\Classes\\AppUserModelId\\{}", APP_ID));
+    let (value, name) = (wide("DisplayName"), wide(APP_NAME));
+    unsafe {
+        RegSetKeyValueW(
+            HKEY_CURRENT_USER,
+            key.as_ptr(),
+            value.as_ptr(),
+            REG_SZ,
+            name.as_ptr() as *const _,
+            (name.len() * std::mem::size_of::<u16>()) as DWORD,
+        );
+    }
+}
+
+fn show(title: &str, message: &str) -> windows::core::Result<()> {
+    let xml = format!(
+        "<toast><visual><binding template=\"ToastGeneric\">\
+         <text>{}</text><text>{}</text>\
+         </binding></visual></toast>",
+        escape(title),
+        escape(message)
+    );
+    let document = XmlDocument::new()?;
+    document.LoadXml(&HSTRING::from(xml))?;
+    let toast = ToastNotification::CreateToastNotification(&document)?;
+    ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_ID))?.Show(&toast)
+}
+
+// Shows Windows notifications when something interesting happens in the simulation
+pub struct Notifier {
+    pub enabled: bool,
+    last_shown: Option<Instant>,
+}
+
+impl Notifier {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            last_shown: None,
+        }
+    }
+
+    // Show a toast, unless they're turned off or one was shown too recently
+    pub fn notify(&mut self, title: &str, message: &str) {
+        if !self.enabled
+            || self
+                .last_shown
+                .is_some_and(|last| last.elapsed() < MIN_INTERVAL)
+        {
+            return;
+        }
+        self.last_shown = Some(Instant::now());
+        register();
+        if let Err(e) = show(title, message) {
+            tracing::warn!("Failed to show a notification: {}", e);
+        }
+    }
+}
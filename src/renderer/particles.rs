@@ -0,0 +1,105 @@
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+
+// Particles thrown out by each death at an intensity of 1
+const BURST: f32 = 8.0;
+pub const MAX_PARTICLES: usize = 4096;
+// Longest step taken at once, so particles don't jump after a pause
+const MAX_STEP_SECONDS: f32 = 0.1;
+// Fraction of their speed particles lose each second
+const DRAG: f32 = 0.9;
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ParticleConfig {
+    // How many particles each death throws out, 1 for a handful
+    pub intensity: f32,
+    // Fastest a particle starts out, in cells per second
+    pub speed: f32,
+    // Seconds a particle takes to fade away
+    pub lifetime: f32,
+}
+
+impl Default for ParticleConfig {
+    fn default() -> Self {
+        Self {
+            intensity: 1.0,
+            speed: 12.0,
+            lifetime: 0.6,
+        }
+    }
+}
+
+// A spark flying out from where a creature died, measured in cells
+pub struct Particle {
+    pub position: [f32; 2],
+    pub velocity: [f32; 2],
+    pub color: [f32; 3],
+    // Seconds since it was thrown out
+    age: f32,
+}
+
+// Bursts of sparks where creatures die, so it's easier to see them being eaten
+pub struct Particles {
+    pub config: ParticleConfig,
+    pub particles: Vec<Particle>,
+    // Fractions of a particle left over from earlier bursts
+    pending: f32,
+    last_step: Instant,
+    rng: StdRng,
+}
+
+impl Particles {
+    pub fn new(config: ParticleConfig) -> Self {
+        Self {
+            config,
+            particles: Vec::new(),
+            pending: 0.0,
+            last_step: Instant::now(),
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    // Throw particles out in every direction from the middle of the cell at `cell`
+    pub fn burst(&mut self, cell: [f32; 2], color: [f32; 3]) {
+        self.pending += BURST * self.config.intensity.max(0.0);
+        while self.pending >= 1.0 {
+            self.pending -= 1.0;
+            if self.particles.len() >= MAX_PARTICLES {
+                continue;
+            }
+            let angle = self.rng.gen_range(0.0..std::f32::consts::TAU);
+            let speed = self.config.speed * self.rng.gen_range(0.3..1.0);
+            self.particles.push(Particle {
+                position: cell,
+                velocity: [angle.cos() * speed, angle.sin() * speed],
+                color,
+                age: 0.0,
+            });
+        }
+    }
+
+    // How far a particle has faded, from 1 when it's new to 0 when it's gone
+    pub fn fade(&self, particle: &Particle) -> f32 {
+        (1.0 - particle.age / self.config.lifetime.max(0.01)).max(0.0)
+    }
+
+    // Move every particle on by the time since the last step, slowing them down and
+    // dropping the ones that have faded away
+    pub fn step(&mut self) {
+        let seconds = self.last_step.elapsed().as_secs_f32().min(MAX_STEP_SECONDS);
+        self.last_step = Instant::now();
+        let slowdown = (1.0 - DRAG).powf(seconds);
+        for particle in &mut self.particles {
+            particle.position[0] += particle.velocity[0] * seconds;
+            particle.position[1] += particle.velocity[1] * seconds;
+            particle.velocity = particle.velocity.map(|v| v * slowdown);
+            particle.age += seconds;
+        }
+        let lifetime = self.config.lifetime;
+        self.particles.retain(|particle| particle.age < lifetime);
+    }
+}
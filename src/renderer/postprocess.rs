@@ -0,0 +1,307 @@
+use serde::Deserialize;
+use wgpu::util::DeviceExt;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostEffectKind {
+    // Bright cells glow onto the ones around them
+    #[default]
+    Bloom,
+    // Scanlines and darker corners like an old monitor
+    Crt,
+    // Everything softened
+    Blur,
+}
+
+// One step of the post-processing chain. Not every effect uses every setting.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PostEffectConfig {
+    pub effect: PostEffectKind,
+    // How strong the glow, scanlines and vignette are
+    pub strength: f32,
+    // Pixels the blur and glow spread over, or between scanlines
+    pub radius: f32,
+    // How bright a cell has to be to glow, from 0 to 1
+    pub threshold: f32,
+}
+
+impl Default for PostEffectConfig {
+    fn default() -> Self {
+        Self {
+            effect: PostEffectKind::Bloom,
+            strength: 0.8,
+            radius: 8.0,
+            threshold: 0.5,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    direction: [f32; 2],
+    strength: f32,
+    threshold: f32,
+    radius: f32,
+    scale: f32,
+    // Uniforms are padded out to 16 bytes
+    _padding: [f32; 2],
+}
+
+// Shader entry points, in the order their pipelines are kept
+const ENTRY_POINTS: [&str; 4] = ["fs_blur", "fs_bright", "fs_combine", "fs_crt"];
+const BLUR: usize = 0;
+const BRIGHT: usize = 1;
+const COMBINE: usize = 2;
+const CRT: usize = 3;
+
+// Textures a pass reads from and writes to. The first is the scene, the next two
+// take turns holding the result of each effect and the last two are scratch space.
+const SCENE: usize = 0;
+const SCRATCH: [usize; 2] = [3, 4];
+const TEXTURES: usize = 5;
+
+struct Pass {
+    pipeline: usize,
+    bind_group: wgpu::BindGroup,
+    // Texture written to, or None for the final output
+    target: Option<usize>,
+}
+
+// Effects applied to the cells after they are drawn into a texture of their own
+// and before they reach the screen
+pub struct PostProcess {
+    views: Vec<wgpu::TextureView>,
+    pipelines: Vec<wgpu::RenderPipeline>,
+    passes: Vec<Pass>,
+}
+
+impl PostProcess {
+    // A chain drawing `effects` onto a target `size` pixels across, which is `scale`
+    // times the size of the window. None when there are no effects to apply.
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        size: (u32, u32),
+        effects: &[PostEffectConfig],
+        scale: u32,
+    ) -> Option<PostProcess> {
+        if effects.is_empty() {
+            return None;
+        }
+
+        let views = (0..TEXTURES)
+            .map(|_| {
+                device
+                    .create_texture(&wgpu::TextureDescriptor {
+                        label: Some("Post-processing Texture"),
+                        size: wgpu::Extent3d {
+                            width: size.0.max(1),
+                            height: size.1.max(1),
+                            depth_or_array_layers: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: wgpu::TextureDimension::D2,
+                        format,
+                        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                            | wgpu::TextureUsages::TEXTURE_BINDING,
+                        view_formats: &[],
+                    })
+                    .create_view(&wgpu::TextureViewDescriptor::default())
+            })
+            .collect::<Vec<_>>();
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post-processing Bind Group Layout"),
+            entries: &[
+                texture_entry(0),
+                texture_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post-processing Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("postprocess.wgsl").into()),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post-processing Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipelines = ENTRY_POINTS
+            .iter()
+            .map(|entry_point| {
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("Post-processing Pipeline"),
+                    layout: Some(&layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: "vs_main",
+                        buffers: &[],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point,
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format,
+                            blend: Some(wgpu::BlendState::REPLACE),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview: None,
+                })
+            })
+            .collect();
+
+        // Each pass gets its own settings, since they are all drawn in one go
+        let pass = |pipeline, input: usize, original: usize, target, params: Params| {
+            let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Post-processing Parameters"),
+                contents: bytemuck::cast_slice(&[params]),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Post-processing Bind Group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&views[input]),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&views[original]),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: buffer.as_entire_binding(),
+                    },
+                ],
+            });
+            Pass {
+                pipeline,
+                bind_group,
+                target,
+            }
+        };
+
+        let mut passes = Vec::new();
+        let mut input = SCENE;
+        for (index, effect) in effects.iter().enumerate() {
+            // The last effect draws straight onto the output
+            let output = match index + 1 == effects.len() {
+                true => None,
+                false => Some(if input == 1 { 2 } else { 1 }),
+            };
+            let params = Params {
+                direction: [0.0; 2],
+                strength: effect.strength,
+                threshold: effect.threshold,
+                radius: effect.radius,
+                scale: scale as f32,
+                _padding: [0.0; 2],
+            };
+            let across = Params {
+                direction: [1.0, 0.0],
+                ..params
+            };
+            let up = Params {
+                direction: [0.0, 1.0],
+                ..params
+            };
+            let [first, second] = SCRATCH;
+            match effect.effect {
+                PostEffectKind::Blur => {
+                    passes.push(pass(BLUR, input, input, Some(first), across));
+                    passes.push(pass(BLUR, first, first, output, up));
+                }
+                PostEffectKind::Bloom => {
+                    passes.push(pass(BRIGHT, input, input, Some(first), params));
+                    passes.push(pass(BLUR, first, first, Some(second), across));
+                    passes.push(pass(BLUR, second, second, Some(first), up));
+                    passes.push(pass(COMBINE, first, input, output, params));
+                }
+                PostEffectKind::Crt => passes.push(pass(CRT, input, input, output, params)),
+            }
+            input = output.unwrap_or(SCENE);
+        }
+
+        Some(PostProcess {
+            views,
+            pipelines,
+            passes,
+        })
+    }
+
+    // Where the cells should be drawn for the effects to be applied to them
+    pub fn scene(&self) -> &wgpu::TextureView {
+        &self.views[SCENE]
+    }
+
+    // Record applying every effect to the scene, finishing on `output`
+    pub fn apply(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        for pass in &self.passes {
+            let view = match pass.target {
+                Some(target) => &self.views[target],
+                None => output,
+            };
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Post-processing Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            render_pass.set_pipeline(&self.pipelines[pass.pipeline]);
+            render_pass.set_bind_group(0, &pass.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+struct Params {
+    // Which way a blur pass samples, (1, 0) across or (0, 1) up
+    direction: vec2<f32>,
+    strength: f32,
+    threshold: f32,
+    // In pixels of the window
+    radius: f32,
+    // How many times bigger than the window the target is, for photos
+    scale: f32,
+    _padding: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var input_texture: texture_2d<f32>;
+// The untouched picture for passes that add their result back onto it
+@group(0) @binding(1)
+var original_texture: texture_2d<f32>;
+@group(0) @binding(2)
+var input_sampler: sampler;
+@group(0) @binding(3)
+var<uniform> params: Params;
+
+// One triangle big enough to cover the whole target
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    // Textures start at the top, clip space at the bottom
+    out.uv = vec2<f32>(corner.x, 1.0 - corner.y);
+    return out;
+}
+
+// Half of a gaussian blur, along params.direction. Wide blurs spread the same
+// number of samples further apart.
+@fragment
+fn fs_blur(in: VertexOutput) -> @location(0) vec4<f32> {
+    let radius = max(params.radius * params.scale, 1.0);
+    let step = max(radius / 16.0, 1.0);
+    let sigma = radius / 2.0;
+    let texel = params.direction / vec2<f32>(textureDimensions(input_texture));
+    var total = vec4<f32>(0.0);
+    var weights = 0.0;
+    for (var i = -16; i <= 16; i++) {
+        let offset = f32(i) * step;
+        if abs(offset) > radius {
+            continue;
+        }
+        let weight = exp(-offset * offset / (2.0 * sigma * sigma));
+        total += textureSampleLevel(input_texture, input_sampler, in.uv + texel * offset, 0.0)
+            * weight;
+        weights += weight;
+    }
+    return total / weights;
+}
+
+// Only the cells brighter than the threshold, which are the ones that glow
+@fragment
+fn fs_bright(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(input_texture, input_sampler, in.uv, 0.0);
+    let luminance = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+    return color * smoothstep(params.threshold, params.threshold + 0.1, luminance);
+}
+
+// The blurred glow added back over the picture it came from
+@fragment
+fn fs_combine(in: VertexOutput) -> @location(0) vec4<f32> {
+    let original = textureSampleLevel(original_texture, input_sampler, in.uv, 0.0);
+    let glow = textureSampleLevel(input_texture, input_sampler, in.uv, 0.0) * params.strength;
+    return vec4<f32>(original.rgb + glow.rgb, clamp(max(original.a, glow.a), 0.0, 1.0));
+}
+
+// Dark lines every params.radius pixels and corners that fade out, like an old
+// monitor
+@fragment
+fn fs_crt(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(input_texture, input_sampler, in.uv, 0.0);
+    let spacing = max(params.radius * params.scale, 2.0);
+    let line = fract(in.clip_position.y / spacing);
+    let scanline = 1.0 - params.strength * (0.5 - 0.5 * cos(line * 6.2831853));
+    let edge = in.uv - 0.5;
+    let vignette = 1.0 - params.strength * dot(edge, edge);
+    return color * scanline * vignette;
+}
@@ -0,0 +1,98 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::game::automaton::AutomatonKind;
+use crate::renderer::base64;
+
+// Shared presets are this followed by the preset's TOML in URL-safe base64
+pub const URL_PREFIX: &str = "automata://preset/";
+
+// Everything that makes a simulation look the way it does, saved under a name to
+// switch back to from the tray menu
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Preset {
+    pub name: String,
+    pub automaton: AutomatonKind,
+    // B/S rulestring for life, the one from the config file when left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    pub theme: String,
+    // Percentage of ticks_per_second
+    pub speed: u32,
+    pub cell_size: u32,
+}
+
+impl Preset {
+    // Whether the two switch to the same thing, whatever they're called
+    pub fn same_settings(&self, other: &Preset) -> bool {
+        let renamed = Preset {
+            name: other.name.clone(),
+            ..self.clone()
+        };
+        renamed == *other
+    }
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct PresetFile {
+    #[serde(default)]
+    presets: Vec<Preset>,
+}
+
+// Presets are kept in a file of their own next to the config file
+pub fn path() -> PathBuf {
+    Config::directory().join("presets.toml")
+}
+
+pub fn load() -> Vec<Preset> {
+    let path = path();
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    match toml::from_str::<PresetFile>(&contents) {
+        Ok(file) => file.presets,
+        Err(e) => {
+            tracing::warn!("Failed to parse {}: {}", path.display(), e);
+            Vec::new()
+        }
+    }
+}
+
+pub fn save(presets: &[Preset]) -> Result<(), String> {
+    let file = PresetFile {
+        presets: presets.to_vec(),
+    };
+    let contents = toml::to_string(&file).map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(Config::directory()).map_err(|e| e.to_string())?;
+    std::fs::write(path(), contents).map_err(|e| e.to_string())
+}
+
+// The first "Preset 1", "Preset 2" and so on that isn't taken yet
+pub fn unused_name(presets: &[Preset]) -> String {
+    (1..)
+        .map(|number| format!("Preset {}", number))
+        .find(|name| presets.iter().all(|preset| &preset.name != name))
+        .unwrap_or_default()
+}
+
+// A string for sharing the preset in chat, which `decode` turns back into it
+pub fn encode(preset: &Preset) -> Result<String, String> {
+    let contents = toml::to_string(preset).map_err(|e| e.to_string())?;
+    let encoded = base64::encode(contents.as_bytes(), &base64::URL_SAFE);
+    Ok(format!("{}{}", URL_PREFIX, encoded))
+}
+
+// The preset in a string from `encode`, which also works without the automata://
+// part in front. Plain base64's + and / are taken too, in case the string went
+// through something that swapped them.
+pub fn decode(text: &str) -> Result<Preset, String> {
+    let text = text.trim();
+    let encoded = text.strip_prefix(URL_PREFIX).unwrap_or(text);
+    let encoded = encoded.replace('+', "-").replace('/', "_");
+    let contents = base64::decode(&encoded, &base64::URL_SAFE)
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(|| "That isn't a preset string".to_string())?;
+    toml::from_str(&contents).map_err(|e| e.to_string())
+}
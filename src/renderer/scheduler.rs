@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+// Never run more than this many ticks in one frame, so a slow frame can't snowball
+const MAX_TICKS_PER_FRAME: u32 = 8;
+// How long turbo mode keeps ticking each frame
+const TURBO_FRAME_BUDGET: Duration = Duration::from_millis(12);
+
+// Decides how many simulation ticks to run each frame, independent of the frame rate.
+// Speeds slower than the frame rate only tick every few frames.
+pub struct TickScheduler {
+    pub ticks_per_second: f64,
+    // Tick as many times as fit in each frame instead of at a fixed speed
+    pub turbo: bool,
+    // Multiple of ticks_per_second picked from the tray menu
+    pub multiplier: f64,
+    // Fraction of the normal speed to run at, lowered while the system is busy
+    pub speed: f64,
+    // Time that hasn't been used up by a tick yet
+    pending: Duration,
+    frame_start: Instant,
+    ticks_this_frame: u32,
+}
+
+impl TickScheduler {
+    pub fn new(ticks_per_second: f64, turbo: bool) -> Self {
+        Self {
+            ticks_per_second,
+            turbo,
+            multiplier: 1.0,
+            speed: 1.0,
+            pending: Duration::ZERO,
+            frame_start: Instant::now(),
+            ticks_this_frame: 0,
+        }
+    }
+
+    pub fn start_frame(&mut self) {
+        let now = Instant::now();
+        self.pending += now - self.frame_start;
+        self.frame_start = now;
+        self.ticks_this_frame = 0;
+    }
+
+    // Whether another tick should run this frame
+    pub fn tick(&mut self) -> bool {
+        if self.turbo {
+            return self.frame_start.elapsed() < TURBO_FRAME_BUDGET.mul_f64(self.speed);
+        }
+
+        // Drop whatever is left over after a long stall instead of catching up later
+        if self.ticks_this_frame == MAX_TICKS_PER_FRAME {
+            self.pending = Duration::ZERO;
+            return false;
+        }
+
+        let ticks_per_second = self.ticks_per_second * self.multiplier * self.speed;
+        let tick_length = Duration::from_secs_f64(1.0 / ticks_per_second.max(0.01));
+        if self.pending < tick_length {
+            return false;
+        }
+        self.pending -= tick_length;
+        self.ticks_this_frame += 1;
+        true
+    }
+}
@@ -0,0 +1,180 @@
+use serde::Deserialize;
+use winit::monitor::MonitorHandle;
+
+use crate::game::obstacles::ObstacleMap;
+use crate::game::world::Simulation;
+
+// Pixels of the window covered by each square of the gap map
+const GAP_MAP_SCALE: u32 = 8;
+
+// Where the monitors are taken to be when working out what each one shows
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MonitorLayout {
+    // Side by side from left to right, lined up along the top
+    #[default]
+    SideBySide,
+    // Where Windows has them, including any that are higher or lower than the rest
+    Actual,
+}
+
+// A rectangle of the window in pixels, measured from its top left corner
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PixelRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PixelRect {
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+// Monitors as the window covers them, from left to right
+pub fn monitor_rects(
+    monitors: impl Iterator<Item = MonitorHandle>,
+    layout: MonitorLayout,
+) -> Vec<PixelRect> {
+    let mut monitors = monitors.collect::<Vec<_>>();
+    monitors.sort_by_key(|monitor| monitor.position().x);
+
+    if layout == MonitorLayout::Actual {
+        // The window starts at the top left of the desktop, which can be above or to
+        // the left of the primary monitor
+        let left = monitors.iter().map(|monitor| monitor.position().x).min();
+        let top = monitors.iter().map(|monitor| monitor.position().y).min();
+        return monitors
+            .iter()
+            .map(|monitor| {
+                let (position, size) = (monitor.position(), monitor.size());
+                PixelRect {
+                    x: (position.x - left.unwrap_or(0)) as u32,
+                    y: (position.y - top.unwrap_or(0)) as u32,
+                    width: size.width,
+                    height: size.height,
+                }
+            })
+            .collect();
+    }
+
+    let mut x = 0;
+    monitors
+        .iter()
+        .map(|monitor| {
+            let size = monitor.size();
+            let rect = PixelRect {
+                x,
+                y: 0,
+                width: size.width,
+                height: size.height,
+            };
+            x += size.width;
+            rect
+        })
+        .collect()
+}
+
+// The size of a window covering every monitor
+pub fn desktop_size(monitors: &[PixelRect]) -> (u32, u32) {
+    let width = monitors
+        .iter()
+        .map(|monitor| monitor.x + monitor.width)
+        .max();
+    let height = monitors
+        .iter()
+        .map(|monitor| monitor.y + monitor.height)
+        .max();
+    (width.unwrap_or(0), height.unwrap_or(0))
+}
+
+// Walls over the parts of a desktop `size` pixels across that none of the monitors
+// show, so cells can only cross between monitors where their edges meet. Returns
+// None when the monitors cover all of it.
+pub fn gap_map(monitors: &[PixelRect], size: (u32, u32)) -> Option<ObstacleMap> {
+    let (width, height) = size;
+    let (map_width, map_height) = (width / GAP_MAP_SCALE, height / GAP_MAP_SCALE);
+    let mut walls = vec![true; (map_width * map_height) as usize];
+    for monitor in monitors {
+        let (left, top) = (monitor.x / GAP_MAP_SCALE, monitor.y / GAP_MAP_SCALE);
+        let right = ((monitor.x + monitor.width) / GAP_MAP_SCALE).min(map_width);
+        let bottom = ((monitor.y + monitor.height) / GAP_MAP_SCALE).min(map_height);
+        for y in top..bottom {
+            for x in left..right {
+                walls[(y * map_width + x) as usize] = false;
+            }
+        }
+    }
+
+    if !walls.contains(&true) {
+        return None;
+    }
+    Some(ObstacleMap {
+        width: map_width as usize,
+        height: map_height as usize,
+        walls,
+    })
+}
+
+// Walls over the desktop icons that fall inside `area` of the window, at the same
+// scale as the gaps. Returns None when there aren't any.
+pub fn icon_map(icons: &[PixelRect], area: PixelRect) -> Option<ObstacleMap> {
+    let (map_width, map_height) = (area.width / GAP_MAP_SCALE, area.height / GAP_MAP_SCALE);
+    let mut walls = vec![false; (map_width * map_height) as usize];
+    for icon in icons {
+        // Rounded outwards so the walls cover all of the icon
+        let left = icon.x.saturating_sub(area.x) / GAP_MAP_SCALE;
+        let top = icon.y.saturating_sub(area.y) / GAP_MAP_SCALE;
+        let right = (icon.x + icon.width)
+            .saturating_sub(area.x)
+            .div_ceil(GAP_MAP_SCALE)
+            .min(map_width);
+        let bottom = (icon.y + icon.height)
+            .saturating_sub(area.y)
+            .div_ceil(GAP_MAP_SCALE)
+            .min(map_height);
+        for y in top..bottom {
+            for x in left..right {
+                walls[(y * map_width + x) as usize] = true;
+            }
+        }
+    }
+
+    if !walls.contains(&true) {
+        return None;
+    }
+    Some(ObstacleMap {
+        width: map_width as usize,
+        height: map_height as usize,
+        walls,
+    })
+}
+
+// A simulation and the part of the window it's drawn in
+pub struct Screen {
+    pub simulation: Simulation,
+    pub rect: PixelRect,
+}
+
+impl Screen {
+    // The world size that fills the screen with cells `cell_size` pixels across, or is
+    // `scale` times wider and taller than that for the camera to move across
+    pub fn world_size(rect: PixelRect, cell_size: u32, scale: u32) -> (usize, usize) {
+        (
+            (rect.width / cell_size * scale) as usize,
+            (rect.height / cell_size * scale) as usize,
+        )
+    }
+
+    // Where the world's bottom left cell sits in the window's grid of cells, which
+    // starts at the bottom left of a window `window_height` pixels tall
+    pub fn offset(&self, window_height: u32, cell_size: u32) -> (usize, usize) {
+        let bottom = window_height.saturating_sub(self.rect.y + self.rect.height);
+        (
+            (self.rect.x / cell_size) as usize,
+            (bottom / cell_size) as usize,
+        )
+    }
+}
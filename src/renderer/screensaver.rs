@@ -0,0 +1,73 @@
+use std::mem;
+
+use winapi::shared::windef::{HWND, RECT};
+use winapi::um::winuser::{
+    GetClientRect, IsWindow, MoveWindow, SetParent, SetWindowLongPtrW, GWL_STYLE, WS_CHILD,
+    WS_VISIBLE,
+};
+use winit::event::{ElementState, WindowEvent};
+
+use crate::config::Config;
+
+// How far the mouse has to move, in pixels, to end the screensaver. Any less is
+// the desk being bumped.
+const MOVE_THRESHOLD: f64 = 10.0;
+
+// The settings button in the screensaver dialog opens the config file, since the
+// screensaver runs with the same settings as the background
+pub fn configure() {
+    let path = Config::path();
+    if let Err(e) = std::process::Command::new("notepad").arg(&path).spawn() {
+        tracing::warn!("Failed to open {}: {}", path.display(), e);
+    }
+}
+
+// Size of the inside of the screensaver settings' preview window
+pub fn preview_size(parent: HWND) -> Option<(u32, u32)> {
+    let mut rect: RECT = unsafe { mem::zeroed() };
+    if unsafe { IsWindow(parent) == 0 || GetClientRect(parent, &mut rect) == 0 } {
+        return None;
+    }
+    Some((
+        (rect.right - rect.left) as u32,
+        (rect.bottom - rect.top) as u32,
+    ))
+}
+
+// Put the window inside the preview, filling it. The window is closed along with
+// the preview.
+pub fn embed(hwnd: HWND, parent: HWND) -> bool {
+    let Some((width, height)) = preview_size(parent) else {
+        return false;
+    };
+    unsafe {
+        SetWindowLongPtrW(hwnd, GWL_STYLE, (WS_CHILD | WS_VISIBLE) as isize);
+        !SetParent(hwnd, parent).is_null()
+            && MoveWindow(hwnd, 0, 0, width as i32, height as i32, 1) != 0
+    }
+}
+
+// Decides when the screensaver is over: a key, a click or the mouse moving
+#[derive(Default)]
+pub struct Dismissal {
+    // Where the mouse was when the screensaver started
+    cursor_start: Option<(f64, f64)>,
+}
+
+impl Dismissal {
+    pub fn dismissed(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => event.state == ElementState::Pressed,
+            WindowEvent::MouseInput { .. } | WindowEvent::MouseWheel { .. } => true,
+            // The first position is where the mouse already was
+            WindowEvent::CursorMoved { position, .. } => match self.cursor_start {
+                Some((x, y)) => (position.x - x).hypot(position.y - y) > MOVE_THRESHOLD,
+                None => {
+                    self.cursor_start = Some((position.x, position.y));
+                    false
+                }
+            },
+            _ => false,
+        }
+    }
+}
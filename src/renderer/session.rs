@@ -0,0 +1,63 @@
+use winapi::shared::basetsd::{DWORD_PTR, UINT_PTR};
+use winapi::shared::minwindef::{BOOL, DWORD, LPARAM, LRESULT, TRUE, UINT, WPARAM};
+use winapi::shared::windef::HWND;
+use winapi::um::commctrl::{DefSubclassProc, SetWindowSubclass};
+use winapi::um::winuser::{
+    SystemParametersInfoW, SPI_GETSCREENSAVERRUNNING, WM_WTSSESSION_CHANGE, WTS_SESSION_LOCK,
+    WTS_SESSION_UNLOCK,
+};
+
+// winapi doesn't have these
+#[link(name = "wtsapi32")]
+extern "system" {
+    fn WTSRegisterSessionNotification(hwnd: HWND, flags: DWORD) -> BOOL;
+}
+const NOTIFY_FOR_THIS_SESSION: DWORD = 0;
+
+type LockCallback = Box<dyn Fn(bool)>;
+
+unsafe extern "system" fn subclass_proc(
+    hwnd: HWND,
+    message: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+    _id: UINT_PTR,
+    data: DWORD_PTR,
+) -> LRESULT {
+    if message == WM_WTSSESSION_CHANGE {
+        let on_lock = &*(data as *const LockCallback);
+        match wparam {
+            WTS_SESSION_LOCK => on_lock(true),
+            WTS_SESSION_UNLOCK => on_lock(false),
+            _ => {}
+        }
+    }
+    DefSubclassProc(hwnd, message, wparam, lparam)
+}
+
+// Call `on_lock` with true when the workstation is locked and false when it's
+// unlocked again, for as long as the window is around
+pub fn watch_lock(hwnd: HWND, on_lock: impl Fn(bool) + 'static) {
+    // The callback lives as long as the app does
+    let on_lock: *mut LockCallback = Box::into_raw(Box::new(Box::new(on_lock)));
+    unsafe {
+        if SetWindowSubclass(hwnd, Some(subclass_proc), 0, on_lock as DWORD_PTR) == 0
+            || WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION) == 0
+        {
+            tracing::warn!("Failed to watch for the workstation being locked");
+        }
+    }
+}
+
+pub fn screensaver_running() -> bool {
+    let mut running: BOOL = 0;
+    unsafe {
+        SystemParametersInfoW(
+            SPI_GETSCREENSAVERRUNNING,
+            0,
+            &mut running as *mut BOOL as *mut _,
+            0,
+        );
+    }
+    running == TRUE
+}
@@ -1,61 +1,242 @@
 struct SimulationParametersUniform {
     width: u32,
     height: u32,
+    cell_shape: u32,
+    // Ways the kaleidoscope mirrors the picture, 1 for not at all
+    mirrors: u32,
+    // Cells across and up the window, fewer than the grid has when the camera is on
+    view: vec2<f32>,
+    // Cell of the grid at the bottom left of the window
+    camera: vec2<f32>,
+    // How many times bigger than `view` fits the cells are drawn, around the middle
+    // of the window
+    zoom: f32,
+    // Fraction of each cell's square left empty along every side, for a grid
+    inset: f32,
+    // How opaque everything is drawn, from 0 to 1
+    opacity: f32,
 };
 
 @group(0) @binding(0)
 var<uniform> simulation_parameters: SimulationParametersUniform;
+@group(0) @binding(1)
+var sprite_texture: texture_2d<f32>;
+@group(0) @binding(2)
+var sprite_sampler: sampler;
+
+@group(1) @binding(0)
+var grid_texture: texture_2d<f32>;
 
 // Vertex shader
 struct VertexInput {
     @location(0) position: vec3<f32>,
 };
 
-struct InstanceInput {
-    @location(1) position: vec2<u32>,
-    @location(2) color: vec3<f32>,
-};
-
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec3<f32>,
+    // Position inside the cell, from 0 to 1 across, or across the whole grid for
+    // the cells
+    @location(1) local: vec2<f32>,
+    // Colors are premultiplied by this, so fading cells let the wallpaper through
+    @location(2) alpha: f32,
 };
 
+// The point in the kaleidoscope's wedge that shows at `point`, both measured in
+// cells from the middle of the window
+fn fold(point: vec2<f32>) -> vec2<f32> {
+    var folded = point;
+    if simulation_parameters.mirrors >= 2u {
+        folded.x = abs(folded.x);
+    }
+    if simulation_parameters.mirrors >= 4u {
+        folded.y = abs(folded.y);
+    }
+    if simulation_parameters.mirrors >= 8u && folded.y > folded.x {
+        folded = folded.yx;
+    }
+    return folded;
+}
+
+// Mirror image number `image` of a point, flipped across, up and along the diagonal
+// by its bits
+fn mirror(point: vec2<f32>, image: u32) -> vec2<f32> {
+    var mirrored = point;
+    if (image & 1u) != 0u {
+        mirrored.x = -mirrored.x;
+    }
+    if (image & 2u) != 0u {
+        mirrored.y = -mirrored.y;
+    }
+    if (image & 4u) != 0u {
+        mirrored = mirrored.yx;
+    }
+    return mirrored;
+}
+
+// One triangle big enough to cover the whole grid, which the fragment shader
+// looks up each cell of
 @vertex
-fn vs_main(
-    model: VertexInput,
-    instance: InstanceInput,
-) -> VertexOutput {
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
     var out: VertexOutput;
-    out.color = instance.color;
+    out.clip_position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    out.color = vec3<f32>(0.0, 0.0, 0.0);
+    out.local = corner;
+    out.alpha = 1.0;
+    return out;
+}
+
+// Agent vertex shader
+struct AgentInput {
+    @location(1) position: vec2<f32>,
+    @location(2) heading: f32,
+    @location(3) color: vec3<f32>,
+};
 
-    let paddingx = 2.0 / f32(simulation_parameters.width);
-    let paddingy = 2.0 / f32(simulation_parameters.height);
+// Where a vertex `offset` cells from something at `position` in the grid goes on
+// screen, in the kaleidoscope's mirror image number `image`
+fn place(position: vec2<f32>, offset: vec2<f32>, image: u32) -> vec4<f32> {
+    let cell_width = 2.0 * simulation_parameters.zoom / simulation_parameters.view.x;
+    let cell_height = 2.0 * simulation_parameters.zoom / simulation_parameters.view.y;
 
-    // Assuming viewport width and height are known and represent the size of your rendering area
-    let cell_width = (2.0 - paddingx) / f32(simulation_parameters.width);
-    let cell_height = (2.0 - paddingy) / f32(simulation_parameters.height);
+    // Things behind the camera come round again from the far side of the grid
+    let size = vec2<f32>(f32(simulation_parameters.width), f32(simulation_parameters.height));
+    let from_camera = position - simulation_parameters.camera;
+    let wrapped = select(from_camera, from_camera + size, from_camera < vec2<f32>(0.0, 0.0));
 
-    let screen_x = (f32(instance.position.x) * cell_width) + (model.position.x * cell_width) -1.0 + (cell_width / 2.0);
-    let screen_y = (f32(instance.position.y) * cell_height) + (model.position.y * cell_height) -1.0 + (cell_height / 2.0);
+    // Things outside the mirrored wedge aren't drawn at all, like the cells there
+    let center = simulation_parameters.view / 2.0;
+    let from_center = wrapped + 0.5 - center;
+    if image >= max(simulation_parameters.mirrors, 1u) || any(fold(from_center) != from_center) {
+        return vec4<f32>(2.0, 2.0, 0.0, 1.0);
+    }
+    let cell = mirror(from_center + offset, image);
 
+    return vec4<f32>(cell.x * cell_width, cell.y * cell_height, 0.0, 1.0);
+}
 
-    // Create the clip space position with z = 0.0 and w = 1.0
-    out.clip_position = vec4<f32>(
-        screen_x,
-        screen_y,
-        0.0,
-        1.0
+@vertex
+fn vs_agent(
+    model: VertexInput,
+    agent: AgentInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = agent.color;
+    out.alpha = 1.0;
+    out.local = vec2<f32>(0.5, 0.5);
+
+    // Rotate the arrow to face the direction the agent is moving
+    let c = cos(agent.heading);
+    let s = sin(agent.heading);
+    let offset = vec2<f32>(
+        model.position.x * c - model.position.y * s,
+        model.position.x * s + model.position.y * c,
     );
+    // Each arrow is drawn once for every mirror image the kaleidoscope makes, the
+    // image being the vertex's z
+    out.clip_position = place(agent.position, offset, u32(model.position.z));
 
     return out;
 }
 
+// Particle vertex shader
+struct ParticleInput {
+    @location(1) position: vec2<f32>,
+    @location(2) velocity: vec2<f32>,
+    @location(3) color: vec3<f32>,
+    // From 1 when the particle is new down to 0 when it's gone
+    @location(4) fade: f32,
+};
+
+@vertex
+fn vs_particle(
+    model: VertexInput,
+    particle: ParticleInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = particle.color * particle.fade;
+    out.alpha = particle.fade;
+    out.local = vec2<f32>(0.5, 0.5);
+
+    // Stretch the spark out along the way it's flying, more the faster it goes
+    let speed = length(particle.velocity);
+    let heading = select(vec2<f32>(1.0, 0.0), particle.velocity / speed, speed > 0.0);
+    let stretched = vec2<f32>(model.position.x * (1.0 + speed * 0.05), model.position.y);
+    let offset = vec2<f32>(
+        stretched.x * heading.x - stretched.y * heading.y,
+        stretched.x * heading.y + stretched.y * heading.x,
+    );
+    out.clip_position = place(particle.position, offset, u32(model.position.z));
 
+    return out;
+}
 
 // Fragment shader
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color, 1.0);
+    // Colors are premultiplied, so dimming takes down all of them
+    return vec4<f32>(in.color, in.alpha) * simulation_parameters.opacity;
+}
+
+// Each cell's color comes from its pixel of the grid texture, already premultiplied
+// by how faded it is. Cells cut out their shape from the square they are drawn on.
+@fragment
+fn fs_cell(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(f32(simulation_parameters.width), f32(simulation_parameters.height));
+    // The window shows the part of the grid the camera is over, which wraps around,
+    // zoomed in around the middle and mirrored by the kaleidoscope
+    let view = simulation_parameters.view;
+    let center = view / 2.0;
+    let from_center = (in.local * view - center) / simulation_parameters.zoom;
+    let folded = fold(from_center) + center;
+    let position = folded + simulation_parameters.camera;
+    let wrapped = position - floor(position / size) * size;
+    let cell = textureLoad(grid_texture, vec2<i32>(floor(wrapped)), 0);
+    // Additive cells have no alpha but still have color to add
+    if all(cell == vec4<f32>(0.0, 0.0, 0.0, 0.0)) {
+        discard;
+    }
+    // Cells are drawn in what's left of their square inside the gap around them
+    let inset = simulation_parameters.inset;
+    let local = (fract(position) - inset) / (1.0 - 2.0 * inset);
+    if any(local < vec2<f32>(0.0, 0.0)) || any(local > vec2<f32>(1.0, 1.0)) {
+        discard;
+    }
+    // The sprite's top row is at the top of the cell
+    let texel = textureSampleLevel(sprite_texture, sprite_sampler, vec2<f32>(local.x, 1.0 - local.y), 0.0);
+    let p = local * 2.0 - 1.0;
+
+    switch simulation_parameters.cell_shape {
+        // Rounded square
+        case 1u: {
+            let q = abs(p) - vec2<f32>(0.5, 0.5);
+            if length(max(q, vec2<f32>(0.0, 0.0))) > 0.5 {
+                discard;
+            }
+        }
+        // Circle
+        case 2u: {
+            if length(p) > 1.0 {
+                discard;
+            }
+        }
+        // Hexagon with flat top and bottom edges
+        case 3u: {
+            let q = abs(p);
+            if max(q.x * 0.866025 + q.y * 0.5, q.y) > 0.866025 {
+                discard;
+            }
+        }
+        // Sprite, tinted with the cell's color
+        case 4u: {
+            if texel.a < 0.5 {
+                discard;
+            }
+            return vec4<f32>(cell.rgb * texel.rgb, cell.a) * simulation_parameters.opacity;
+        }
+        default: {}
+    }
+    return cell * simulation_parameters.opacity;
 }
\ No newline at end of file
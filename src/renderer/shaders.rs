@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::config::Config;
+
+// The shader the cells are drawn with unless the config picks another
+pub const BUILT_IN: &str = include_str!("shader.wgsl");
+
+// Custom shaders live in a folder next to the config file
+pub fn folder() -> PathBuf {
+    Config::directory().join("shaders")
+}
+
+// Where the shader called `name` is, with or without its .wgsl extension
+pub fn path(name: &str) -> PathBuf {
+    let path = folder().join(name);
+    match path.extension() {
+        Some(_) => path,
+        None => path.with_extension("wgsl"),
+    }
+}
+
+pub fn load(name: &str) -> Result<String, String> {
+    let path = path(name);
+    std::fs::read_to_string(&path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))
+}
+
+// When the shader called `name` was last changed, if it exists
+pub fn modified(name: &str) -> Option<SystemTime> {
+    std::fs::metadata(path(name)).ok()?.modified().ok()
+}
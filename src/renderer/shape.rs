@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+// Furthest in from each side of its square a cell can be drawn, so there's always
+// some of it left
+const MAX_INSET: f32 = 0.4;
+
+// How each cell is drawn inside its square of the grid
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellShape {
+    #[default]
+    Square,
+    Rounded,
+    Circle,
+    Hexagon,
+    // An image from `cell_sprite`, tinted with the cell's color
+    Sprite,
+}
+
+impl CellShape {
+    // The number the shader knows this shape by
+    pub fn id(self) -> u32 {
+        match self {
+            CellShape::Square => 0,
+            CellShape::Rounded => 1,
+            CellShape::Circle => 2,
+            CellShape::Hexagon => 3,
+            CellShape::Sprite => 4,
+        }
+    }
+}
+
+// How far in from each side of its square a cell is drawn, as a fraction of the
+// square, to leave `gap` pixels between cells `cell_size` pixels across
+pub fn inset(gap: u32, cell_size: u32) -> f32 {
+    (gap as f32 / 2.0 / cell_size.max(1) as f32).min(MAX_INSET)
+}
+
+pub fn load_sprite(path: &Path) -> Result<image::RgbaImage, String> {
+    Ok(image::open(path).map_err(|e| e.to_string())?.to_rgba8())
+}
@@ -0,0 +1,99 @@
+use winapi::shared::windef::{HWND, RECT};
+use winapi::um::wingdi::{
+    GetStockObject, StretchDIBits, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, BLACK_BRUSH,
+    DIB_RGB_COLORS, SRCCOPY,
+};
+use winapi::um::winuser::{FillRect, GetDC, ReleaseDC};
+
+use crate::renderer::screen::PixelRect;
+
+// Draws the cells with GDI when there's no graphics adapter to use, such as over
+// remote desktop. Each cell is one pixel of a bitmap that gets stretched over the
+// window, so every cell is a plain square.
+pub struct Canvas {
+    hwnd: HWND,
+    width: usize,
+    height: usize,
+    // One 0RGB pixel per cell, with the bottom row first like the GPU grid
+    pixels: Vec<u32>,
+}
+
+impl Canvas {
+    pub fn new(hwnd: HWND) -> Canvas {
+        Canvas {
+            hwnd,
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+        }
+    }
+
+    // Start a new frame of `width` by `height` cells, all empty
+    pub fn clear(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.pixels.clear();
+        self.pixels.resize(width * height, 0);
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: [f32; 3]) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let [r, g, b] = color.map(|c| (c.clamp(0.0, 1.0) * 255.0) as u32);
+        self.pixels[y * self.width + x] = r << 16 | g << 8 | b;
+    }
+
+    // Stretch the cells over the window, lined up with its bottom left corner, and
+    // black out the monitors that are left alone
+    pub fn present(&self, cell_size: u32, window_height: u32, hidden: &[PixelRect]) {
+        if self.pixels.is_empty() {
+            return;
+        }
+        let mut info: BITMAPINFO = unsafe { std::mem::zeroed() };
+        info.bmiHeader = BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: self.width as i32,
+            // A positive height makes the first row the bottom one
+            biHeight: self.height as i32,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB,
+            ..unsafe { std::mem::zeroed() }
+        };
+        let width = (self.width as u32 * cell_size) as i32;
+        let height = (self.height as u32 * cell_size) as i32;
+
+        unsafe {
+            let dc = GetDC(self.hwnd);
+            if dc.is_null() {
+                return;
+            }
+            StretchDIBits(
+                dc,
+                0,
+                window_height as i32 - height,
+                width,
+                height,
+                0,
+                0,
+                self.width as i32,
+                self.height as i32,
+                self.pixels.as_ptr() as *const _,
+                &info,
+                DIB_RGB_COLORS,
+                SRCCOPY,
+            );
+            for monitor in hidden {
+                let rect = RECT {
+                    left: monitor.x as i32,
+                    top: monitor.y as i32,
+                    right: (monitor.x + monitor.width) as i32,
+                    bottom: (monitor.y + monitor.height) as i32,
+                };
+                FillRect(dc, &rect, GetStockObject(BLACK_BRUSH as i32) as _);
+            }
+            ReleaseDC(self.hwnd, dc);
+        }
+    }
+}
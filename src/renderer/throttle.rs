@@ -0,0 +1,85 @@
+use std::time::{Duration, Instant};
+
+use winapi::shared::minwindef::FILETIME;
+use winapi::um::processthreadsapi::GetSystemTimes;
+
+// How often the CPU load is checked
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+// The slowest the simulation is allowed to get while the system is busy
+const MIN_SPEED: f64 = 0.125;
+// How far under the busy level the load has to drop before speeding up again, so
+// the speed doesn't flip back and forth around it
+const IDLE_MARGIN: f64 = 20.0;
+
+fn ticks(time: FILETIME) -> u64 {
+    (time.dwHighDateTime as u64) << 32 | time.dwLowDateTime as u64
+}
+
+// Idle and total CPU time across every core since the system started
+pub fn system_times() -> Option<(u64, u64)> {
+    let (mut idle, mut kernel, mut user) = unsafe { std::mem::zeroed() };
+    if unsafe { GetSystemTimes(&mut idle, &mut kernel, &mut user) } == 0 {
+        return None;
+    }
+    // Kernel time already includes the idle time
+    Some((ticks(idle), ticks(kernel) + ticks(user)))
+}
+
+// Percentage of the CPU used between two readings of system_times
+pub fn cpu_percent(previous: (u64, u64), current: (u64, u64)) -> Option<f64> {
+    let total = current.1.saturating_sub(previous.1);
+    if total == 0 {
+        return None;
+    }
+    let idle = current.0.saturating_sub(previous.0);
+    Some(100.0 * (1.0 - idle as f64 / total as f64))
+}
+
+// Slows the simulation down while the rest of the system is busy and brings it back
+// up to full speed once things are quiet again
+pub struct Throttle {
+    // CPU usage in percent above which the system counts as busy
+    pub busy_percent: f64,
+    // Fraction of the normal tick rate to run at
+    speed: f64,
+    previous: Option<(u64, u64)>,
+    last_sample: Instant,
+}
+
+impl Throttle {
+    pub fn new(busy_percent: f64) -> Self {
+        Self {
+            busy_percent,
+            speed: 1.0,
+            previous: system_times(),
+            last_sample: Instant::now(),
+        }
+    }
+
+    // Check the CPU load every so often, halving the speed while it's busy and
+    // doubling it again while it's idle
+    pub fn update(&mut self) -> f64 {
+        if self.last_sample.elapsed() < SAMPLE_INTERVAL {
+            return self.speed;
+        }
+        self.last_sample = Instant::now();
+
+        let current = system_times();
+        if let Some(load) = current
+            .zip(self.previous)
+            .and_then(|(current, previous)| cpu_percent(previous, current))
+        {
+            if load > self.busy_percent {
+                self.speed = (self.speed / 2.0).max(MIN_SPEED);
+            } else if load < self.busy_percent - IDLE_MARGIN {
+                self.speed = (self.speed * 2.0).min(1.0);
+            }
+        }
+        self.previous = current;
+        self.speed
+    }
+
+    pub fn is_throttled(&self) -> bool {
+        self.speed < 1.0
+    }
+}
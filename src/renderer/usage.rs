@@ -0,0 +1,68 @@
+use std::time::{Duration, Instant};
+
+use winapi::um::sysinfoapi::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+
+use crate::config::UsageConfig;
+use crate::renderer::throttle::{cpu_percent, system_times};
+
+// How often the CPU and memory are checked
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Percentage of physical memory in use
+fn memory_percent() -> Option<f64> {
+    let mut status: MEMORYSTATUSEX = unsafe { std::mem::zeroed() };
+    status.dwLength = std::mem::size_of::<MEMORYSTATUSEX>() as u32;
+    if unsafe { GlobalMemoryStatusEx(&mut status) } == 0 || status.ullTotalPhys == 0 {
+        return None;
+    }
+    Some(100.0 * (1.0 - status.ullAvailPhys as f64 / status.ullTotalPhys as f64))
+}
+
+// Turns the CPU and memory use into creatures, so the background shows how hard the
+// computer is working
+pub struct UsageMonitor {
+    pub config: UsageConfig,
+    // Last readings, shown in the tray tooltip
+    pub cpu_percent: Option<f64>,
+    pub memory_percent: Option<f64>,
+    previous_times: Option<(u64, u64)>,
+    last_sample: Instant,
+}
+
+impl UsageMonitor {
+    pub fn new(config: UsageConfig) -> Self {
+        Self {
+            config,
+            cpu_percent: None,
+            memory_percent: memory_percent(),
+            previous_times: system_times(),
+            last_sample: Instant::now(),
+        }
+    }
+
+    // Check the CPU and memory every so often, returning how many predators and prey
+    // to drop in
+    pub fn update(&mut self) -> Option<(usize, usize)> {
+        if self.last_sample.elapsed() < SAMPLE_INTERVAL {
+            return None;
+        }
+        self.last_sample = Instant::now();
+
+        let times = system_times();
+        self.cpu_percent = times
+            .zip(self.previous_times)
+            .and_then(|(current, previous)| cpu_percent(previous, current));
+        self.previous_times = times;
+        let predators = self.cpu_percent.unwrap_or(0.0) / 100.0 * self.config.predators as f64;
+
+        let memory = memory_percent();
+        let freed = match (self.memory_percent, memory) {
+            (Some(before), Some(now)) => (before - now).max(0.0),
+            _ => 0.0,
+        };
+        self.memory_percent = memory;
+        let prey = freed * self.config.prey as f64;
+
+        Some((predators.round() as usize, prey.round() as usize))
+    }
+}
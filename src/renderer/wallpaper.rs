@@ -0,0 +1,70 @@
+use std::ffi::OsString;
+use std::os::windows::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+
+use image::imageops::FilterType;
+use image::RgbaImage;
+use winapi::shared::minwindef::MAX_PATH;
+use winapi::um::winuser::{SystemParametersInfoW, SPI_GETDESKWALLPAPER};
+
+use crate::game::palette::Theme;
+
+// Wallpapers are shrunk to this size before their colors are picked out
+const SAMPLE_SIZE: u32 = 64;
+
+// The image file set as the desktop wallpaper, if there is one
+fn wallpaper_path() -> Option<PathBuf> {
+    let mut buffer = [0u16; MAX_PATH];
+    let found = unsafe {
+        SystemParametersInfoW(
+            SPI_GETDESKWALLPAPER,
+            MAX_PATH as u32,
+            buffer.as_mut_ptr() as *mut _,
+            0,
+        )
+    };
+    let length = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    if found == 0 || length == 0 {
+        return None;
+    }
+    Some(PathBuf::from(OsString::from_wide(&buffer[..length])))
+}
+
+fn load_theme(path: &Path) -> Result<Theme, String> {
+    let image = image::open(path).map_err(|e| e.to_string())?;
+    let pixels = image
+        .thumbnail(SAMPLE_SIZE, SAMPLE_SIZE)
+        .to_rgb8()
+        .pixels()
+        .map(|pixel| pixel.0.map(|c| c as f32 / 255.0))
+        .collect::<Vec<_>>();
+    Theme::from_colors("wallpaper", &pixels).ok_or_else(|| "it has no colors".to_string())
+}
+
+// A theme made from the main colors of the desktop wallpaper
+pub fn wallpaper_theme() -> Option<Theme> {
+    let path = wallpaper_path()?;
+    match load_theme(&path) {
+        Ok(theme) => Some(theme),
+        Err(e) => {
+            tracing::warn!("Failed to read colors from {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+// The desktop wallpaper, shrunk to fit in `max_size` each way if it's any bigger
+pub fn wallpaper_image(max_size: u32) -> Option<RgbaImage> {
+    let path = wallpaper_path()?;
+    let mut image = match image::open(&path) {
+        Ok(image) => image,
+        Err(e) => {
+            tracing::warn!("Failed to load the wallpaper {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    if image.width() > max_size || image.height() > max_size {
+        image = image.resize(max_size, max_size, FilterType::Triangle);
+    }
+    Some(image.to_rgba8())
+}
@@ -0,0 +1,43 @@
+// Part of the wallpaper shown on one monitor, as an offset and size in texture
+// coordinates
+struct CropInput {
+    @location(0) crop: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var wallpaper_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var wallpaper_sampler: sampler;
+
+// Two triangles covering the whole viewport, which is set to one monitor at a time
+@vertex
+fn vs_main(
+    @builtin(vertex_index) index: u32,
+    input: CropInput,
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[index];
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    // Images start at the top, clip space at the bottom
+    out.uv = input.crop.xy + vec2<f32>(corner.x, 1.0 - corner.y) * input.crop.zw;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(textureSample(wallpaper_texture, wallpaper_sampler, in.uv).rgb, 1.0);
+}
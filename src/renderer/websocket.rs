@@ -0,0 +1,142 @@
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use sha1_smol::Sha1;
+
+use crate::renderer::base64;
+use crate::renderer::control::{Subscriber, Subscribers};
+
+// Added to the client's key before hashing, as the WebSocket handshake asks
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+// Messages waiting for a slow client before new ones are dropped
+const QUEUE_LENGTH: usize = 8;
+// Clients only send control frames, so anything bigger than this is a mistake
+const MAX_FRAME_BYTES: u64 = 64 * 1024;
+
+const TEXT: u8 = 0x1;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xA;
+
+// A message as a single unmasked frame, the way servers send them
+fn frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        length if length < 126 => frame.push(length as u8),
+        length if length <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+// The opcode and unmasked payload of the next frame the client sends
+fn read_frame(stream: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+    let mut head = [0; 2];
+    stream.read_exact(&mut head)?;
+    let length = match head[1] & 0x7F {
+        126 => {
+            let mut length = [0; 2];
+            stream.read_exact(&mut length)?;
+            u16::from_be_bytes(length) as u64
+        }
+        127 => {
+            let mut length = [0; 8];
+            stream.read_exact(&mut length)?;
+            u64::from_be_bytes(length)
+        }
+        length => length as u64,
+    };
+    if length > MAX_FRAME_BYTES {
+        let error = "The frame is too big";
+        return Err(io::Error::new(io::ErrorKind::InvalidData, error));
+    }
+    let mut mask = [0; 4];
+    if head[1] & 0x80 != 0 {
+        stream.read_exact(&mut mask)?;
+    }
+    let mut payload = vec![0; length as usize];
+    stream.read_exact(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok((head[0] & 0x0F, payload))
+}
+
+// Answer the client's pings and close the connection when it asks to, until it
+// goes away
+fn read_frames(mut reader: TcpStream, writer: &Mutex<TcpStream>) {
+    loop {
+        let reply = match read_frame(&mut reader) {
+            Ok((PING, payload)) => frame(PONG, &payload),
+            // The close frame is echoed back before hanging up
+            Ok((CLOSE, payload)) => {
+                let mut writer = writer.lock().unwrap();
+                let _ = writer.write_all(&frame(CLOSE, &payload));
+                let _ = writer.shutdown(Shutdown::Both);
+                return;
+            }
+            Ok(_) => continue,
+            Err(_) => {
+                let _ = reader.shutdown(Shutdown::Both);
+                return;
+            }
+        };
+        if writer.lock().unwrap().write_all(&reply).is_err() {
+            return;
+        }
+    }
+}
+
+// Finish the handshake for a client that asked to upgrade to a WebSocket, then send
+// it every message the event loop streams until it goes away
+pub fn accept(mut stream: TcpStream, key: &str, grid: Option<usize>, subscribers: &Subscribers) {
+    let digest = Sha1::from(format!("{}{}", key, HANDSHAKE_GUID))
+        .digest()
+        .bytes();
+    let accept = base64::encode(&digest, &base64::STANDARD);
+    let handshake = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        accept
+    );
+    if stream.write_all(handshake.as_bytes()).is_err() {
+        return;
+    }
+    let _ = stream.set_read_timeout(None);
+    let _ = stream.set_nodelay(true);
+    let Ok(reader) = stream.try_clone() else {
+        return;
+    };
+    // Frames from both threads go out whole, one after the other
+    let writer = Arc::new(Mutex::new(stream));
+
+    let (sender, receiver) = mpsc::sync_channel::<String>(QUEUE_LENGTH);
+    subscribers
+        .lock()
+        .unwrap()
+        .push(Subscriber { grid, sender });
+    let replies = writer.clone();
+    thread::spawn(move || read_frames(reader, &replies));
+    // Dropping the receiver when the client goes away takes it off the list
+    thread::spawn(move || {
+        for message in receiver {
+            if writer
+                .lock()
+                .unwrap()
+                .write_all(&frame(TEXT, message.as_bytes()))
+                .is_err()
+            {
+                return;
+            }
+        }
+    });
+}
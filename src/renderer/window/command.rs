@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::sync::mpsc::TrySendError;
+
+use crate::game::automaton::AutomatonKind;
+use crate::game::life::Rule;
+use crate::renderer::control::{self, json_string, ok_response, Command, Reply};
+
+use super::State;
+
+impl<'a> State<'a> {
+    // Carry out the commands that came in over the control pipe or HTTP
+    pub(super) fn handle_requests(&mut self) {
+        let Some(requests) = &self.requests else {
+            return;
+        };
+        let requests = requests.try_iter().collect::<Vec<_>>();
+        for request in requests {
+            match self.handle_command(&request.command) {
+                Ok(Reply::Json(response)) => request.respond(response),
+                Ok(Reply::Png(png)) => request.respond_png(png),
+                Err(e) => request.respond(control::error_response(&e)),
+            }
+        }
+    }
+
+    fn handle_command(&mut self, command: &Command) -> Result<Reply, String> {
+        match command {
+            Command::Pause | Command::Resume => {
+                if self.is_paused() != (*command == Command::Pause) {
+                    self.toggle_pause();
+                }
+            }
+            Command::Reseed => self.reset(),
+            Command::SetRule { rule } => {
+                Rule::parse(rule)?;
+                self.rule = Some(rule.clone());
+                self.set_automaton(AutomatonKind::Life);
+            }
+            Command::SetSpeed { percent } => self.set_speed(*percent),
+            Command::SetAutomaton { automaton } => self.set_automaton(*automaton),
+            Command::SetCellSize { size } => {
+                // Any bigger and a screen would be left without a single cell
+                let smallest = self
+                    .screens
+                    .iter()
+                    .map(|screen| screen.rect.width.min(screen.rect.height))
+                    .min()
+                    .unwrap_or(0);
+                if *size > smallest {
+                    return Err(format!("The cell size can be {} pixels at most", smallest));
+                }
+                self.set_cell_size(*size);
+            }
+            Command::Stats => return Ok(Reply::Json(self.stats())),
+            Command::Settings => return Ok(Reply::Json(self.settings())),
+            Command::Snapshot => return self.snapshot().map(Reply::Png),
+        }
+        Ok(Reply::Json(ok_response(&[])))
+    }
+
+    fn settings(&self) -> String {
+        let rule = self.rule.as_deref().map_or("null".to_string(), json_string);
+        let theme = self.themes.get(self.theme).map_or("", |theme| &theme.name);
+        ok_response(&[
+            ("automaton", json_string(&self.automaton.key())),
+            ("rule", rule),
+            ("speed", self.speed.to_string()),
+            ("cell_size", self.cell_size.to_string()),
+            ("theme", json_string(theme)),
+            ("paused", self.is_paused().to_string()),
+        ])
+    }
+
+    // The current frame as a PNG
+    fn snapshot(&self) -> Result<Vec<u8>, String> {
+        let image = self.capture(1)?;
+        let mut png = Vec::new();
+        image
+            .write_to(
+                &mut std::io::Cursor::new(&mut png),
+                image::ImageOutputFormat::Png,
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(png)
+    }
+
+    // What's running and how many of each kind of cell there are on every screen
+    fn stats(&self) -> String {
+        self.stats_with_grid(None)
+    }
+
+    // The stats, with each screen's cells shrunk down to `grid` columns if given
+    fn stats_with_grid(&self, grid: Option<usize>) -> String {
+        let screens = self
+            .screens
+            .iter()
+            .map(|screen| {
+                let simulation = &screen.simulation;
+                let populations = simulation
+                    .statistics
+                    .latest()
+                    .map(|sample| {
+                        sample
+                            .populations
+                            .iter()
+                            .map(|(cell_type, count)| {
+                                format!("{}:{}", json_string(&format!("{:?}", cell_type)), count)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                let grid = match grid {
+                    Some(width) => {
+                        let rows = simulation.world().type_rows(width);
+                        let rows = rows.iter().map(|row| json_string(row)).collect::<Vec<_>>();
+                        format!(",\"grid\":[{}]", rows.join(","))
+                    }
+                    None => String::new(),
+                };
+                format!(
+                    "{{\"tick\":{},\"populations\":{{{}}}{}}}",
+                    simulation.ticks(),
+                    populations.join(","),
+                    grid
+                )
+            })
+            .collect::<Vec<_>>();
+        ok_response(&[
+            ("automaton", json_string(self.automaton.name())),
+            ("paused", self.is_paused().to_string()),
+            ("speed", self.speed.to_string()),
+            ("screens", format!("[{}]", screens.join(","))),
+        ])
+    }
+
+    // Send the stats to everything watching the WebSocket stream, forgetting the
+    // ones that went away
+    pub(super) fn stream_stats(&self) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        let mut messages = HashMap::new();
+        subscribers.retain(|subscriber| {
+            let message = messages
+                .entry(subscriber.grid)
+                .or_insert_with(|| self.stats_with_grid(subscriber.grid));
+            // Clients that can't keep up miss a tick instead of falling behind
+            !matches!(
+                subscriber.sender.try_send(message.clone()),
+                Err(TrySendError::Disconnected(_))
+            )
+        });
+    }
+}
@@ -0,0 +1,3873 @@
+mod command;
+mod pattern;
+mod preset;
+mod tray;
+
+use std::any::Any;
+use std::iter;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::config::Config;
+use crate::game::automaton::{Automaton, AutomatonKind};
+use crate::game::boids::Boids;
+use crate::game::brush::Brush;
+use crate::game::heatmap::Heatmap;
+use crate::game::history::RewindBuffer;
+use crate::game::obstacles::{ObstacleMap, WALL_COLOR};
+use crate::game::packets::Packets;
+use crate::game::palette::{Blend, BlendMode, DayNightConfig, Theme};
+use crate::game::recording::{Recorder, Replay};
+use crate::game::text::TextSeed;
+use crate::game::world::*;
+use crate::logfile;
+use crate::renderer::accent::{self, wide};
+use crate::renderer::adapter;
+use crate::renderer::audio::AudioListener;
+use crate::renderer::autostart;
+use crate::renderer::camera::Camera;
+use crate::renderer::clock;
+use crate::renderer::control::{self, Request, Subscribers};
+use crate::renderer::coverage;
+use crate::renderer::fade::Fade;
+use crate::renderer::hotkeys::{self, Hotkey};
+#[cfg(feature = "http")]
+use crate::renderer::http;
+use crate::renderer::icons;
+use crate::renderer::kaleidoscope;
+use crate::renderer::keyboard;
+use crate::renderer::layer::Layer;
+use crate::renderer::layeredwindow;
+use crate::renderer::mouse;
+use crate::renderer::network::NetworkMeter;
+use crate::renderer::notify::Notifier;
+use crate::renderer::particles::{self, Particles};
+use crate::renderer::postprocess::{PostEffectConfig, PostProcess};
+use crate::renderer::presets::{self, Preset};
+use crate::renderer::scheduler::TickScheduler;
+use crate::renderer::screen::{desktop_size, gap_map, icon_map, monitor_rects, PixelRect, Screen};
+use crate::renderer::screensaver;
+use crate::renderer::session;
+use crate::renderer::shaders;
+use crate::renderer::shape::{self, load_sprite, CellShape};
+use crate::renderer::software::Canvas;
+use crate::renderer::throttle::Throttle;
+use crate::renderer::usage::UsageMonitor;
+use crate::renderer::wallpaper;
+use trayicon::{TrayIcon, TrayIconBuilder};
+use wgpu::{
+    rwh::{HasWindowHandle, RawWindowHandle},
+    util::DeviceExt,
+};
+use winapi::shared::windef::{HWND, POINT};
+use winapi::um::winuser::{
+    ClientToScreen, GetCursorPos, MessageBoxW, ScreenToClient, SetParent, MB_ICONERROR,
+    MB_ICONWARNING, MB_OK,
+};
+use winit::dpi::PhysicalSize;
+use winit::{
+    dpi::PhysicalPosition,
+    event_loop::{ControlFlow, EventLoopBuilder},
+};
+use winit::{
+    event::*,
+    keyboard::{KeyCode, PhysicalKey},
+    window::{Window, WindowBuilder},
+};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use self::tray::{themes, tray_menu, MenuChecks, CELL_SIZES, OPACITIES};
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum UserEvents {
+    RightClickTrayIcon,
+    LeftClickTrayIcon,
+    DoubleClickTrayIcon,
+    Exit,
+    TogglePause,
+    Reset,
+    Step,
+    Rewind,
+    ExportPattern,
+    Screenshot,
+    // Save a picture at this many times the screen's resolution
+    Photo(u32),
+    SaveWorld,
+    LoadWorld,
+    // Switch every simulation over to this automaton
+    Automaton(AutomatonKind),
+    // Switch to the automaton after the current one in the tray menu
+    NextAutomaton,
+    // Switch to the theme at this index of Theme::all
+    Theme(usize),
+    // Rebuild the grid with cells this many pixels across
+    CellSize(u32),
+    // Run at this percentage of ticks_per_second, or pause at 0
+    Speed(u32),
+    // Draw everything at this percentage of fully opaque
+    Opacity(u32),
+    // Show or hide the monitor at this index, counting from the left
+    Monitor(usize),
+    // Open the log file in whatever program handles .log files
+    OpenLog,
+    // Start the app when the user logs in, or stop doing so
+    ToggleAutostart,
+    // The workstation was locked (true) or unlocked (false)
+    SessionLocked(bool),
+    // Paint with the brush picked in the tray menu
+    Brush(Brush),
+    // The mouse was dragged to this point on the screen while painting
+    Paint(i32, i32),
+    // A key was pressed somewhere
+    Keystroke,
+    // Commands came in over the control pipe
+    Control,
+    // Move the camera this many steps to the right and up
+    Pan(i32, i32),
+    // Zoom in this many steps, or out for less than 0
+    Zoom(i32),
+    // Start or stop the camera drifting on its own
+    ToggleDrift,
+    // Switch to the preset at this index of the presets file
+    Preset(usize),
+    // Save what's running now as a new preset
+    SavePreset,
+    // Copy what's running now to the clipboard as a preset string to share
+    CopyPreset,
+    // Add the preset string on the clipboard to the presets and switch to it
+    ImportPreset,
+    // Stamp the pattern at this index of the pattern library into the world
+    Pattern(usize),
+    // Switch between stamping patterns in the middle of the world and anywhere
+    ToggleCentered,
+}
+
+// How the app shows itself, picked from the command line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    // Behind the desktop icons
+    Background,
+    // Over every monitor as the Windows screensaver, until a key or the mouse is touched
+    Screensaver,
+    // Inside the preview in the screensaver settings, whose window handle is given
+    Preview(isize),
+    // The screensaver settings button was pressed
+    Configure,
+    // In a normal window, for working on the app or showing it off
+    Windowed,
+}
+
+impl Mode {
+    // --windowed runs in a window. Windows runs screensavers with /s to start,
+    // /p <hwnd> to preview and /c (or nothing at all) to change the settings.
+    pub fn from_args(args: &[String]) -> Mode {
+        let Some(flag) = args.first() else {
+            let exe = std::env::current_exe().unwrap_or_default();
+            return match exe
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("scr"))
+            {
+                true => Mode::Configure,
+                false => Mode::Background,
+            };
+        };
+        // The window handle can also come after a colon, as in /p:1234
+        let flag = flag.to_lowercase();
+        let (flag, handle) = match flag.split_once(':') {
+            Some((flag, handle)) => (flag, Some(handle)),
+            None => (flag.as_str(), args.get(1).map(String::as_str)),
+        };
+        let handle = handle.and_then(|handle| handle.trim().parse().ok());
+        match (flag.trim_start_matches(['/', '-']), handle) {
+            ("windowed", _) => Mode::Windowed,
+            ("s", _) => Mode::Screensaver,
+            ("p", Some(handle)) => Mode::Preview(handle),
+            ("c", _) => Mode::Configure,
+            _ => Mode::Background,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+}
+
+// Size of the window in pixels when running in one of its own
+const WINDOWED_SIZE: (u32, u32) = (1280, 720);
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+// How often to look for the custom shader being saved, short so changes to it show
+// up straight away
+const SHADER_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+// Population records only count once a run has had this many ticks to settle down
+const RECORD_WARMUP_TICKS: u32 = 1000;
+// How often to look for the screensaver finishing or the desktop being uncovered
+// while nothing is being drawn
+const SLEEP_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// Give up on the graphics device if it has to be restarted this many times in a
+// short while, rather than flickering forever
+const MAX_RESTARTS: usize = 3;
+const RESTART_WINDOW: Duration = Duration::from_secs(60);
+// How often to make sure the desktop window the background sits in is still there
+const DESKTOP_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+// How often to look for desktop icons that have been moved
+const ICON_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+// How far the camera moves each time it's panned, as a fraction of the window
+const PAN_STEP: f32 = 0.1;
+// How much each press of a zoom hotkey zooms in or out, and the furthest in it goes
+const ZOOM_STEP: f32 = 1.25;
+const MAX_ZOOM: f32 = 16.0;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct AgentInstance {
+    position: [f32; 2],
+    heading: f32,
+    color: [f32; 3],
+}
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ParticleInstance {
+    position: [f32; 2],
+    velocity: [f32; 2],
+    color: [f32; 3],
+    // From 1 when the particle is new down to 0 when it's gone
+    fade: f32,
+}
+// Agents the agent buffer starts out with room for. It doubles whenever there are
+// more and halves again once they'd fit in a quarter of it.
+const MIN_AGENT_CAPACITY: usize = 1024;
+// More separate changes to the grid than this are uploaded as one region covering
+// them all, rather than copying each on its own
+const MAX_GRID_REGIONS: usize = 32;
+// Cells painted around the mouse in every direction
+const BRUSH_RADIUS: usize = 2;
+// Monitors the wallpaper is drawn on, any more show the cells over black
+const MAX_WALLPAPER_MONITORS: usize = 16;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct SimulationParametersUniform {
+    width: u32,
+    height: u32,
+    // CellShape::id of the shape cells are drawn as
+    cell_shape: u32,
+    // Ways the kaleidoscope mirrors the picture, 1 for not at all
+    mirrors: u32,
+    // Cells that fit across and up the window, which is fewer than the grid has when
+    // the camera is on
+    view: [f32; 2],
+    // Cell of the grid at the bottom left of the window
+    camera: [f32; 2],
+    // How many times bigger than `view` fits the cells are drawn, around the middle of
+    // the window
+    zoom: f32,
+    // Fraction of each cell's square left empty along every side, for a grid between
+    // the cells
+    inset: f32,
+    // How opaque everything is drawn, from 0 to 1
+    opacity: f32,
+    // Uniforms are padded out to 16 bytes
+    _padding: f32,
+}
+
+impl AgentInstance {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<AgentInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+            ],
+        }
+    }
+}
+
+impl ParticleInstance {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<ParticleInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 7]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32,
+                },
+            ],
+        }
+    }
+}
+
+impl Vertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[wgpu::VertexAttribute {
+                offset: 0,
+                shader_location: 0,
+                format: wgpu::VertexFormat::Float32x3,
+            }],
+        }
+    }
+}
+
+const AGENT_VERTICES: &[Vertex] = &[
+    // An arrow head pointing along +x, measured in cells
+    Vertex {
+        position: [1.5, 0.0, 0.0],
+    },
+    Vertex {
+        position: [-1.0, 0.8, 0.0],
+    },
+    Vertex {
+        position: [-1.0, -0.8, 0.0],
+    },
+];
+
+const PARTICLE_VERTICES: &[Vertex] = &[
+    // A thin diamond along +x, measured in cells, which the shader stretches out the
+    // faster the particle is going
+    Vertex {
+        position: [0.4, 0.0, 0.0],
+    },
+    Vertex {
+        position: [0.0, 0.15, 0.0],
+    },
+    Vertex {
+        position: [-0.4, 0.0, 0.0],
+    },
+    Vertex {
+        position: [0.4, 0.0, 0.0],
+    },
+    Vertex {
+        position: [-0.4, 0.0, 0.0],
+    },
+    Vertex {
+        position: [0.0, -0.15, 0.0],
+    },
+];
+
+// `vertices` once for each of the kaleidoscope's mirror images, numbered in z
+fn mirrored(vertices: &[Vertex]) -> Vec<Vertex> {
+    (0..kaleidoscope::MAX_MIRRORS)
+        .flat_map(|image| {
+            vertices.iter().map(move |vertex| Vertex {
+                position: [vertex.position[0], vertex.position[1], image as f32],
+            })
+        })
+        .collect()
+}
+
+// Zooming out as far as the whole world when it's `world_scale` times the size of
+// the window, and no further
+fn clamp_zoom(zoom: f32, world_scale: u32) -> f32 {
+    let zoom = if zoom.is_finite() { zoom } else { 1.0 };
+    zoom.clamp(1.0 / world_scale.max(1) as f32, MAX_ZOOM)
+}
+
+// A cell's pixel in the grid, with its color premultiplied by `alpha` so cells
+// that are fading out or see-through let what's under them show
+fn cell_pixel(color: [f32; 3], alpha: f32) -> [u8; 4] {
+    let alpha = alpha.clamp(0.0, 1.0);
+    let [r, g, b] = color.map(|c| ((c * alpha).clamp(0.0, 1.0) * 255.0).round() as u8);
+    [r, g, b, (alpha * 255.0).round() as u8]
+}
+
+// A cell's pixel drawn with `blend`, faded to `fade` of full strength. Additive
+// cells leave the alpha at 0, so their color is added to what's under them
+// instead of covering it.
+fn blended_pixel(color: [f32; 3], blend: Blend, fade: f32) -> [u8; 4] {
+    let pixel = cell_pixel(color, blend.alpha * fade);
+    match blend.mode {
+        BlendMode::Normal => pixel,
+        BlendMode::Additive => [pixel[0], pixel[1], pixel[2], 0],
+    }
+}
+
+// The premultiplied pixel `over` drawn on top of `under`
+fn blend_pixel(under: [u8; 4], over: [u8; 4]) -> [u8; 4] {
+    let show = 255 - over[3] as u32;
+    let mut pixel = over;
+    for (pixel, under) in pixel.iter_mut().zip(under) {
+        *pixel = (*pixel as u32 + (under as u32 * show + 127) / 255).min(255) as u8;
+    }
+    pixel
+}
+
+// Cells of the grid texture, counted from its bottom left corner
+#[derive(Clone, Copy, Debug)]
+struct GridRegion {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+// The parts of a grid `width` cells across that are different in `new` from `old`.
+// Rows changed next to each other share a region, narrowed to the columns that
+// changed in any of them.
+fn changed_regions(old: &[[u8; 4]], new: &[[u8; 4]], width: usize) -> Vec<GridRegion> {
+    if width == 0 {
+        return Vec::new();
+    }
+    let height = new.len() / width;
+    if old.len() != new.len() {
+        return vec![GridRegion {
+            x: 0,
+            y: 0,
+            width: width as u32,
+            height: height as u32,
+        }];
+    }
+
+    let mut regions: Vec<GridRegion> = Vec::new();
+    let mut last_row = None;
+    for (y, (old_row, new_row)) in old.chunks(width).zip(new.chunks(width)).enumerate() {
+        let mut changed = old_row.iter().zip(new_row).map(|(old, new)| old != new);
+        let Some(left) = changed.position(|changed| changed) else {
+            continue;
+        };
+        let right = match changed.rev().position(|changed| changed) {
+            Some(from_end) => width - from_end,
+            None => left + 1,
+        };
+        let (left, right) = (left as u32, right as u32);
+        match regions.last_mut() {
+            Some(region) if last_row.is_some_and(|row| row + 1 == y) => {
+                let region_right = (region.x + region.width).max(right);
+                region.x = region.x.min(left);
+                region.width = region_right - region.x;
+                region.height += 1;
+            }
+            _ => regions.push(GridRegion {
+                x: left,
+                y: y as u32,
+                width: right - left,
+                height: 1,
+            }),
+        }
+        last_row = Some(y);
+    }
+
+    if regions.len() > MAX_GRID_REGIONS {
+        let left = regions.iter().map(|region| region.x).min().unwrap_or(0);
+        let right = regions.iter().map(|region| region.x + region.width).max();
+        let (first, last) = (regions[0], regions[regions.len() - 1]);
+        return vec![GridRegion {
+            x: left,
+            y: first.y,
+            width: right.unwrap_or(left) - left,
+            height: last.y + last.height - first.y,
+        }];
+    }
+    regions
+}
+
+// Tell the user why the app had to stop, since there's no console for them to see
+// the log in
+fn show_error(message: &str) {
+    tracing::error!("{}", message);
+    let text = wide(&format!("Automata Background had to stop:\n\n{}", message));
+    let caption = wide("Automata Background");
+    unsafe {
+        MessageBoxW(
+            std::ptr::null_mut(),
+            text.as_ptr(),
+            caption.as_ptr(),
+            MB_OK | MB_ICONERROR,
+        );
+    }
+}
+
+// What a caught panic said
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    match panic.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => panic.downcast_ref::<String>().map_or("", String::as_str),
+    }
+}
+
+// Tell the user something they asked for from the tray didn't work
+fn show_warning(message: &str) {
+    tracing::warn!("{}", message);
+    let text = wide(message);
+    let caption = wide("Automata Background");
+    unsafe {
+        MessageBoxW(
+            std::ptr::null_mut(),
+            text.as_ptr(),
+            caption.as_ptr(),
+            MB_OK | MB_ICONWARNING,
+        );
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn window_hwnd(window: &Window) -> Option<HWND> {
+    let Ok(window_handle) = window.window_handle() else {
+        return None;
+    };
+    let RawWindowHandle::Win32(handle) = window_handle.as_raw() else {
+        return None;
+    };
+    Some(handle.hwnd.get() as HWND)
+}
+
+// Put the window behind the desktop icons, returning whether it worked. Done again
+// whenever explorer may have been restarted
+#[cfg(target_os = "windows")]
+fn attach_to_desktop(window: &Window) -> bool {
+    let Some(winit_hwnd) = window_hwnd(window) else {
+        return false;
+    };
+    match layeredwindow::get_worker_window_handle() {
+        Ok(layered_window_handle) => unsafe {
+            // Set the winit window's parent to the layered window
+            !SetParent(winit_hwnd, layered_window_handle).is_null()
+        },
+        // Without one it's a normal window kept behind the others
+        Err(_) => {
+            tracing::warn!("Couldn't find the desktop's WorkerW window");
+            false
+        }
+    }
+}
+
+// The desktop wallpaper drawn under the cells, filling each monitor the way Windows
+// does by default
+struct WallpaperLayer {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    // Which part of the image each visible monitor shows
+    crop_buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+}
+
+impl WallpaperLayer {
+    fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        image: &image::RgbaImage,
+    ) -> WallpaperLayer {
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Wallpaper Texture"),
+                size: wgpu::Extent3d {
+                    width: image.width(),
+                    height: image.height(),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            image,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Wallpaper Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Wallpaper Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Wallpaper Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../wallpaper.wgsl").into()),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Wallpaper Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Wallpaper Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x4],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let crop_size = MAX_WALLPAPER_MONITORS * std::mem::size_of::<[f32; 4]>();
+        let crop_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Wallpaper Crop Buffer"),
+            size: crop_size as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        WallpaperLayer {
+            pipeline,
+            bind_group,
+            crop_buffer,
+            width: image.width(),
+            height: image.height(),
+        }
+    }
+
+    // The middle of the wallpaper, scaled to cover the whole monitor
+    fn crop(&self, monitor: &PixelRect) -> [f32; 4] {
+        let scale = (monitor.width as f32 / self.width as f32)
+            .max(monitor.height as f32 / self.height as f32);
+        let width = monitor.width as f32 / scale / self.width as f32;
+        let height = monitor.height as f32 / scale / self.height as f32;
+        [(1.0 - width) / 2.0, (1.0 - height) / 2.0, width, height]
+    }
+}
+
+// Everything that lives on the graphics card, which has to be made again from
+// scratch when the device is lost
+struct Gpu<'a> {
+    surface: wgpu::Surface<'a>,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    config: wgpu::SurfaceConfiguration,
+    render_pipeline: wgpu::RenderPipeline,
+    // Kept for making the pipelines again with a different shader
+    render_pipeline_layout: wgpu::PipelineLayout,
+    // Every cell's color, one pixel each, which the cell shader looks up
+    grid_texture: wgpu::Texture,
+    grid_bind_group_layout: wgpu::BindGroupLayout,
+    grid_bind_group: wgpu::BindGroup,
+    // Whether every cell has been copied into the grid texture since it was made
+    grid_filled: bool,
+    agent_render_pipeline: wgpu::RenderPipeline,
+    agent_instance_buffer: wgpu::Buffer,
+    agent_vertex_buffer: wgpu::Buffer,
+    // Always drawn with the built in shader, so custom shaders don't need to know
+    // about particles
+    particle_render_pipeline: wgpu::RenderPipeline,
+    particle_instance_buffer: wgpu::Buffer,
+    particle_vertex_buffer: wgpu::Buffer,
+    simulation_parameters_buffer: wgpu::Buffer,
+    simulation_parameters_uniform_bind_group: wgpu::BindGroup,
+    wallpaper: Option<WallpaperLayer>,
+    // Effects the cells go through on their way to the screen, if there are any
+    post_process: Option<PostProcess>,
+    // Set when the device stops working or reports an error
+    lost: Arc<AtomicBool>,
+}
+
+impl<'a> Gpu<'a> {
+    async fn new(
+        window: &'a Window,
+        size: winit::dpi::PhysicalSize<u32>,
+        simulation_parameters_uniform: SimulationParametersUniform,
+        sprite: &image::RgbaImage,
+        gpu: Option<&str>,
+        show_wallpaper: bool,
+        post_effects: &[PostEffectConfig],
+    ) -> Result<Gpu<'a>, String> {
+        // The instance is a handle to our GPU
+        // BackendBit::PRIMARY => Vulkan + Metal + DX12 + Browser WebGPU
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            #[cfg(not(target_arch = "wasm32"))]
+            backends: wgpu::Backends::PRIMARY,
+            #[cfg(target_arch = "wasm32")]
+            backends: wgpu::Backends::GL,
+            ..Default::default()
+        });
+
+        // # Safety
+        //
+        // The surface needs to live as long as the window that created it.
+        // State owns the window so this should be safe.
+        let surface = instance
+            .create_surface(window)
+            .map_err(|e| format!("Couldn't create a surface to draw on: {}", e))?;
+
+        let adapter = adapter::pick_adapter(&instance, &surface, gpu)
+            .await
+            .ok_or("Couldn't find a graphics adapter. Updating the graphics drivers may help.")?;
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: wgpu::Features::empty(),
+                    // WebGL doesn't support all of wgpu's features, so if
+                    // we're building for the web we'll have to disable some.
+                    required_limits: if cfg!(target_arch = "wasm32") {
+                        wgpu::Limits::downlevel_webgl2_defaults()
+                    } else {
+                        wgpu::Limits::default()
+                    },
+                },
+                None, // Trace path
+            )
+            .await
+            .map_err(|e| {
+                format!(
+                    "Couldn't open the graphics device ({}). Updating the graphics drivers \
+                     may help.",
+                    e
+                )
+            })?;
+
+        let surface_caps = surface.get_capabilities(&adapter);
+        // Shader code in this tutorial assumes an Srgb surface texture. Using a different
+        // one will result all the colors comming out darker. If you want to support non
+        // Srgb surfaces, you'll need to account for that when drawing to the frame.
+        let surface_format = surface_caps
+            .formats
+            .iter()
+            .copied()
+            .find(|f| f.is_srgb())
+            .unwrap_or(surface_caps.formats[0]);
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: surface_format,
+            width: size.width,
+            height: size.height,
+            present_mode: surface_caps.present_modes[0],
+            alpha_mode: surface_caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+
+        let sprite_size = wgpu::Extent3d {
+            width: sprite.width(),
+            height: sprite.height(),
+            depth_or_array_layers: 1,
+        };
+        let sprite_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Sprite Texture"),
+            size: sprite_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &sprite_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            sprite,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * sprite.width()),
+                rows_per_image: Some(sprite.height()),
+            },
+            sprite_size,
+        );
+        let sprite_view = sprite_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sprite_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let simulation_parameters_buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Simulation Parameters"),
+                contents: bytemuck::cast_slice(&[simulation_parameters_uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        let simulation_parameters_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Simulation Parameters Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let simulation_parameters_bind_group =
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Simulation Parameters Bind Group"),
+                layout: &simulation_parameters_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: simulation_parameters_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&sprite_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&sprite_sampler),
+                    },
+                ],
+            });
+
+        let grid_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Grid Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                }],
+            });
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts: &[
+                    &simulation_parameters_bind_group_layout,
+                    &grid_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+
+        let (render_pipeline, agent_render_pipeline) = Self::create_pipelines(
+            &device,
+            config.format,
+            &render_pipeline_layout,
+            shaders::BUILT_IN,
+        );
+
+        let grid_size = (
+            simulation_parameters_uniform.width,
+            simulation_parameters_uniform.height,
+        );
+        let (grid_texture, grid_bind_group) =
+            Self::create_grid(&device, &grid_bind_group_layout, grid_size);
+
+        let agent_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Agent Vertex Buffer"),
+            contents: bytemuck::cast_slice(&mirrored(AGENT_VERTICES)),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let agent_instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Agent Instance Buffer"),
+            size: (MIN_AGENT_CAPACITY * std::mem::size_of::<AgentInstance>())
+                as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let particle_render_pipeline =
+            Self::create_particle_pipeline(&device, config.format, &render_pipeline_layout);
+        let particle_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Vertex Buffer"),
+            contents: bytemuck::cast_slice(&mirrored(PARTICLE_VERTICES)),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let particle_instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Instance Buffer"),
+            size: (particles::MAX_PARTICLES * std::mem::size_of::<ParticleInstance>())
+                as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let wallpaper = if show_wallpaper {
+            wallpaper::wallpaper_image(device.limits().max_texture_dimension_2d)
+                .map(|image| WallpaperLayer::new(&device, &queue, config.format, &image))
+        } else {
+            None
+        };
+        let post_process = PostProcess::new(
+            &device,
+            config.format,
+            (size.width, size.height),
+            post_effects,
+            1,
+        );
+
+        // Anything going wrong on the device means starting it over
+        let lost = Arc::new(AtomicBool::new(false));
+        let device_lost = lost.clone();
+        device.set_device_lost_callback(move |reason, message| {
+            tracing::error!("Lost the graphics device ({:?}): {}", reason, message);
+            device_lost.store(true, Ordering::Relaxed);
+        });
+        let device_error = lost.clone();
+        device.on_uncaptured_error(Box::new(move |e| {
+            tracing::error!("Graphics error: {}", e);
+            device_error.store(true, Ordering::Relaxed);
+        }));
+
+        Ok(Gpu {
+            surface,
+            device,
+            queue,
+            config,
+            render_pipeline,
+            render_pipeline_layout,
+            grid_texture,
+            grid_bind_group_layout,
+            grid_bind_group,
+            grid_filled: false,
+            agent_render_pipeline,
+            agent_instance_buffer,
+            agent_vertex_buffer,
+            particle_render_pipeline,
+            particle_instance_buffer,
+            particle_vertex_buffer,
+            simulation_parameters_buffer,
+            simulation_parameters_uniform_bind_group: simulation_parameters_bind_group,
+            wallpaper,
+            post_process,
+            lost,
+        })
+    }
+
+    // A texture with a pixel for each cell of a grid `size` cells across
+    fn create_grid(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        size: (u32, u32),
+    ) -> (wgpu::Texture, wgpu::BindGroup) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Grid Texture"),
+            size: wgpu::Extent3d {
+                width: size.0.max(1),
+                height: size.1.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Grid Bind Group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            }],
+        });
+        (texture, bind_group)
+    }
+
+    // Make the grid texture again for a grid `size` cells across
+    fn resize_grid(&mut self, size: (u32, u32)) {
+        (self.grid_texture, self.grid_bind_group) =
+            Self::create_grid(&self.device, &self.grid_bind_group_layout, size);
+        self.grid_filled = false;
+    }
+
+    // Copy the cells' colors, `width` to a row from the bottom up, into the grid.
+    // Only `regions` have changed since last time, unless the texture is new.
+    fn upload_grid(&mut self, cells: &[[u8; 4]], width: u32, regions: &[GridRegion]) {
+        let size = self.grid_texture.size();
+        if cells.len() != (size.width * size.height) as usize || width != size.width {
+            return;
+        }
+        let everything = [GridRegion {
+            x: 0,
+            y: 0,
+            width: size.width,
+            height: size.height,
+        }];
+        let regions = match self.grid_filled {
+            true => regions,
+            false => &everything,
+        };
+        for region in regions {
+            self.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &self.grid_texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: region.x,
+                        y: region.y,
+                        z: 0,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                bytemuck::cast_slice(cells),
+                wgpu::ImageDataLayout {
+                    offset: 4 * (region.y * size.width + region.x) as wgpu::BufferAddress,
+                    bytes_per_row: Some(4 * size.width),
+                    rows_per_image: Some(region.height),
+                },
+                wgpu::Extent3d {
+                    width: region.width,
+                    height: region.height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        self.grid_filled = true;
+    }
+
+    fn agent_capacity(&self) -> usize {
+        self.agent_instance_buffer.size() as usize / std::mem::size_of::<AgentInstance>()
+    }
+
+    // Make the agent buffer bigger when `count` agents don't fit in it, or smaller
+    // when they'd fit in a quarter of it, up to the most the device can hold
+    fn fit_agent_buffer(&mut self, count: usize) {
+        let capacity = self.agent_capacity();
+        let wanted = count.next_power_of_two().max(MIN_AGENT_CAPACITY);
+        let most =
+            self.device.limits().max_buffer_size as usize / std::mem::size_of::<AgentInstance>();
+        let wanted = wanted.min(most);
+        if wanted == capacity || (count <= capacity && wanted * 4 > capacity) {
+            return;
+        }
+        self.agent_instance_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Agent Instance Buffer"),
+            size: (wanted * std::mem::size_of::<AgentInstance>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+    }
+
+    // Draw with the shader in `source` from now on. Mistakes in it are reported
+    // instead of losing the device, and the shader already in use is kept.
+    fn set_shader(&mut self, source: &str) -> Result<(), String> {
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let (render_pipeline, agent_render_pipeline) = Self::create_pipelines(
+            &self.device,
+            self.config.format,
+            &self.render_pipeline_layout,
+            source,
+        );
+        if let Some(e) = pollster::block_on(self.device.pop_error_scope()) {
+            return Err(e.to_string());
+        }
+        self.render_pipeline = render_pipeline;
+        self.agent_render_pipeline = agent_render_pipeline;
+        Ok(())
+    }
+
+    // The pipelines drawing the cells and agents with the shader in `source`
+    fn create_pipelines(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        layout: &wgpu::PipelineLayout,
+        source: &str,
+    ) -> (wgpu::RenderPipeline, wgpu::RenderPipeline) {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_cell",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    // Fading cells blend into whatever is underneath
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent::OVER,
+                        alpha: wgpu::BlendComponent::OVER,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                // Setting this to anything other than Fill requires Features::POLYGON_MODE_LINE
+                // or Features::POLYGON_MODE_POINT
+                polygon_mode: wgpu::PolygonMode::Fill,
+                // Requires Features::DEPTH_CLIP_CONTROL
+                unclipped_depth: false,
+                // Requires Features::CONSERVATIVE_RASTERIZATION
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            // If the pipeline will be used with a multiview render pass, this
+            // indicates how many array layers the attachments will have.
+            multiview: None,
+        });
+
+        let agent_render_pipeline =
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Agent Render Pipeline"),
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_agent",
+                    buffers: &[Vertex::desc(), AgentInstance::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        // See-through when the opacity is turned down
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent::OVER,
+                            alpha: wgpu::BlendComponent::OVER,
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    // Agents are rotated freely, so draw both faces
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+            });
+        (render_pipeline, agent_render_pipeline)
+    }
+
+    fn create_particle_pipeline(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        layout: &wgpu::PipelineLayout,
+    ) -> wgpu::RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Shader"),
+            source: wgpu::ShaderSource::Wgsl(shaders::BUILT_IN.into()),
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Particle Render Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_particle",
+                buffers: &[Vertex::desc(), ParticleInstance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    // Particles fade into whatever is underneath
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent::OVER,
+                        alpha: wgpu::BlendComponent::OVER,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        })
+    }
+}
+
+struct State<'a> {
+    // Nothing on the graphics card when there's no adapter to use, in which case
+    // the cells are drawn on the canvas instead
+    gpu: Option<Gpu<'a>>,
+    canvas: Option<Canvas>,
+    size: winit::dpi::PhysicalSize<u32>,
+    // Every cell's color premultiplied by how opaque it is, a row at a time from
+    // the bottom up
+    cells: Vec<[u8; 4]>,
+    // The grid from the frame before, kept to fill in again without allocating
+    spare_cells: Vec<[u8; 4]>,
+    agent_instances: Vec<AgentInstance>,
+    particle_instances: Vec<ParticleInstance>,
+    // Kept to upload again if the device has to be made again
+    sprite: image::RgbaImage,
+    // When the GPU side was last started over, to give up if it keeps happening
+    restarts: Vec<Instant>,
+    // Graphics adapter asked for in the config
+    gpu_choice: Option<String>,
+    show_wallpaper: bool,
+    post_effects: Vec<PostEffectConfig>,
+    // Name of the custom shader from the shaders folder, if one is picked
+    shader: Option<String>,
+    shader_modified: Option<SystemTime>,
+    last_shader_check: Instant,
+    window: &'a Window,
+
+    // Left out when running as a screensaver
+    trayicon: Option<TrayIcon<UserEvents>>,
+    mode: Mode,
+
+    // One simulation across the whole window, or one for each monitor
+    screens: Vec<Screen>,
+    // Simulations drawn over the screens, from the bottom up
+    layers: Vec<Layer>,
+    per_monitor: bool,
+    // Slides the window across a world bigger than it, when turned on
+    camera: Option<Camera>,
+    // Zoom in the config file, to tell it being changed there from zooming with the
+    // hotkeys
+    zoom_setting: f32,
+    // Every monitor the window covers, and which of them are left out
+    monitors: Vec<PixelRect>,
+    excluded: Vec<bool>,
+    scheduler: TickScheduler,
+    notifier: Notifier,
+    // Percentage of ticks_per_second picked from the tray menu
+    speed: u32,
+    // Slows the simulation down while the system is busy, when turned on
+    throttle: Option<Throttle>,
+    // Drives the simulation from the music playing, when turned on
+    audio: Option<AudioListener>,
+    // How bright the cells are drawn, dimmed by quiet music
+    brightness: f32,
+    // Eases the opacity and speed in at startup and out again on exit
+    fade: Fade,
+    // Fade level the simulation parameters were last written with
+    fade_level: f32,
+    // Spawns creatures from the CPU and memory use, when turned on
+    usage: Option<UsageMonitor>,
+    // Cells dropped in for every key pressed
+    typing: usize,
+    screenshot_folder: std::path::PathBuf,
+    // Writes the time into the simulations whenever it changes, when turned on
+    clock: bool,
+    clock_24_hour: bool,
+    clock_text: Option<String>,
+    // Packets flying over everything with the network traffic, when turned on
+    network: Option<(NetworkMeter, Packets)>,
+    // Sparks where creatures have died, when turned on
+    particles: Option<Particles>,
+    // Nothing can be seen while the background is hidden, the workstation is locked,
+    // the screensaver is on or windows fill every monitor, so there's no point
+    // updating or drawing
+    hidden: bool,
+    locked: bool,
+    screensaver: bool,
+    covered: bool,
+    pause_when_covered: bool,
+    // Save the world on exit and restore it on the next launch
+    autosave: bool,
+    // Run a single tick on the next update, even while paused
+    step_requested: bool,
+    // The world changed without ticking, so the grid needs filling in again
+    redraw_requested: bool,
+    // Updates it takes a dead cell to fade out, 0 to remove it straight away
+    fade_frames: u32,
+    // The last color and blending of every cell and how far it has faded since it died
+    fading: Vec<([f32; 3], f32, Blend)>,
+    // Width and height of each cell in pixels
+    cell_size: u32,
+    // Pixels left empty between neighboring cells
+    cell_gap: u32,
+    // Every theme in the tray menu and the one in use
+    themes: Vec<Theme>,
+    theme: usize,
+    // Presets in the tray menu, as they were when it was last built
+    presets: Vec<Preset>,
+    // Stamp patterns from the library in the middle of the world instead of anywhere
+    centered_patterns: bool,
+    // Changes the theme with the time of day until one is picked from the tray menu
+    day_night: Option<DayNightConfig>,
+    // Windows accent color the "accent" theme was last made from
+    accent: Option<[f32; 3]>,
+    // Used to pick up rule changes made to the config file while running
+    config_modified: Option<SystemTime>,
+    last_config_check: Instant,
+    // Whether the window sits behind the desktop icons, to put it back there if
+    // explorer restarts
+    attached: bool,
+    last_desktop_check: Instant,
+    // Wall off the desktop icons, which were last seen at `icons`
+    avoid_icons: bool,
+    icons: Vec<PixelRect>,
+    last_icon_check: Instant,
+    // What dragging on the desktop with Alt held down paints
+    brush: Brush,
+    // Where the mouse is in the window and whether it's painting, when the app has a
+    // window of its own
+    cursor: Option<PhysicalPosition<f64>>,
+    dragging: bool,
+    // The automaton picked last, to know which one comes next
+    automaton: AutomatonKind,
+    // Rule for life set over the control pipe, used instead of the config's
+    rule: Option<String>,
+    // Commands from the control pipe and HTTP server waiting to be carried out
+    requests: Option<Receiver<Request>>,
+    // WebSocket clients sent the stats after every tick
+    subscribers: Subscribers,
+    last_tooltip_update: Instant,
+
+    simulation_parameters_uniform: SimulationParametersUniform,
+}
+
+impl<'a> State<'a> {
+    async fn new(
+        window: &'a Window,
+        trayicon: Option<TrayIcon<UserEvents>>,
+        mode: Mode,
+        app_config: &Config,
+        themes: Vec<Theme>,
+        presets: Vec<Preset>,
+        monitors: Vec<PixelRect>,
+    ) -> Result<State<'a>, String> {
+        let size = window.inner_size();
+
+        let sim_scale = 1.0 / app_config.cell_size.max(1) as f32;
+
+        // Calculate aspect ratio
+
+        // Sprites fall back to squares when there's no image to draw
+        let sprite = match (app_config.cell_shape, &app_config.cell_sprite) {
+            (CellShape::Sprite, Some(path)) => match load_sprite(path) {
+                Ok(sprite) => Some(sprite),
+                Err(e) => {
+                    tracing::warn!("Failed to load sprite {}: {}", path.display(), e);
+                    None
+                }
+            },
+            _ => None,
+        };
+        let cell_shape = match (app_config.cell_shape, &sprite) {
+            (CellShape::Sprite, None) => CellShape::Square,
+            (shape, _) => shape,
+        };
+
+        // The grid is the size of the world, which the window shows part of when the
+        // camera is on
+        let camera = app_config.camera.clone().map(Camera::new);
+        let world_scale = camera.as_ref().map_or(1, Camera::scale);
+        let view = (
+            (size.width as f32 * sim_scale) as u32,
+            (size.height as f32 * sim_scale) as u32,
+        );
+        let simulation_parameters_uniform = SimulationParametersUniform {
+            width: view.0 * world_scale,
+            height: view.1 * world_scale,
+            cell_shape: cell_shape.id(),
+            mirrors: kaleidoscope::mirrors(app_config.kaleidoscope),
+            view: [view.0 as f32, view.1 as f32],
+            camera: [0.0; 2],
+            zoom: clamp_zoom(app_config.zoom, world_scale),
+            inset: shape::inset(app_config.cell_gap, app_config.cell_size),
+            opacity: app_config.opacity.clamp(0.0, 1.0),
+            _padding: 0.0,
+        };
+
+        // Every shape shares the same bind group, so a single white pixel stands in
+        // when there isn't a sprite
+        let sprite = sprite.unwrap_or_else(|| image::RgbaImage::from_pixel(1, 1, [255; 4].into()));
+        let gpu = Gpu::new(
+            window,
+            size,
+            simulation_parameters_uniform,
+            &sprite,
+            app_config.gpu.as_deref(),
+            app_config.wallpaper,
+            &app_config.post_processing,
+        )
+        .await;
+        let (gpu, canvas) = Self::software_fallback(window, gpu)?;
+
+        // Log the seed so an interesting run can be shared and replayed
+        let seed = app_config.seed.unwrap_or_else(rand::random);
+        tracing::info!("Simulation seed: {}", seed);
+
+        let theme = match themes
+            .iter()
+            .position(|theme| theme.name == app_config.theme)
+        {
+            Some(index) => index,
+            None => {
+                tracing::warn!("There is no theme called {}", app_config.theme);
+                0
+            }
+        };
+
+        // Each monitor runs its own simulation when asked, otherwise one covers them all
+        let per_monitor = app_config.per_monitor && !monitors.is_empty();
+        let excluded = (1..=monitors.len())
+            .map(|number| app_config.exclude_monitors.contains(&number))
+            .collect();
+        let rects = if per_monitor {
+            monitors.clone()
+        } else {
+            vec![Self::window_rect(size)]
+        };
+        let cell_size = app_config.cell_size.max(1);
+        let rects_count = rects.len();
+        let screens = rects
+            .into_iter()
+            .enumerate()
+            .map(|(index, rect)| {
+                let config = app_config.for_monitor(index);
+                let world_size = Screen::world_size(rect, cell_size, world_scale);
+                let seed = seed.wrapping_add(index as u64);
+                let mut simulation = Self::create_simulation(&config, world_size, seed, index);
+                simulation.automaton.set_theme(&themes[theme]);
+                Screen { simulation, rect }
+            })
+            .collect();
+
+        // Layers cover the whole window, whether or not each monitor has its own screen
+        let layers = app_config
+            .layers
+            .iter()
+            .enumerate()
+            .map(|(index, layer_config)| {
+                let config = app_config.for_layer(index);
+                let world_size =
+                    Screen::world_size(Self::window_rect(size), cell_size, world_scale);
+                let seed = seed.wrapping_add((rects_count + index) as u64);
+                let mut simulation = Self::create_simulation(&config, world_size, seed, index);
+                let layer_theme = layer_config.theme.as_ref().and_then(|name| {
+                    let found = themes.iter().position(|theme| &theme.name == name);
+                    if found.is_none() {
+                        tracing::warn!("There is no theme called {}", name);
+                    }
+                    found
+                });
+                simulation
+                    .automaton
+                    .set_theme(&themes[layer_theme.unwrap_or(theme)]);
+                Layer {
+                    simulation,
+                    scheduler: TickScheduler::new(config.ticks_per_second, config.turbo),
+                    opacity: layer_config.opacity.clamp(0.0, 1.0),
+                    theme: layer_theme,
+                }
+            })
+            .collect();
+
+        let grid_size = (
+            simulation_parameters_uniform.width as usize,
+            simulation_parameters_uniform.height as usize,
+        );
+
+        let mut state = Self {
+            gpu,
+            canvas,
+            size,
+            cells: Vec::new(),
+            spare_cells: Vec::new(),
+            agent_instances: Vec::new(),
+            particle_instances: Vec::new(),
+            sprite,
+            restarts: Vec::new(),
+            gpu_choice: app_config.gpu.clone(),
+            show_wallpaper: app_config.wallpaper,
+            post_effects: app_config.post_processing.clone(),
+            shader: app_config.shader.clone(),
+            shader_modified: None,
+            last_shader_check: Instant::now(),
+            window,
+
+            trayicon,
+            mode,
+
+            simulation_parameters_uniform,
+
+            screens,
+            layers,
+            per_monitor,
+            camera,
+            zoom_setting: app_config.zoom,
+            monitors,
+            excluded,
+            scheduler: TickScheduler::new(app_config.ticks_per_second, app_config.turbo),
+            speed: 100,
+            notifier: Notifier::new(app_config.notifications),
+            throttle: app_config
+                .throttle
+                .then(|| Throttle::new(app_config.busy_cpu_percent)),
+            audio: app_config.audio.clone().map(AudioListener::start),
+            brightness: 1.0,
+            fade: Fade::new(app_config.fade_seconds),
+            // Anything but where the fade starts, so the first update writes it
+            fade_level: 1.0,
+            usage: app_config.system_monitor.clone().map(UsageMonitor::new),
+            typing: app_config.typing,
+            screenshot_folder: app_config
+                .screenshot_folder
+                .clone()
+                .unwrap_or_else(|| Config::directory().join("screenshots")),
+            clock: app_config.clock,
+            clock_24_hour: app_config.clock_24_hour,
+            clock_text: None,
+            network: app_config
+                .network
+                .clone()
+                .map(|config| (NetworkMeter::new(), Packets::new(config))),
+            particles: app_config.particles.clone().map(Particles::new),
+            hidden: false,
+            locked: false,
+            screensaver: false,
+            covered: false,
+            pause_when_covered: app_config.pause_when_covered,
+            autosave: app_config.autosave,
+            step_requested: false,
+            redraw_requested: false,
+            fade_frames: app_config.fade_frames,
+            fading: vec![([0.0; 3], 1.0, Blend::default()); grid_size.0 * grid_size.1],
+            cell_size,
+            cell_gap: app_config.cell_gap,
+            themes,
+            presets,
+            centered_patterns: false,
+            theme,
+            day_night: app_config.day_night.clone(),
+            accent: accent::accent_color(),
+            config_modified: Config::modified(),
+            last_config_check: Instant::now(),
+            attached: mode == Mode::Background
+                && window_hwnd(window).is_some_and(|hwnd| !layeredwindow::parent_lost(hwnd)),
+            last_desktop_check: Instant::now(),
+            avoid_icons: app_config.avoid_icons,
+            icons: Vec::new(),
+            last_icon_check: Instant::now(),
+            brush: Brush::default(),
+            cursor: None,
+            dragging: false,
+            automaton: app_config.automaton,
+            rule: None,
+            requests: None,
+            subscribers: Subscribers::default(),
+            last_tooltip_update: Instant::now(),
+        };
+        if state.shader.is_some() {
+            state.load_shader();
+        }
+        state.update_day_night();
+        state.update_gaps();
+        state.update_icons();
+        Ok(state)
+    }
+
+    // The simulation for the monitor at `index`, restored from where it was left if
+    // autosave is on
+    fn create_simulation(
+        config: &Config,
+        world_size: (usize, usize),
+        seed: u64,
+        index: usize,
+    ) -> Simulation {
+        let replay = config.replay.as_ref().and_then(|path| {
+            Replay::open(path, config)
+                .map_err(|e| tracing::warn!("Failed to open recording {}: {}", path.display(), e))
+                .ok()
+        });
+        let automaton: Box<dyn Automaton> = match replay {
+            Some(replay) => Box::new(replay),
+            None => config.automaton.create(config),
+        };
+
+        let mut simulation = Simulation::new(world_size, automaton, seed, config.cycle_window);
+        simulation.set_boundary(config.boundary);
+        simulation.rewind = RewindBuffer::new(config.rewind_memory_mb * 1024 * 1024);
+        if config.autosave {
+            let path = Self::autosave_path(index);
+            if path.exists() {
+                match World::load(&path) {
+                    Ok((world, ticks)) => simulation.restore(&world, ticks),
+                    Err(e) => tracing::warn!("Failed to restore {}: {}", path.display(), e),
+                }
+            }
+        }
+        if let Some(path) = &config.record {
+            match Recorder::create(path, world_size, seed) {
+                Ok(recorder) => simulation.recorder = Some(recorder),
+                Err(e) => tracing::warn!("Failed to start recording {}: {}", path.display(), e),
+            }
+        }
+        if let Some(text) = &config.seed_text {
+            simulation.text = Some(TextSeed::new(text));
+        }
+        if let Some(path) = &config.obstacle_map {
+            match ObstacleMap::load(path) {
+                Ok(obstacles) => simulation.obstacles = Some(obstacles),
+                Err(e) => tracing::warn!("Failed to load obstacle map {}: {}", path.display(), e),
+            }
+        }
+        if config.heatmap {
+            simulation.heatmap = Some(Heatmap::new(world_size));
+        }
+        if config.boids {
+            // The flock is spawned on the first update
+            simulation.boids = Some(Boids::new());
+        }
+        if config.particles.is_some() {
+            simulation.deaths = Some(Vec::new());
+        }
+        simulation
+    }
+
+    fn window_rect(size: PhysicalSize<u32>) -> PixelRect {
+        PixelRect {
+            x: 0,
+            y: 0,
+            width: size.width,
+            height: size.height,
+        }
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.size = new_size;
+            if let Some(gpu) = &mut self.gpu {
+                gpu.config.width = new_size.width;
+                gpu.config.height = new_size.height;
+                gpu.surface.configure(&gpu.device, &gpu.config);
+            }
+            self.update_post_process();
+            self.resize_world();
+        }
+    }
+
+    // Draw with the custom shader picked in the config, or the built in one. One
+    // that doesn't compile is reported and the shader already in use is kept.
+    fn load_shader(&mut self) {
+        let Some(gpu) = &mut self.gpu else {
+            return;
+        };
+        self.shader_modified = self.shader.as_deref().and_then(shaders::modified);
+        let source = match &self.shader {
+            Some(name) => shaders::load(name),
+            None => Ok(shaders::BUILT_IN.to_string()),
+        };
+        if let Err(e) = source.and_then(|source| gpu.set_shader(&source)) {
+            let name = self.shader.as_deref().unwrap_or_default();
+            tracing::warn!("Failed to use shader {}: {}", name, e);
+            self.notifier
+                .notify(&format!("Shader {} has a problem", name), &e);
+        }
+        self.redraw_requested = true;
+    }
+
+    // Load the custom shader again whenever it's saved
+    fn check_shader(&mut self) {
+        if self.last_shader_check.elapsed() < SHADER_CHECK_INTERVAL {
+            return;
+        }
+        self.last_shader_check = Instant::now();
+        let Some(name) = &self.shader else {
+            return;
+        };
+        if shaders::modified(name) != self.shader_modified {
+            self.load_shader();
+        }
+    }
+
+    // Make the post-processing textures again for the window's size and effects
+    fn update_post_process(&mut self) {
+        if let Some(gpu) = &mut self.gpu {
+            let size = (gpu.config.width, gpu.config.height);
+            gpu.post_process =
+                PostProcess::new(&gpu.device, gpu.config.format, size, &self.post_effects, 1);
+        }
+        self.redraw_requested = true;
+    }
+
+    // How many cells fit across and up the window at the current cell size
+    fn view_size(&self) -> (usize, usize) {
+        (
+            (self.size.width / self.cell_size) as usize,
+            (self.size.height / self.cell_size) as usize,
+        )
+    }
+
+    // How many times wider and taller than the window the worlds are
+    fn world_scale(&self) -> u32 {
+        self.camera.as_ref().map_or(1, Camera::scale)
+    }
+
+    // How many cells the grid holds, which is the size of the main world
+    fn grid_size(&self) -> (usize, usize) {
+        let (width, height) = self.view_size();
+        let scale = self.world_scale() as usize;
+        (width * scale, height * scale)
+    }
+
+    // Tell the shader how many cells the window and grid hold now
+    fn update_simulation_parameters(&mut self) {
+        let (width, height) = self.grid_size();
+        let (view_width, view_height) = self.view_size();
+        self.simulation_parameters_uniform.width = width as u32;
+        self.simulation_parameters_uniform.height = height as u32;
+        self.simulation_parameters_uniform.view = [view_width as f32, view_height as f32];
+        self.write_simulation_parameters();
+        if let Some(gpu) = &mut self.gpu {
+            gpu.resize_grid((width as u32, height as u32));
+        }
+        self.fading = vec![([0.0; 3], 1.0, Blend::default()); width * height];
+        self.redraw_requested = true;
+    }
+
+    fn write_simulation_parameters(&mut self) {
+        self.simulation_parameters_uniform.camera =
+            self.camera.as_ref().map_or([0.0; 2], Camera::position);
+        // Fading in and out dims everything on top of the opacity setting
+        let uniform = SimulationParametersUniform {
+            opacity: self.simulation_parameters_uniform.opacity * self.fade_level,
+            ..self.simulation_parameters_uniform
+        };
+        if let Some(gpu) = &self.gpu {
+            gpu.queue.write_buffer(
+                &gpu.simulation_parameters_buffer,
+                0,
+                bytemuck::cast_slice(&[uniform]),
+            );
+        }
+    }
+
+    // Follow the fade in or out, returning whether it changed anything
+    fn update_fade(&mut self) -> bool {
+        let level = self.fade.level();
+        if level == self.fade_level {
+            return false;
+        }
+        self.fade_level = level;
+        self.write_simulation_parameters();
+        true
+    }
+
+    // Start fading out to close. Returns false when there's nothing to wait for and
+    // the app can close straight away.
+    fn leave(&mut self) -> bool {
+        !self.is_asleep() && self.fade.leave()
+    }
+
+    // Whether the app has finished fading out and can close
+    fn has_left(&self) -> bool {
+        self.fade.is_gone()
+    }
+
+    // Let the camera drift, returning whether it moved
+    fn update_camera(&mut self) -> bool {
+        let size = self.grid_size();
+        let Some(camera) = &mut self.camera else {
+            return false;
+        };
+        if !camera.update(size) {
+            return false;
+        }
+        self.write_simulation_parameters();
+        true
+    }
+
+    // Move the camera `steps` to the right and up
+    fn pan(&mut self, steps: (i32, i32)) {
+        let (width, height) = self.view_size();
+        let size = self.grid_size();
+        let Some(camera) = &mut self.camera else {
+            return;
+        };
+        // The window shows fewer cells the further in it's zoomed
+        let step = PAN_STEP / self.simulation_parameters_uniform.zoom;
+        let cells = [
+            steps.0 as f32 * width as f32 * step,
+            steps.1 as f32 * height as f32 * step,
+        ];
+        camera.pan(cells.map(f32::round), size);
+        self.write_simulation_parameters();
+        self.redraw_requested = true;
+    }
+
+    fn zoom(&mut self, steps: i32) {
+        self.set_zoom(self.simulation_parameters_uniform.zoom * ZOOM_STEP.powi(steps));
+    }
+
+    // Picking an opacity by hand keeps it in the config file for next time
+    fn pick_opacity(&mut self, percent: u32) {
+        let opacity = percent as f32 / 100.0;
+        if let Err(e) = Config::save_setting("opacity", opacity as f64) {
+            let path = Config::path();
+            tracing::warn!("Failed to save the opacity to {}: {}", path.display(), e);
+        }
+        // Saving isn't an edit to pick up
+        self.config_modified = Config::modified();
+        self.set_opacity(opacity);
+    }
+
+    fn set_opacity(&mut self, opacity: f32) {
+        let opacity = opacity.clamp(0.0, 1.0);
+        self.simulation_parameters_uniform.opacity = opacity;
+        self.write_simulation_parameters();
+        self.redraw_requested = true;
+        for percent in OPACITIES {
+            let checked = percent == (opacity * 100.0).round() as u32;
+            self.check_menu_item(UserEvents::Opacity(percent), checked);
+        }
+    }
+
+    // Draw the cells `zoom` times bigger than the window fits, around its middle
+    fn set_zoom(&mut self, zoom: f32) {
+        self.simulation_parameters_uniform.zoom = clamp_zoom(zoom, self.world_scale());
+        self.write_simulation_parameters();
+        self.redraw_requested = true;
+    }
+
+    fn toggle_drift(&mut self) {
+        let Some(camera) = &mut self.camera else {
+            return;
+        };
+        camera.drifting = !camera.drifting;
+        let drifting = camera.drifting;
+        self.check_menu_item(UserEvents::ToggleDrift, drifting);
+    }
+
+    // Turn the camera on or off, which changes how big the worlds are and so how far
+    // out it can zoom
+    fn set_camera(&mut self, camera: Option<Camera>) {
+        self.camera = camera;
+        self.set_zoom(self.simulation_parameters_uniform.zoom);
+        self.resize_world();
+        self.update_gaps();
+        self.update_icons();
+    }
+
+    // Keep the worlds lined up with the window after it changes size
+    fn resize_world(&mut self) {
+        if !self.per_monitor {
+            self.screens[0].rect = Self::window_rect(self.size);
+        }
+        let scale = self.world_scale();
+        for screen in &mut self.screens {
+            let world_size = Screen::world_size(screen.rect, self.cell_size, scale);
+            if world_size != screen.simulation.world().size {
+                screen.simulation.resize(world_size);
+            }
+        }
+        let world_size = Screen::world_size(Self::window_rect(self.size), self.cell_size, scale);
+        for layer in &mut self.layers {
+            if world_size != layer.simulation.world().size {
+                layer.simulation.resize(world_size);
+            }
+        }
+        self.update_simulation_parameters();
+    }
+
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        if self.mode == Mode::Windowed && self.mouse_input(event) {
+            return true;
+        }
+        let WindowEvent::KeyboardInput {
+            event:
+                KeyEvent {
+                    state: ElementState::Pressed,
+                    physical_key: PhysicalKey::Code(key),
+                    ..
+                },
+            ..
+        } = event
+        else {
+            return false;
+        };
+
+        match key {
+            KeyCode::Space => self.toggle_pause(),
+            KeyCode::Period | KeyCode::ArrowRight => self.step(),
+            KeyCode::Comma | KeyCode::ArrowLeft => self.rewind(),
+            KeyCode::KeyR => self.reset(),
+            KeyCode::KeyN => self.next_automaton(),
+            KeyCode::KeyS => self.screenshot(),
+            _ => return false,
+        }
+        true
+    }
+
+    // Drag with the left button to paint with the brush, in a window of its own
+    fn mouse_input(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => self.cursor = Some(*position),
+            WindowEvent::CursorLeft { .. } => self.cursor = None,
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => self.dragging = *state == ElementState::Pressed,
+            _ => return false,
+        }
+        let (Some(position), true, Some(hwnd)) =
+            (self.cursor, self.dragging, window_hwnd(self.window))
+        else {
+            return true;
+        };
+        // Painting goes by where it is on the screen, like the mouse hook's
+        let mut point = POINT {
+            x: position.x as i32,
+            y: position.y as i32,
+        };
+        if unsafe { ClientToScreen(hwnd, &mut point) } != 0 {
+            self.paint(point.x, point.y);
+        }
+        true
+    }
+
+    fn set_theme(&mut self, index: usize) {
+        let Some(theme) = self.themes.get(index) else {
+            return;
+        };
+        for screen in &mut self.screens {
+            screen.simulation.automaton.set_theme(theme);
+        }
+        for layer in self.layers.iter_mut().filter(|layer| layer.theme.is_none()) {
+            layer.simulation.automaton.set_theme(theme);
+        }
+        self.theme = index;
+        for i in 0..self.themes.len() {
+            self.check_menu_item(UserEvents::Theme(i), i == index);
+        }
+        self.redraw_requested = true;
+    }
+
+    // Run a different automaton, with the rest of its settings from the config file
+    fn set_automaton(&mut self, kind: AutomatonKind) {
+        self.automaton = kind;
+        for automaton in AutomatonKind::ALL {
+            self.check_menu_item(UserEvents::Automaton(automaton), automaton == kind);
+        }
+
+        let app_config = Config::load();
+        for (index, screen) in self.screens.iter_mut().enumerate() {
+            let mut config = app_config.for_monitor(index);
+            config.automaton = kind;
+            if let Some(rule) = &self.rule {
+                config.rule = rule.clone();
+            }
+            screen.simulation.set_automaton(kind.create(&config));
+        }
+        // The new automata need coloring in like the old ones were
+        self.set_theme(self.theme);
+        self.update_day_night();
+    }
+
+    fn next_automaton(&mut self) {
+        let all = AutomatonKind::ALL;
+        let index = all.iter().position(|&kind| kind == self.automaton);
+        let next = index.map_or(0, |index| (index + 1) % all.len());
+        self.set_automaton(all[next]);
+    }
+
+    // Start over with a grid of cells `cell_size` pixels across
+    fn set_cell_size(&mut self, cell_size: u32) {
+        let cell_size = cell_size.max(1);
+        self.cell_size = cell_size;
+        for size in CELL_SIZES {
+            self.check_menu_item(UserEvents::CellSize(size), size == cell_size);
+        }
+
+        let scale = self.world_scale();
+        for screen in &mut self.screens {
+            screen
+                .simulation
+                .set_size(Screen::world_size(screen.rect, cell_size, scale));
+        }
+        let world_size = Screen::world_size(Self::window_rect(self.size), cell_size, scale);
+        for layer in &mut self.layers {
+            layer.simulation.set_size(world_size);
+        }
+        // The gap stays the same number of pixels
+        self.simulation_parameters_uniform.inset = shape::inset(self.cell_gap, cell_size);
+        self.update_simulation_parameters();
+    }
+
+    // Picking a theme by hand stops it changing with the time of day, and it's kept
+    // in the config file for next time
+    fn pick_theme(&mut self, index: usize) {
+        let Some(theme) = self.themes.get(index) else {
+            return;
+        };
+        if let Err(e) = Config::save_setting("theme", theme.name.as_str()) {
+            tracing::warn!(
+                "Failed to save the theme to {}: {}",
+                Config::path().display(),
+                e
+            );
+        }
+        // Saving isn't an edit to pick up, which would turn day and night back on
+        self.config_modified = Config::modified();
+
+        self.day_night = None;
+        self.set_theme(index);
+    }
+
+    // Blend between the day and night themes for the current time
+    fn update_day_night(&mut self) {
+        let Some(day_night) = &self.day_night else {
+            return;
+        };
+        let find = |name: &str| self.themes.iter().find(|theme| theme.name == name);
+        let (Some(day), Some(night)) = (find(&day_night.day_theme), find(&day_night.night_theme))
+        else {
+            tracing::warn!(
+                "Day and night themes {} and {} don't both exist",
+                day_night.day_theme,
+                day_night.night_theme
+            );
+            self.day_night = None;
+            return;
+        };
+
+        let night = night.dimmed(day_night.night_brightness);
+        let theme = night.blend(day, day_night.daylight(clock::local_hour()));
+        for screen in &mut self.screens {
+            screen.simulation.automaton.set_theme(&theme);
+        }
+        for layer in self.layers.iter_mut().filter(|layer| layer.theme.is_none()) {
+            layer.simulation.automaton.set_theme(&theme);
+        }
+        self.redraw_requested = true;
+    }
+
+    // Rebuild the accent theme when the Windows accent color changes
+    fn update_accent(&mut self) {
+        let accent = accent::accent_color();
+        if accent == self.accent {
+            return;
+        }
+        self.accent = accent;
+
+        let (Some(color), Some(index)) = (
+            accent,
+            self.themes.iter().position(|theme| theme.name == "accent"),
+        ) else {
+            return;
+        };
+        self.themes[index] = Theme::from_accent(color);
+        if self.theme == index {
+            self.set_theme(index);
+        }
+    }
+
+    // The monitors still being drawn on, or the whole window when there aren't any
+    fn visible_monitors(&self) -> Vec<PixelRect> {
+        if self.monitors.is_empty() {
+            return vec![Self::window_rect(self.size)];
+        }
+        self.monitors
+            .iter()
+            .zip(&self.excluded)
+            .filter(|(_, &excluded)| !excluded)
+            .map(|(monitor, _)| *monitor)
+            .collect()
+    }
+
+    // A single simulation keeps out of the parts of the desktop no visible monitor
+    // shows. With the camera on, those parts move across the world, so nothing is
+    // kept out.
+    fn update_gaps(&mut self) {
+        if self.per_monitor {
+            return;
+        }
+        if self.camera.is_some() {
+            self.screens[0].simulation.gaps = None;
+            return;
+        }
+        let gaps = gap_map(&self.visible_monitors(), desktop_size(&self.monitors));
+        self.screens[0].simulation.gaps = gaps;
+    }
+
+    // Put walls where the desktop icons are, if they've moved since last time
+    fn update_icons(&mut self) {
+        self.last_icon_check = Instant::now();
+        // The icons stay put while the camera moves the world under them
+        let icons = match window_hwnd(self.window) {
+            Some(hwnd) if self.avoid_icons && self.camera.is_none() => icons::icon_rects(hwnd),
+            _ => Vec::new(),
+        };
+        if icons == self.icons {
+            return;
+        }
+        for screen in &mut self.screens {
+            screen.simulation.set_icons(icon_map(&icons, screen.rect));
+        }
+        self.icons = icons;
+        self.redraw_requested = true;
+    }
+
+    fn set_brush(&mut self, brush: Brush) {
+        self.brush = brush;
+        for b in Brush::ALL {
+            self.check_menu_item(UserEvents::Brush(b), b == brush);
+        }
+    }
+
+    // The index of the screen under the point `x`, `y` on the screen and the cell of
+    // its world there, unless that monitor is left out
+    fn cell_at(&self, x: i32, y: i32) -> Option<(usize, (usize, usize))> {
+        let hwnd = window_hwnd(self.window)?;
+        let mut point = POINT { x, y };
+        if unsafe { ScreenToClient(hwnd, &mut point) } == 0 || point.x < 0 || point.y < 0 {
+            return None;
+        }
+        let (x, y) = (point.x as u32, point.y as u32);
+
+        let hidden = self
+            .monitors
+            .iter()
+            .zip(&self.excluded)
+            .any(|(monitor, &excluded)| excluded && monitor.contains(x, y));
+        if hidden {
+            return None;
+        }
+        // The grid counts its rows up from the bottom
+        let cell_size = self.cell_size as f32;
+        let bottom = self.size.height.saturating_sub(y + 1);
+        let (column, row) = self.grid_cell([x as f32 / cell_size, bottom as f32 / cell_size]);
+        self.screens.iter().enumerate().find_map(|(index, screen)| {
+            let (offset_x, offset_y) = screen.offset(self.size.height, self.cell_size);
+            let (width, height) = screen.simulation.world().size;
+            let (x, y) = (column.checked_sub(offset_x)?, row.checked_sub(offset_y)?);
+            (x < width && y < height).then_some((index, (x, y)))
+        })
+    }
+
+    // The cell of the grid that shows at `point` in the window, measured in cells from
+    // its bottom left, found the same way the shader does
+    fn grid_cell(&self, point: [f32; 2]) -> (usize, usize) {
+        let uniform = &self.simulation_parameters_uniform;
+        let center = uniform.view.map(|cells| cells / 2.0);
+        let from_center = [0, 1].map(|axis| (point[axis] - center[axis]) / uniform.zoom);
+        let folded = kaleidoscope::fold(from_center, uniform.mirrors);
+        let size = [uniform.width, uniform.height].map(|cells| cells.max(1) as f32);
+        let [x, y] = [0, 1].map(|axis| {
+            let position = folded[axis] + center[axis] + uniform.camera[axis];
+            (position.rem_euclid(size[axis]) as usize).min(size[axis] as usize - 1)
+        });
+        (x, y)
+    }
+
+    // Paint the cells under the point `x`, `y` on the screen
+    fn paint(&mut self, x: i32, y: i32) {
+        let Some((index, (x, y))) = self.cell_at(x, y) else {
+            return;
+        };
+        let simulation = &mut self.screens[index].simulation;
+        let Some(cell_type) = self.brush.cell_type(&*simulation.automaton) else {
+            return;
+        };
+        simulation.paint(x, y, BRUSH_RADIUS, cell_type);
+        self.redraw_requested = true;
+    }
+
+    // Typing brings new life to the desktop, a few cells for every key
+    fn keystroke(&mut self) {
+        if self.typing == 0 {
+            return;
+        }
+        for screen in &mut self.screens {
+            let simulation = &mut screen.simulation;
+            if let Some(cell_type) = simulation.automaton.seed_cell_type() {
+                simulation.sprinkle(self.typing, cell_type);
+            }
+        }
+        self.redraw_requested = true;
+    }
+
+    // Follow the music: louder runs faster and brighter, and each beat of the bass
+    // drops new cells in. Returns how much faster to run.
+    fn update_audio(&mut self) -> f64 {
+        let Some(audio) = &mut self.audio else {
+            if self.brightness != 1.0 {
+                self.brightness = 1.0;
+                self.redraw_requested = true;
+            }
+            return 1.0;
+        };
+        let levels = audio.levels();
+        let beats = audio.take_beats() as usize;
+        let config = &audio.config;
+
+        if beats > 0 && config.spawn > 0 {
+            for screen in &mut self.screens {
+                let simulation = &mut screen.simulation;
+                if let Some(cell_type) = simulation.automaton.seed_cell_type() {
+                    simulation.sprinkle(config.spawn * beats, cell_type);
+                }
+            }
+            self.redraw_requested = true;
+        }
+
+        let loudness = levels.mid.max(levels.treble).min(1.0);
+        let brightness = 1.0 - config.brightness.clamp(0.0, 1.0) * (1.0 - loudness);
+        // Only redraw for changes big enough to see
+        if (brightness - self.brightness).abs() > 0.01 {
+            self.brightness = brightness;
+            self.redraw_requested = true;
+        }
+        1.0 + config.speed.max(0.0) as f64 * levels.energy.min(1.0) as f64
+    }
+
+    // Busy CPUs bring in predators and freed memory brings in prey
+    fn update_usage(&mut self) {
+        let Some((predators, prey)) = self.usage.as_mut().and_then(UsageMonitor::update) else {
+            return;
+        };
+        for screen in &mut self.screens {
+            let simulation = &mut screen.simulation;
+            for (brush, count) in [(Brush::Predators, predators), (Brush::Prey, prey)] {
+                if let Some(cell_type) = brush.cell_type(simulation.automaton.as_ref()) {
+                    simulation.sprinkle(count, cell_type);
+                }
+            }
+        }
+        self.redraw_requested |= predators + prey > 0;
+    }
+
+    // Write the time in once a minute, which the simulation then eats away at
+    fn update_clock(&mut self) {
+        if !self.clock {
+            self.clock_text = None;
+            return;
+        }
+        // The first tick seeds the world, which would cover it straight up
+        if self
+            .screens
+            .iter()
+            .any(|screen| screen.simulation.ticks() == 0)
+        {
+            return;
+        }
+        let text = clock::time_text(self.clock_24_hour);
+        if self.clock_text.as_ref() == Some(&text) {
+            return;
+        }
+        let seed = TextSeed::new(&text);
+        for screen in &mut self.screens {
+            screen.simulation.write_text(&seed);
+        }
+        self.clock_text = Some(text);
+        self.redraw_requested = true;
+    }
+
+    // Move the packets along and send more out as fast as the network is going
+    fn update_network(&mut self) {
+        let view_size = self.view_size();
+        let Some((meter, packets)) = &mut self.network else {
+            return;
+        };
+        if let Some((received, sent)) = meter.update() {
+            packets.set_throughput(received, sent);
+        }
+        packets.step(view_size);
+        // They move every frame, not just when the simulation ticks
+        self.redraw_requested |= !packets.agents.is_empty();
+    }
+
+    // Throw out particles where creatures died since last time and move the ones
+    // already flying along, returning whether there's anything to draw
+    fn update_particles(&mut self) -> bool {
+        let Some(particles) = &mut self.particles else {
+            return false;
+        };
+        let drawn = !particles.particles.is_empty();
+        for screen in &mut self.screens {
+            let (offset_x, offset_y) = screen.offset(self.size.height, self.cell_size);
+            let simulation = &mut screen.simulation;
+            let Some(deaths) = simulation.deaths.as_mut().map(std::mem::take) else {
+                continue;
+            };
+            for death in deaths {
+                let Some(color) = simulation.automaton.color(&death.cell, simulation.ticks())
+                else {
+                    continue;
+                };
+                let cell = [(offset_x + death.row) as f32, (offset_y + death.col) as f32];
+                particles.burst(cell, color);
+            }
+        }
+        particles.step();
+        // One more frame once the last of them are gone, to clear them away
+        drawn || !particles.particles.is_empty()
+    }
+
+    // Let the automata know where the mouse is, so their creatures can react to it
+    fn update_cursor(&mut self) {
+        let mut point = POINT { x: 0, y: 0 };
+        let cell = if unsafe { GetCursorPos(&mut point) } != 0 {
+            self.cell_at(point.x, point.y)
+        } else {
+            None
+        };
+        for (index, screen) in self.screens.iter_mut().enumerate() {
+            let position = cell
+                .filter(|&(i, _)| i == index)
+                .map(|(_, position)| position);
+            screen.simulation.automaton.set_cursor(position);
+        }
+    }
+
+    // Show or hide the simulation on a monitor
+    fn toggle_monitor(&mut self, index: usize) {
+        let Some(excluded) = self.excluded.get_mut(index) else {
+            return;
+        };
+        *excluded = !*excluded;
+        let shown = !*excluded;
+        self.check_menu_item(UserEvents::Monitor(index), shown);
+
+        // A single simulation starts over so its walls match the monitors left
+        if !self.per_monitor {
+            self.update_gaps();
+            self.screens[0].simulation.reset_simulation();
+        }
+        self.redraw_requested = true;
+    }
+
+    // Every screen is paused and resumed together
+    fn is_paused(&self) -> bool {
+        self.screens[0].simulation.is_paused()
+    }
+
+    fn toggle_pause(&mut self) {
+        let paused = !self.is_paused();
+        for screen in &mut self.screens {
+            if paused {
+                screen.simulation.pause();
+            } else {
+                screen.simulation.resume();
+            }
+        }
+        self.check_menu_item(UserEvents::TogglePause, paused);
+        self.update_speed_menu();
+    }
+
+    // Run at `percent` of the configured speed, resuming if paused. 0 pauses instead.
+    fn set_speed(&mut self, percent: u32) {
+        if percent == 0 {
+            if !self.is_paused() {
+                self.toggle_pause();
+            }
+            return;
+        }
+
+        self.speed = percent;
+        self.scheduler.multiplier = percent as f64 / 100.0;
+        if self.is_paused() {
+            self.toggle_pause();
+        } else {
+            self.update_speed_menu();
+        }
+    }
+
+    fn toggle_autostart(&mut self) {
+        let enabled = !autostart::is_enabled();
+        if let Err(e) = autostart::set_enabled(enabled) {
+            tracing::warn!(
+                "Failed to change whether the app starts with Windows: {}",
+                e
+            );
+        }
+        self.check_menu_item(UserEvents::ToggleAutostart, autostart::is_enabled());
+    }
+
+    // Start every simulation over from a fresh seed
+    fn reset(&mut self) {
+        for screen in &mut self.screens {
+            screen.simulation.reset_simulation();
+        }
+        for layer in &mut self.layers {
+            layer.simulation.reset_simulation();
+        }
+        self.redraw_requested = true;
+    }
+
+    // Advance a single tick, pausing first so it can be looked at
+    fn step(&mut self) {
+        if !self.is_paused() {
+            self.toggle_pause();
+        }
+        self.step_requested = true;
+    }
+
+    // Go back a tick, pausing first so it doesn't carry straight on again
+    fn rewind(&mut self) {
+        if !self.is_paused() {
+            self.toggle_pause();
+        }
+        for screen in &mut self.screens {
+            self.redraw_requested |= screen.simulation.rewind();
+        }
+    }
+
+    // Save what's on screen as a PNG in the screenshot folder
+    fn screenshot(&self) {
+        self.save_capture("screenshot", 1);
+    }
+
+    // Save the current frame drawn at `scale` times the resolution of the screen,
+    // big enough to print. Nothing ticks until it's done.
+    fn photo(&self, scale: u32) {
+        let Some(gpu) = &self.gpu else {
+            tracing::warn!("Photos need a graphics card to draw with");
+            return;
+        };
+        // Textures can only get so big
+        let largest = gpu.config.width.max(gpu.config.height).max(1);
+        let limit = gpu.device.limits().max_texture_dimension_2d / largest;
+        if limit < scale {
+            tracing::warn!(
+                "Photos of this screen can only be {}x, not {}x",
+                limit,
+                scale
+            );
+        }
+        self.save_capture(&format!("photo-{}x", scale), scale.min(limit).max(1));
+    }
+
+    fn save_capture(&self, name: &str, scale: u32) {
+        let seconds = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = self
+            .screenshot_folder
+            .join(format!("{}-{}.png", name, seconds));
+        let saved = self.capture(scale).and_then(|image| {
+            std::fs::create_dir_all(&self.screenshot_folder).map_err(|e| e.to_string())?;
+            image.save(&path).map_err(|e| e.to_string())
+        });
+        match saved {
+            Ok(()) => tracing::info!("Saved {} to {}", name, path.display()),
+            Err(e) => tracing::warn!("Failed to save {}: {}", path.display(), e),
+        }
+    }
+
+    // Draw the frame again into a texture of its own, `scale` times the size of the
+    // window, and read it back, since the window's own can't be copied from
+    fn capture(&self, scale: u32) -> Result<image::RgbaImage, String> {
+        let Some(gpu) = &self.gpu else {
+            return Err("Screenshots need a graphics card to draw with".to_string());
+        };
+        let (width, height) = (gpu.config.width * scale, gpu.config.height * scale);
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Screenshot Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: gpu.config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Rows of a texture copied into a buffer have to line up on 256 bytes
+        let row_bytes = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Screenshot Buffer"),
+            size: row_bytes as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Screenshot Encoder"),
+            });
+        self.draw(gpu, &mut encoder, &view, scale);
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(row_bytes),
+                    rows_per_image: Some(height),
+                },
+            },
+            size,
+        );
+        gpu.queue.submit(iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        gpu.device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+
+        let bgra = matches!(
+            gpu.config.format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        );
+        let data = slice.get_mapped_range();
+        let mut image = image::RgbaImage::new(width, height);
+        for (y, row) in data
+            .chunks(row_bytes as usize)
+            .take(height as usize)
+            .enumerate()
+        {
+            for (x, pixel) in row.chunks(4).take(width as usize).enumerate() {
+                let [r, g, b] = match bgra {
+                    true => [pixel[2], pixel[1], pixel[0]],
+                    false => [pixel[0], pixel[1], pixel[2]],
+                };
+                // Nothing drawn is see-through on the desktop, but black in a picture
+                image.put_pixel(x as u32, y as u32, [r, g, b, 255].into());
+            }
+        }
+        drop(data);
+        buffer.unmap();
+        Ok(image)
+    }
+
+    // Files for the first monitor keep the names from before there was one per monitor
+    fn world_path(name: &str, index: usize, extension: &str) -> std::path::PathBuf {
+        match index {
+            0 => Config::directory().join(format!("{}.{}", name, extension)),
+            _ => Config::directory().join(format!("{}-{}.{}", name, index, extension)),
+        }
+    }
+
+    fn saved_world_path(index: usize) -> std::path::PathBuf {
+        Self::world_path("saved", index, "world")
+    }
+
+    fn autosave_path(index: usize) -> std::path::PathBuf {
+        Self::world_path("autosave", index, "world")
+    }
+
+    // Keep the worlds when the app closes, so they carry on where they left off next time
+    fn autosave(&self) {
+        // The screensaver starts from the background's world but leaves it alone
+        if !self.autosave || self.mode != Mode::Background {
+            return;
+        }
+        let _ = std::fs::create_dir_all(Config::directory());
+        for (index, screen) in self.screens.iter().enumerate() {
+            let path = Self::autosave_path(index);
+            let simulation = &screen.simulation;
+            if let Err(e) = simulation.world().save(&path, simulation.ticks()) {
+                tracing::warn!("Failed to save {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    fn save_world(&self) {
+        let _ = std::fs::create_dir_all(Config::directory());
+        for (index, screen) in self.screens.iter().enumerate() {
+            let path = Self::saved_world_path(index);
+            let simulation = &screen.simulation;
+            match simulation.world().save(&path, simulation.ticks()) {
+                Ok(()) => tracing::info!("Saved world to {}", path.display()),
+                Err(e) => tracing::warn!("Failed to save {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    fn load_world(&mut self) {
+        for (index, screen) in self.screens.iter_mut().enumerate() {
+            let path = Self::saved_world_path(index);
+            match World::load(&path) {
+                Ok((world, ticks)) => {
+                    screen.simulation.restore(&world, ticks);
+                    self.redraw_requested = true;
+                }
+                Err(e) => tracing::warn!("Failed to load {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    fn advance_simulation(&mut self) {
+        // Titles and messages of anything worth a notification
+        let mut events = Vec::new();
+        for (index, screen) in self.screens.iter_mut().enumerate() {
+            // Monitors that are left out don't need their simulation running
+            if self.per_monitor && self.excluded[index] {
+                continue;
+            }
+            let simulation = &mut screen.simulation;
+            let peak_population = simulation.statistics.peak_population;
+            let tick = simulation.ticks();
+            let _span = tracing::debug_span!("tick", screen = index, tick).entered();
+            simulation.step();
+
+            let statistics = &simulation.statistics;
+            for cell_type in statistics.died_out() {
+                if let CellType::Preditor | CellType::Prey | CellType::Species(_) = cell_type {
+                    let message = format!(
+                        "The last {:?} died at tick {}",
+                        cell_type,
+                        simulation.ticks()
+                    );
+                    events.push(("Extinction".to_string(), message));
+                }
+            }
+            if statistics.peak_population > peak_population
+                && simulation.ticks() > RECORD_WARMUP_TICKS
+            {
+                let message = format!("{} cells alive at once", statistics.peak_population);
+                events.push(("Population record".to_string(), message));
+            }
+
+            if let Some(period) = simulation.cycle {
+                tracing::info!("Stuck in a {} tick cycle, reseeding", period);
+                let message = format!("Stuck in a {} tick cycle", period);
+                events.push(("Reseeded".to_string(), message));
+                simulation.reset_simulation();
+            } else if simulation.is_extinct() {
+                events.push(("Reseeded".to_string(), "Everything died out".to_string()));
+                simulation.reset_simulation();
+            }
+        }
+
+        for (title, message) in events {
+            self.notifier.notify(&title, &message);
+        }
+    }
+
+    // Apply the simulation parameters from the config file if it has been edited
+    fn reload_config(&mut self) {
+        if self.last_config_check.elapsed() < CONFIG_CHECK_INTERVAL {
+            return;
+        }
+        self.last_config_check = Instant::now();
+        self.check_visibility();
+        self.update_accent();
+        self.update_day_night();
+
+        let modified = Config::modified();
+        if modified != self.config_modified {
+            self.config_modified = modified;
+            let config = Config::load();
+            for screen in &mut self.screens {
+                screen.simulation.set_config(&config.simulation);
+            }
+            self.scheduler.ticks_per_second = config.ticks_per_second;
+            self.scheduler.turbo = config.turbo;
+            match (&mut self.throttle, config.throttle) {
+                (Some(throttle), true) => throttle.busy_percent = config.busy_cpu_percent,
+                (None, true) => self.throttle = Some(Throttle::new(config.busy_cpu_percent)),
+                (_, false) => {
+                    self.throttle = None;
+                    self.scheduler.speed = 1.0;
+                }
+            }
+            match (&mut self.audio, config.audio) {
+                (Some(audio), Some(settings)) => audio.config = settings,
+                (None, Some(settings)) => self.audio = Some(AudioListener::start(settings)),
+                (_, None) => self.audio = None,
+            }
+            match (&mut self.usage, config.system_monitor) {
+                (Some(usage), Some(settings)) => usage.config = settings,
+                (None, Some(settings)) => self.usage = Some(UsageMonitor::new(settings)),
+                (_, None) => self.usage = None,
+            }
+            match (&mut self.network, config.network) {
+                (Some((_, packets)), Some(settings)) => packets.config = settings,
+                (None, Some(settings)) => {
+                    self.network = Some((NetworkMeter::new(), Packets::new(settings)))
+                }
+                (_, None) => self.network = None,
+            }
+            match (&mut self.particles, config.particles) {
+                (Some(particles), Some(settings)) => particles.config = settings,
+                (None, Some(settings)) => self.particles = Some(Particles::new(settings)),
+                (_, None) => self.particles = None,
+            }
+            // Deaths are only kept while there are particles to show them with
+            for screen in &mut self.screens {
+                let deaths = &mut screen.simulation.deaths;
+                if deaths.is_some() != self.particles.is_some() {
+                    *deaths = self.particles.as_ref().map(|_| Vec::new());
+                }
+            }
+            // A different world scale resizes the worlds, anything else just changes
+            // how the camera moves
+            let settings = config.camera.filter(|_| !self.per_monitor);
+            let scale = settings
+                .as_ref()
+                .map(|settings| settings.world_scale.max(1));
+            match (&mut self.camera, settings) {
+                (Some(camera), Some(settings)) if scale == Some(camera.scale()) => {
+                    camera.config = settings
+                }
+                (None, None) => {}
+                (_, settings) => self.set_camera(settings.map(Camera::new)),
+            }
+            self.fade_frames = config.fade_frames;
+            self.typing = config.typing;
+            self.clock = config.clock;
+            self.clock_24_hour = config.clock_24_hour;
+            self.pause_when_covered = config.pause_when_covered;
+            self.notifier.enabled = config.notifications;
+            if config.opacity.clamp(0.0, 1.0) != self.simulation_parameters_uniform.opacity {
+                self.set_opacity(config.opacity);
+            }
+            if config.cell_gap != self.cell_gap {
+                self.cell_gap = config.cell_gap;
+                self.simulation_parameters_uniform.inset =
+                    shape::inset(config.cell_gap, self.cell_size);
+                self.write_simulation_parameters();
+                self.redraw_requested = true;
+            }
+            if config.cell_size.max(1) != self.cell_size {
+                self.set_cell_size(config.cell_size);
+            }
+            if config.day_night.is_some() {
+                self.day_night = config.day_night;
+            }
+            if config.shader != self.shader {
+                self.shader = config.shader;
+                self.load_shader();
+            }
+            if config.zoom != self.zoom_setting {
+                self.zoom_setting = config.zoom;
+                self.set_zoom(config.zoom);
+            }
+            let mirrors = kaleidoscope::mirrors(config.kaleidoscope);
+            if mirrors != self.simulation_parameters_uniform.mirrors {
+                self.simulation_parameters_uniform.mirrors = mirrors;
+                self.write_simulation_parameters();
+                self.redraw_requested = true;
+            }
+            if config.post_processing != self.post_effects {
+                self.post_effects = config.post_processing;
+                self.update_post_process();
+            }
+        }
+    }
+
+    fn is_asleep(&self) -> bool {
+        self.hidden || self.locked || self.screensaver || self.covered
+    }
+
+    // Hide the background to show the normal desktop, or bring it back
+    fn toggle_visible(&mut self) {
+        self.hidden = !self.hidden;
+        self.window.set_visible(!self.hidden);
+        self.window.request_redraw();
+    }
+
+    fn set_locked(&mut self, locked: bool) {
+        self.locked = locked;
+        // Start drawing again, in case that stopped while it was locked
+        self.window.request_redraw();
+    }
+
+    fn check_visibility(&mut self) {
+        // The screensaver running and covering the desktop is this, as the screensaver
+        if self.mode != Mode::Background {
+            return;
+        }
+        self.screensaver = session::screensaver_running();
+        self.covered = self.pause_when_covered && coverage::desktop_covered();
+    }
+
+    // Explorer takes the WorkerW window with it when it crashes or restarts, so wait
+    // for the new one and move back into it
+    fn check_desktop(&mut self) {
+        if !self.attached || self.last_desktop_check.elapsed() < DESKTOP_CHECK_INTERVAL {
+            return;
+        }
+        self.last_desktop_check = Instant::now();
+        let Some(hwnd) = window_hwnd(self.window) else {
+            return;
+        };
+        if !layeredwindow::parent_lost(hwnd) {
+            return;
+        }
+        tracing::warn!("Lost the desktop window, attaching to it again");
+        if attach_to_desktop(self.window) {
+            self.redraw_requested = true;
+        }
+    }
+
+    // Throw away everything on the graphics card and build it again
+    fn restart_gpu(&mut self) -> Result<(), String> {
+        let now = Instant::now();
+        self.restarts
+            .retain(|restart| now.duration_since(*restart) < RESTART_WINDOW);
+        if self.restarts.len() >= MAX_RESTARTS {
+            return Err(format!(
+                "The graphics device failed {} times in a row",
+                MAX_RESTARTS + 1
+            ));
+        }
+        self.restarts.push(now);
+        tracing::warn!("Restarting the graphics device");
+
+        #[cfg(target_os = "windows")]
+        attach_to_desktop(self.window);
+        // The old surface has to go before a new one can take over the window
+        self.gpu = None;
+        let gpu = pollster::block_on(Gpu::new(
+            self.window,
+            self.size,
+            self.simulation_parameters_uniform,
+            &self.sprite,
+            self.gpu_choice.as_deref(),
+            self.show_wallpaper,
+            &self.post_effects,
+        ));
+        (self.gpu, self.canvas) = Self::software_fallback(self.window, gpu)?;
+        if self.shader.is_some() {
+            self.load_shader();
+        }
+        self.resize(self.size);
+        self.redraw_requested = true;
+        Ok(())
+    }
+
+    // Draw with GDI when the graphics card can't be used at all
+    fn software_fallback(
+        window: &Window,
+        gpu: Result<Gpu<'a>, String>,
+    ) -> Result<(Option<Gpu<'a>>, Option<Canvas>), String> {
+        let e = match gpu {
+            Ok(gpu) => return Ok((Some(gpu), None)),
+            Err(e) => e,
+        };
+        let hwnd = window_hwnd(window).ok_or(e.clone())?;
+        tracing::warn!("{} Drawing without the graphics card instead.", e);
+        Ok((None, Some(Canvas::new(hwnd))))
+    }
+
+    fn is_gpu_lost(&self) -> bool {
+        self.gpu
+            .as_ref()
+            .is_some_and(|gpu| gpu.lost.load(Ordering::Relaxed))
+    }
+
+    fn is_throttled(&self) -> bool {
+        self.throttle.as_ref().is_some_and(Throttle::is_throttled)
+    }
+
+    // Run the simulation for this frame, returning whether anything changed
+    fn update(&mut self) -> bool {
+        self.reload_config();
+        self.check_shader();
+        self.check_desktop();
+        if self.last_icon_check.elapsed() >= ICON_CHECK_INTERVAL {
+            self.update_icons();
+        }
+        self.update_tooltip();
+        let throttle_speed = self.throttle.as_mut().map_or(1.0, Throttle::update);
+        // The simulation speeds up as it fades in and slows down as it fades out
+        let faded = self.update_fade();
+        self.scheduler.speed = throttle_speed * self.update_audio() * self.fade_level as f64;
+        self.update_usage();
+        self.update_network();
+        self.update_clock();
+        self.update_cursor();
+
+        let moved = self.update_camera() | faded;
+
+        self.scheduler.start_frame();
+        let mut ticked = std::mem::take(&mut self.redraw_requested);
+        while !self.is_paused() && self.scheduler.tick() {
+            self.advance_simulation();
+            ticked = true;
+        }
+        if std::mem::take(&mut self.step_requested) {
+            self.advance_simulation();
+            ticked = true;
+        }
+        // Layers run at their own speed, but follow the tray's speed and pausing
+        let paused = self.is_paused();
+        for layer in &mut self.layers {
+            layer.scheduler.multiplier = self.scheduler.multiplier;
+            layer.scheduler.speed = self.scheduler.speed;
+            if paused {
+                layer.scheduler.start_frame();
+            } else {
+                ticked |= layer.update();
+            }
+        }
+        // Particles move every frame, not just when the simulation ticks
+        ticked |= self.update_particles();
+        // Nothing changed, so the buffers from last frame can be drawn again, from
+        // wherever the camera is now
+        if !ticked {
+            return moved;
+        }
+        self.stream_stats();
+
+        // fill in the grid, placing each world over its part of the window
+        let (grid_width, grid_height) = self.grid_size();
+        let fade_step = match self.fade_frames {
+            0 => 1.0,
+            frames => 1.0 / frames as f32,
+        };
+        let mut cells = std::mem::take(&mut self.spare_cells);
+        cells.clear();
+        cells.resize(grid_width * grid_height, [0; 4]);
+        let mut agent_instances = Vec::new();
+        for screen in &self.screens {
+            let (offset_x, offset_y) = screen.offset(self.size.height, self.cell_size);
+            let simulation = &screen.simulation;
+            let world = simulation.world();
+            let trail_color = simulation.automaton.trail_color();
+            for (cell_idx, cell) in world.cells().enumerate() {
+                let (x, y) = world.get_cell_x_y(cell_idx);
+                let (x, y) = (offset_x + x, offset_y + y);
+                if x >= grid_width || y >= grid_height {
+                    continue;
+                }
+
+                let automaton = &simulation.automaton;
+                let mut color = match cell.cell_type {
+                    CellType::Wall => Some((WALL_COLOR, Blend::default())),
+                    _ => automaton
+                        .color(&cell, simulation.ticks())
+                        .map(|color| (color, automaton.blend(&cell))),
+                };
+
+                // Cells without anything in them show the trail layer, faded by its strength
+                if let (None, Some(trail_color)) = (color, trail_color) {
+                    let strength = world.trail[cell_idx].min(1.0);
+                    if strength > 0.05 {
+                        color = Some((trail_color.map(|c| c * strength), Blend::default()));
+                    }
+                }
+
+                // Anything left shows the heatmap underneath
+                if let (None, Some(heatmap)) = (color, &simulation.heatmap) {
+                    color = heatmap
+                        .color(cell_idx)
+                        .map(|color| (color, Blend::default()));
+                }
+
+                // Cells that just died keep their last color for a few updates as it fades
+                let (last_color, decay, last_blend) = &mut self.fading[x * grid_height + y];
+                let (color, decay, blend) = match color {
+                    Some((color, blend)) => {
+                        (*last_color, *decay, *last_blend) = (color, 0.0, blend);
+                        (color, 0.0, blend)
+                    }
+                    None => {
+                        *decay += fade_step;
+                        if *decay >= 1.0 {
+                            continue;
+                        }
+                        (*last_color, *decay, *last_blend)
+                    }
+                };
+
+                let color = color.map(|c| c * self.brightness);
+                cells[y * grid_width + x] = blended_pixel(color, blend, 1.0 - decay);
+            }
+
+            agent_instances.extend(simulation.agents().map(|agent| AgentInstance {
+                position: [
+                    agent.position[0] + offset_x as f32,
+                    agent.position[1] + offset_y as f32,
+                ],
+                heading: agent.velocity[1].atan2(agent.velocity[0]),
+                color: agent.color.map(|c| c * self.brightness),
+            }));
+        }
+
+        // Layers go on top, blended with what's underneath by their opacity
+        for layer in &self.layers {
+            let simulation = &layer.simulation;
+            let world = simulation.world();
+            for (cell_idx, cell) in world.cells().enumerate() {
+                let (x, y) = world.get_cell_x_y(cell_idx);
+                if x >= grid_width || y >= grid_height {
+                    continue;
+                }
+                let Some(color) = simulation.automaton.color(&cell, simulation.ticks()) else {
+                    continue;
+                };
+                let color = color.map(|c| c * self.brightness);
+                let blend = simulation.automaton.blend(&cell);
+                let pixel = &mut cells[y * grid_width + x];
+                *pixel = blend_pixel(*pixel, blended_pixel(color, blend, layer.opacity));
+            }
+            agent_instances.extend(simulation.agents().map(|agent| AgentInstance {
+                position: agent.position,
+                heading: agent.velocity[1].atan2(agent.velocity[0]),
+                color: agent.color.map(|c| c * self.brightness),
+            }));
+        }
+        // Packets fly over the window rather than the world, so they go where the
+        // camera does
+        if let Some((_, packets)) = &self.network {
+            let camera = self.camera.as_ref().map_or([0.0; 2], Camera::position);
+            agent_instances.extend(packets.agents.iter().map(|agent| AgentInstance {
+                position: [agent.position[0] + camera[0], agent.position[1] + camera[1]],
+                heading: agent.velocity[1].atan2(agent.velocity[0]),
+                color: agent.color.map(|c| c * self.brightness),
+            }));
+        }
+        if let Some(gpu) = &mut self.gpu {
+            gpu.fit_agent_buffer(agent_instances.len());
+            agent_instances.truncate(gpu.agent_capacity());
+        }
+        self.particle_instances = self
+            .particles
+            .iter()
+            .flat_map(|particles| {
+                particles.particles.iter().map(|particle| ParticleInstance {
+                    position: particle.position,
+                    velocity: particle.velocity,
+                    color: particle.color.map(|c| c * self.brightness),
+                    fade: particles.fade(particle),
+                })
+            })
+            .collect();
+        let regions = changed_regions(&self.cells, &cells, grid_width);
+        self.spare_cells = std::mem::replace(&mut self.cells, cells);
+        self.agent_instances = agent_instances;
+
+        // upload the parts of the grid that changed, the agents and the particles
+        if let Some(gpu) = &mut self.gpu {
+            gpu.upload_grid(&self.cells, grid_width as u32, &regions);
+            gpu.queue.write_buffer(
+                &gpu.agent_instance_buffer,
+                0,
+                bytemuck::cast_slice(&self.agent_instances),
+            );
+            gpu.queue.write_buffer(
+                &gpu.particle_instance_buffer,
+                0,
+                bytemuck::cast_slice(&self.particle_instances),
+            );
+        }
+        true
+    }
+
+    // Fill in the canvas the way the shader would, leaving out cell shapes
+    fn render_software(&mut self) {
+        let grid_size = self.grid_size();
+        let (view_width, view_height) = self.view_size();
+        // Taken out while it's drawn on, to work out which cell goes where
+        let Some(mut canvas) = self.canvas.take() else {
+            return;
+        };
+        // The window shows the part of the grid the camera is over, zoomed in and
+        // mirrored by the kaleidoscope
+        let uniform = &self.simulation_parameters_uniform;
+        let center = uniform.view.map(|cells| cells / 2.0);
+        let opacity = uniform.opacity * self.fade_level;
+        canvas.clear(view_width, view_height);
+        for y in 0..view_height {
+            for x in 0..view_width {
+                let (grid_x, grid_y) = self.grid_cell([x as f32 + 0.5, y as f32 + 0.5]);
+                let Some(pixel) = self.cells.get(grid_y * grid_size.0 + grid_x) else {
+                    continue;
+                };
+                // Nothing shows through, so turning the opacity down dims the cells
+                if *pixel != [0; 4] {
+                    let color = [pixel[0], pixel[1], pixel[2]];
+                    canvas.set(x, y, color.map(|c| c as f32 / 255.0 * opacity));
+                }
+            }
+        }
+        let size = [grid_size.0 as f32, grid_size.1 as f32];
+        // Agents and particles are a single pixel each
+        let agents = self
+            .agent_instances
+            .iter()
+            .map(|agent| (agent.position, agent.color));
+        let particles = self.particle_instances.iter().map(|particle| {
+            let color = particle.color.map(|c| c * particle.fade);
+            (particle.position, color)
+        });
+        for (position, color) in agents.chain(particles) {
+            // Agents behind the camera come round again from the far side of the world
+            let point = [0, 1].map(|axis| {
+                let position = position[axis] - uniform.camera[axis];
+                let position = if position < 0.0 {
+                    position + size[axis]
+                } else {
+                    position
+                };
+                position + 0.5 - center[axis]
+            });
+            for image in kaleidoscope::images(point, uniform.mirrors) {
+                let [x, y] = [0, 1].map(|axis| {
+                    let position = center[axis] + image[axis] * uniform.zoom;
+                    position.floor().max(0.0) as usize
+                });
+                canvas.set(x, y, color.map(|c| c * opacity));
+            }
+        }
+
+        let hidden = self
+            .monitors
+            .iter()
+            .zip(&self.excluded)
+            .filter(|(_, &excluded)| excluded)
+            .map(|(monitor, _)| *monitor)
+            .collect::<Vec<_>>();
+        canvas.present(self.cell_size, self.size.height, &hidden);
+        self.canvas = Some(canvas);
+    }
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        let Some(gpu) = &self.gpu else {
+            self.render_software();
+            return Ok(());
+        };
+        let output = gpu.surface.get_current_texture()?;
+        let view = output
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+        self.draw(gpu, &mut encoder, &view, 1);
+        gpu.queue.submit(iter::once(encoder.finish()));
+        output.present();
+
+        Ok(())
+    }
+
+    // Record drawing the wallpaper, cells and agents onto `view`, which is `scale`
+    // times the size of the window, then the post-processing effects over them
+    fn draw(
+        &self,
+        gpu: &Gpu,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        scale: u32,
+    ) {
+        // Photos are bigger than the window, so they need textures of their own
+        let scaled;
+        let post_process = match scale {
+            1 => gpu.post_process.as_ref(),
+            _ => {
+                let size = (gpu.config.width * scale, gpu.config.height * scale);
+                let format = gpu.config.format;
+                scaled = PostProcess::new(&gpu.device, format, size, &self.post_effects, scale);
+                scaled.as_ref()
+            }
+        };
+        match post_process {
+            Some(post_process) => {
+                self.draw_cells(gpu, encoder, post_process.scene(), scale);
+                post_process.apply(encoder, view);
+            }
+            None => self.draw_cells(gpu, encoder, view, scale),
+        }
+    }
+
+    fn draw_cells(
+        &self,
+        gpu: &Gpu,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        scale: u32,
+    ) {
+        let visible = self.visible_monitors();
+        if let Some(wallpaper) = &gpu.wallpaper {
+            let crops = visible
+                .iter()
+                .take(MAX_WALLPAPER_MONITORS)
+                .map(|monitor| wallpaper.crop(monitor))
+                .collect::<Vec<_>>();
+            gpu.queue
+                .write_buffer(&wallpaper.crop_buffer, 0, bytemuck::cast_slice(&crops));
+        }
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: 0.0,
+                            g: 0.0,
+                            b: 0.0,
+                            a: 0.0,
+                        }),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            // Only the monitors that haven't been left out are drawn on
+            for (i, monitor) in visible.iter().enumerate() {
+                let x = monitor.x.min(gpu.config.width);
+                let y = monitor.y.min(gpu.config.height);
+                let width = monitor.width.min(gpu.config.width - x);
+                let height = monitor.height.min(gpu.config.height - y);
+                if width == 0 || height == 0 {
+                    continue;
+                }
+                let (x, y, width, height) = (x * scale, y * scale, width * scale, height * scale);
+                render_pass.set_scissor_rect(x, y, width, height);
+
+                let wallpaper = gpu
+                    .wallpaper
+                    .as_ref()
+                    .filter(|_| i < MAX_WALLPAPER_MONITORS);
+                if let Some(wallpaper) = wallpaper {
+                    render_pass.set_viewport(
+                        x as f32,
+                        y as f32,
+                        width as f32,
+                        height as f32,
+                        0.0,
+                        1.0,
+                    );
+                    render_pass.set_pipeline(&wallpaper.pipeline);
+                    render_pass.set_bind_group(0, &wallpaper.bind_group, &[]);
+                    render_pass.set_vertex_buffer(0, wallpaper.crop_buffer.slice(..));
+                    let i = i as u32;
+                    render_pass.draw(0..6, i..i + 1);
+                    // The cells are laid out over the whole window
+                    render_pass.set_viewport(
+                        0.0,
+                        0.0,
+                        (gpu.config.width * scale) as f32,
+                        (gpu.config.height * scale) as f32,
+                        0.0,
+                        1.0,
+                    );
+                }
+
+                // One triangle covering the window draws every cell
+                render_pass.set_pipeline(&gpu.render_pipeline);
+                render_pass.set_bind_group(0, &gpu.simulation_parameters_uniform_bind_group, &[]);
+                render_pass.set_bind_group(1, &gpu.grid_bind_group, &[]);
+                render_pass.draw(0..3, 0..1);
+
+                // Agents are drawn over the top of the grid
+                render_pass.set_pipeline(&gpu.agent_render_pipeline);
+                render_pass.set_vertex_buffer(0, gpu.agent_vertex_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, gpu.agent_instance_buffer.slice(..));
+                // Once for each mirror image
+                let mirrors = self.simulation_parameters_uniform.mirrors;
+                render_pass.draw(
+                    0..AGENT_VERTICES.len() as u32 * mirrors,
+                    0..self.agent_instances.len() as _,
+                );
+
+                // Then the particles over them
+                render_pass.set_pipeline(&gpu.particle_render_pipeline);
+                render_pass.set_vertex_buffer(0, gpu.particle_vertex_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, gpu.particle_instance_buffer.slice(..));
+                render_pass.draw(
+                    0..PARTICLE_VERTICES.len() as u32 * mirrors,
+                    0..self.particle_instances.len() as _,
+                );
+            }
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
+// `preset` is a shared preset string to switch to once it's running
+pub async fn run(mode: Mode, preset: Option<String>) {
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+            console_log::init_with_level(log::Level::Warn).expect("Could't initialize logger");
+        } else {
+            logfile::init();
+        }
+    }
+    // Everything logged from here on is tagged with the mode the app is running in
+    let _span = tracing::info_span!("app", ?mode).entered();
+
+    if mode == Mode::Configure {
+        return screensaver::configure();
+    }
+
+    let mut config = Config::load();
+
+    let event_loop = match EventLoopBuilder::<UserEvents>::with_user_event().build() {
+        Ok(event_loop) => event_loop,
+        Err(e) => return show_error(&format!("Couldn't start the event loop: {}", e)),
+    };
+    let proxy = event_loop.create_proxy();
+    let session_proxy = event_loop.create_proxy();
+    let paint_proxy = event_loop.create_proxy();
+    let hotkey_proxy = event_loop.create_proxy();
+    let key_proxy = event_loop.create_proxy();
+    let control_proxy = event_loop.create_proxy();
+
+    // The window covers every monitor, wherever they are laid out
+    let mut monitors = monitor_rects(event_loop.available_monitors(), config.monitor_layout);
+    // Recordings are left to the background
+    if mode != Mode::Background {
+        config.record = None;
+    }
+    let window_size = match mode {
+        // The preview starts afresh with its cells shrunk down to match, since the
+        // saved world wouldn't fit
+        Mode::Preview(parent) => {
+            let Some((width, height)) = screensaver::preview_size(parent as HWND) else {
+                return;
+            };
+            let (desktop_width, _) = desktop_size(&monitors);
+            config.cell_size = (config.cell_size * width / desktop_width.max(1)).max(1);
+            config.autosave = false;
+            Some((width, height))
+        }
+        Mode::Windowed => Some(WINDOWED_SIZE),
+        _ => None,
+    };
+    // These are a single screen, without monitors to leave out or icons to avoid
+    if let Some((width, height)) = window_size {
+        monitors = vec![PixelRect {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        }];
+        config.per_monitor = false;
+        config.exclude_monitors.clear();
+        config.avoid_icons = false;
+    }
+    // The camera moves across one world, so it can't be used with one on each monitor
+    if config.per_monitor && config.camera.is_some() {
+        tracing::warn!("The camera can't be used with per_monitor, leaving it off");
+        config.camera = None;
+    }
+    // As the screensaver, the window goes over the whole desktop, which starts above
+    // or to the left of the primary monitor when there are others there. A window of
+    // its own goes wherever Windows puts it.
+    let position = match mode {
+        Mode::Screensaver => {
+            let positions = event_loop
+                .available_monitors()
+                .map(|monitor| monitor.position());
+            Some(
+                positions.fold(PhysicalPosition::new(0, 0), |corner, position| {
+                    PhysicalPosition::new(corner.x.min(position.x), corner.y.min(position.y))
+                }),
+            )
+        }
+        Mode::Windowed => None,
+        _ => Some(PhysicalPosition::new(0, 0)),
+    };
+
+    let icon = include_bytes!("../../../desktop_automata_icon.ico");
+    // let icon1 = Icon::from_buffer(icon, None, None).unwrap(); // (width, height)
+
+    let themes = themes(&config);
+    let presets = presets::load();
+    let menu = tray_menu(&MenuChecks {
+        themes: &themes,
+        theme: themes.iter().position(|theme| theme.name == config.theme),
+        automaton: config.replay.is_none().then_some(config.automaton),
+        paused: false,
+        speed: 100,
+        opacity: config.opacity,
+        cell_size: config.cell_size,
+        brush: Brush::default(),
+        shown: (0..monitors.len())
+            .map(|index| !config.exclude_monitors.contains(&(index + 1)))
+            .collect(),
+        drifting: config.camera.as_ref().map(|_| true),
+        presets: &presets,
+        centered: false,
+    });
+
+    // The screensaver ends when it's touched, so it has no need for a tray icon
+    let trayicon = match mode {
+        Mode::Background => match TrayIconBuilder::new()
+            .sender(move |e: &UserEvents| {
+                let _ = proxy.send_event(e.clone());
+            })
+            .icon_from_buffer(icon)
+            .tooltip("Automata")
+            .on_click(UserEvents::LeftClickTrayIcon)
+            .on_right_click(UserEvents::RightClickTrayIcon)
+            .on_double_click(UserEvents::DoubleClickTrayIcon)
+            .menu(menu)
+            .build()
+        {
+            Ok(trayicon) => Some(trayicon),
+            Err(e) => return show_error(&format!("Couldn't add the tray icon: {:?}", e)),
+        },
+        _ => None,
+    };
+
+    let windowed = mode == Mode::Windowed;
+    let mut window_builder = WindowBuilder::new()
+        .with_title(match windowed {
+            true => "Automata Background",
+            false => "Transparent Overlay Window",
+        })
+        .with_decorations(windowed)
+        .with_transparent(!windowed)
+        // Set window to be on second monitor
+        .with_visible(false);
+    if let Some(position) = position {
+        window_builder = window_builder.with_position(position);
+    }
+    let window = match window_builder.build(&event_loop) {
+        Ok(window) => window,
+        Err(e) => return show_error(&format!("Couldn't create the window: {}", e)),
+    };
+
+    let (monitor_width, monitor_height) = desktop_size(&monitors);
+
+    // set the size of the window
+    let _ = window.request_inner_size(PhysicalSize::new(monitor_width, monitor_height));
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        // Winit prevents sizing with CSS, so we have to set
+        // the size manually when on web.
+        use winit::dpi::PhysicalSize;
+        let _ = window.request_inner_size(PhysicalSize::new(450, 400));
+
+        use winit::platform::web::WindowExtWebSys;
+        web_sys::window()
+            .and_then(|win| win.document())
+            .and_then(|doc| {
+                let dst = doc.get_element_by_id("wasm-example")?;
+                let canvas = web_sys::Element::from(window.canvas()?);
+                dst.append_child(&canvas).ok()?;
+                Some(())
+            })
+            .expect("Couldn't append canvas to document body.");
+    }
+
+    #[cfg(target_os = "windows")]
+    if mode == Mode::Background {
+        use winit::platform::windows::WindowExtWindows;
+        attach_to_desktop(&window);
+        if let Some(hwnd) = window_hwnd(&window) {
+            session::watch_lock(hwnd, move |locked| {
+                let _ = session_proxy.send_event(UserEvents::SessionLocked(locked));
+            });
+            if config.hotkeys {
+                hotkeys::register(hwnd, config.camera.is_some(), move |hotkey| {
+                    let event = match hotkey {
+                        Hotkey::Pause => UserEvents::TogglePause,
+                        Hotkey::Reseed => UserEvents::Reset,
+                        Hotkey::NextAutomaton => UserEvents::NextAutomaton,
+                        Hotkey::Screenshot => UserEvents::Screenshot,
+                        Hotkey::PanLeft => UserEvents::Pan(-1, 0),
+                        Hotkey::PanRight => UserEvents::Pan(1, 0),
+                        Hotkey::PanUp => UserEvents::Pan(0, 1),
+                        Hotkey::PanDown => UserEvents::Pan(0, -1),
+                        Hotkey::ZoomIn => UserEvents::Zoom(1),
+                        Hotkey::ZoomOut => UserEvents::Zoom(-1),
+                    };
+                    let _ = hotkey_proxy.send_event(event);
+                });
+            }
+        }
+        mouse::watch_painting(move |x, y| {
+            let _ = paint_proxy.send_event(UserEvents::Paint(x, y));
+        });
+        if config.typing > 0 {
+            keyboard::watch_typing(move || {
+                let _ = key_proxy.send_event(UserEvents::Keystroke);
+            });
+        }
+        window.set_window_level(winit::window::WindowLevel::AlwaysOnBottom);
+        window.set_ime_allowed(false);
+        if let Err(e) = window.set_cursor_hittest(false) {
+            tracing::warn!("Couldn't let clicks through to the desktop: {}", e);
+        }
+
+        window.set_enable(false);
+        window.set_visible(true);
+    }
+    #[cfg(target_os = "windows")]
+    match mode {
+        Mode::Screensaver => {
+            window.set_window_level(winit::window::WindowLevel::AlwaysOnTop);
+            window.set_cursor_visible(false);
+            window.set_visible(true);
+            window.focus_window();
+        }
+        Mode::Preview(parent) => {
+            use winit::platform::windows::WindowExtWindows;
+            let embedded =
+                window_hwnd(&window).is_some_and(|hwnd| screensaver::embed(hwnd, parent as HWND));
+            if !embedded {
+                tracing::warn!("Couldn't show the screensaver preview");
+                return;
+            }
+            // Clicks go to the settings around the preview
+            window.set_enable(false);
+            window.set_visible(true);
+        }
+        Mode::Windowed => window.set_visible(true),
+        _ => {}
+    }
+
+    // Panics are logged here, then caught around each frame
+    panic::set_hook(Box::new(|info| tracing::error!("{}", info)));
+
+    // State::new uses async code, so we're going to wait for it to finish
+    let mut state =
+        match State::new(&window, trayicon, mode, &config, themes, presets, monitors).await {
+            Ok(state) => state,
+            Err(e) => return show_error(&e),
+        };
+    if let Some(preset) = preset {
+        if let Err(e) = state.import_preset(&preset) {
+            tracing::warn!("Couldn't use the preset from the command line: {}", e);
+        }
+    }
+    // The pipe and the HTTP server hand their commands to the event loop the same way
+    let (requests, receiver) = std::sync::mpsc::channel();
+    let wake = move || {
+        let _ = control_proxy.send_event(UserEvents::Control);
+    };
+    // The screensaver leaves commands to the background, so it can run alongside it
+    let serving = matches!(mode, Mode::Background | Mode::Windowed);
+    if config.control_pipe && serving {
+        control::serve(requests.clone(), wake.clone());
+    }
+    if let (Some(port), true) = (config.http_port, serving) {
+        #[cfg(feature = "http")]
+        http::serve(
+            port,
+            requests.clone(),
+            wake.clone(),
+            state.subscribers.clone(),
+        );
+        #[cfg(not(feature = "http"))]
+        tracing::warn!(
+            "Port {} is set for HTTP, but this build has no HTTP server",
+            port
+        );
+    }
+    state.requests = Some(receiver);
+    let mut surface_configured = false;
+    let mut dismissal = (mode == Mode::Screensaver).then(screensaver::Dismissal::default);
+
+    let result = event_loop.run(move |event, control_flow| {
+        match event {
+            Event::UserEvent(event) => {
+                match event {
+                    UserEvents::LeftClickTrayIcon => state.toggle_visible(),
+                    UserEvents::RightClickTrayIcon => {
+                        if let Some(trayicon) = &mut state.trayicon {
+                            let _ = trayicon.show_menu();
+                        }
+                    }
+                    UserEvents::DoubleClickTrayIcon => {}
+                    // Fade out first, closing once everything has gone
+                    UserEvents::Exit => {
+                        if !state.leave() {
+                            control_flow.exit();
+                        }
+                    }
+                    UserEvents::TogglePause => state.toggle_pause(),
+                    UserEvents::Reset => state.reset(),
+                    UserEvents::Step => state.step(),
+                    UserEvents::Rewind => state.rewind(),
+                    UserEvents::ExportPattern => state.export_pattern(),
+                    UserEvents::Screenshot => state.screenshot(),
+                    UserEvents::Photo(scale) => state.photo(scale),
+                    UserEvents::SaveWorld => state.save_world(),
+                    UserEvents::LoadWorld => state.load_world(),
+                    UserEvents::Automaton(kind) => state.set_automaton(kind),
+                    UserEvents::NextAutomaton => state.next_automaton(),
+                    UserEvents::Theme(index) => state.pick_theme(index),
+                    UserEvents::CellSize(size) => state.set_cell_size(size),
+                    UserEvents::Speed(percent) => state.set_speed(percent),
+                    UserEvents::Monitor(index) => state.toggle_monitor(index),
+                    UserEvents::ToggleAutostart => state.toggle_autostart(),
+                    UserEvents::OpenLog => logfile::open(),
+                    UserEvents::SessionLocked(locked) => state.set_locked(locked),
+                    UserEvents::Brush(brush) => state.set_brush(brush),
+                    UserEvents::Paint(x, y) => state.paint(x, y),
+                    UserEvents::Keystroke => state.keystroke(),
+                    UserEvents::Control => state.handle_requests(),
+                    UserEvents::Pan(x, y) => state.pan((x, y)),
+                    UserEvents::ToggleDrift => state.toggle_drift(),
+                    UserEvents::Zoom(steps) => state.zoom(steps),
+                    UserEvents::Opacity(percent) => state.pick_opacity(percent),
+                    UserEvents::Preset(index) => state.apply_preset(index),
+                    UserEvents::SavePreset => state.save_preset(),
+                    UserEvents::CopyPreset => state.copy_preset(),
+                    UserEvents::ImportPreset => state.import_preset_from_clipboard(),
+                    UserEvents::Pattern(index) => state.place_pattern(index),
+                    UserEvents::ToggleCentered => state.toggle_centered(),
+                }
+            }
+            // Woken up to check whether anything can be seen yet
+            Event::NewEvents(StartCause::ResumeTimeReached { .. }) => {
+                state.check_visibility();
+                state.window().request_redraw();
+            }
+            Event::LoopExiting { .. } => {
+                state.autosave();
+                if mode == Mode::Background {
+                    layeredwindow::send_cleanup_message();
+                }
+            }
+            Event::WindowEvent {
+                ref event,
+                window_id,
+            } if window_id == state.window().id() => {
+                if dismissal
+                    .as_mut()
+                    .is_some_and(|dismissal| dismissal.dismissed(event))
+                {
+                    control_flow.exit();
+                    return;
+                }
+                if !state.input(event) {
+                    match event {
+                        WindowEvent::Occluded(_) => {
+                            // unminimize the window
+                            if state.window.is_minimized().unwrap_or(false) {
+                                state.window.set_minimized(false);
+                            }
+                        }
+                        // The preview goes when the settings are closed
+                        WindowEvent::CloseRequested
+                        | WindowEvent::Destroyed
+                        | WindowEvent::KeyboardInput {
+                            event:
+                                KeyEvent {
+                                    state: ElementState::Pressed,
+                                    physical_key: PhysicalKey::Code(KeyCode::Escape),
+                                    ..
+                                },
+                            ..
+                        } => control_flow.exit(),
+                        WindowEvent::Resized(physical_size) => {
+                            surface_configured = true;
+                            state.resize(*physical_size);
+                        }
+                        WindowEvent::RedrawRequested => {
+                            if state.has_left() {
+                                control_flow.exit();
+                                return;
+                            }
+                            // Stop drawing until it can be seen again, checking back
+                            // every so often
+                            if state.is_asleep() {
+                                control_flow.set_control_flow(ControlFlow::wait_duration(
+                                    SLEEP_CHECK_INTERVAL,
+                                ));
+                                return;
+                            }
+                            control_flow.set_control_flow(ControlFlow::Wait);
+
+                            // This tells winit that we want another frame after this one
+                            state.window().request_redraw();
+
+                            if !surface_configured {
+                                return;
+                            }
+
+                            // A panic while stepping the simulation is a bug that
+                            // a new graphics device wouldn't fix, unless the device
+                            // was lost while the frame was uploaded
+                            let update = panic::catch_unwind(AssertUnwindSafe(|| state.update()));
+                            let changed = match update {
+                                Ok(changed) => changed,
+                                Err(_) if state.is_gpu_lost() => true,
+                                Err(panic) => {
+                                    let message = panic_message(panic.as_ref());
+                                    show_error(&format!("The simulation failed: {}", message));
+                                    control_flow.exit();
+                                    return;
+                                }
+                            };
+                            // A panic while drawing or a lost device restarts the
+                            // graphics side instead of leaving a black desktop.
+                            // Frames that haven't changed aren't drawn again while
+                            // the system is busy.
+                            let frame = match changed || !state.is_throttled() {
+                                true => panic::catch_unwind(AssertUnwindSafe(|| state.render())),
+                                false => Ok(Ok(())),
+                            };
+                            let frame = match frame {
+                                Ok(frame) if !state.is_gpu_lost() => frame,
+                                _ => {
+                                    if let Err(e) = state.restart_gpu() {
+                                        show_error(&e);
+                                        control_flow.exit();
+                                    }
+                                    return;
+                                }
+                            };
+                            match frame {
+                                Ok(_) => {}
+                                // Reconfigure the surface if it's lost or outdated
+                                Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                                    state.resize(state.size)
+                                }
+                                // The system is out of memory, we should probably quit
+                                Err(wgpu::SurfaceError::OutOfMemory) => {
+                                    tracing::error!("OutOfMemory");
+                                    control_flow.exit();
+                                }
+
+                                // This happens when the a frame takes too long to present
+                                Err(wgpu::SurfaceError::Timeout) => {
+                                    tracing::warn!("Surface timeout")
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    });
+    if let Err(e) = result {
+        show_error(&format!("The event loop stopped: {}", e));
+    }
+}
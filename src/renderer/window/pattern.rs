@@ -0,0 +1,55 @@
+use std::time::SystemTime;
+
+use crate::config::Config;
+use crate::game::patterns::LIBRARY;
+use crate::game::plaintext::{CellsPattern, Region};
+
+use super::{State, UserEvents};
+
+impl<'a> State<'a> {
+    // Stamp the pattern at `index` of the library into every monitor's simulation
+    pub(super) fn place_pattern(&mut self, index: usize) {
+        let Some(entry) = LIBRARY.get(index) else {
+            return;
+        };
+        let pattern = match entry.pattern() {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                tracing::warn!("Failed to load the {} pattern: {}", entry.name, e);
+                return;
+            }
+        };
+        let centered = self.centered_patterns;
+        for screen in &mut self.screens {
+            screen.simulation.place_pattern(&pattern, centered);
+        }
+        self.redraw_requested = true;
+    }
+
+    pub(super) fn toggle_centered(&mut self) {
+        self.centered_patterns = !self.centered_patterns;
+        self.check_menu_item(UserEvents::ToggleCentered, self.centered_patterns);
+    }
+
+    // Write everything on screen to a .cells file, one for each monitor's simulation
+    pub(super) fn export_pattern(&self) {
+        let seconds = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        for (index, screen) in self.screens.iter().enumerate() {
+            let world = screen.simulation.world();
+            let Some(region) = Region::bounding_box(world) else {
+                tracing::warn!("Nothing to export");
+                continue;
+            };
+
+            let path = Self::world_path(&format!("pattern-{}", seconds), index, "cells");
+            let _ = std::fs::create_dir_all(Config::directory());
+            match CellsPattern::from_region(world, region).save(&path) {
+                Ok(()) => tracing::info!("Exported pattern to {}", path.display()),
+                Err(e) => tracing::warn!("Failed to export {}: {}", path.display(), e),
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::renderer::clipboard;
+use crate::renderer::presets::{self, Preset};
+
+use super::{show_warning, State};
+
+impl<'a> State<'a> {
+    // Switch to everything the preset at `index` has saved
+    pub(super) fn apply_preset(&mut self, index: usize) {
+        let Some(preset) = self.presets.get(index).cloned() else {
+            return;
+        };
+        match self
+            .themes
+            .iter()
+            .position(|theme| theme.name == preset.theme)
+        {
+            // Like picking a theme by hand, this stops it changing with the time of day
+            Some(theme) => {
+                self.theme = theme;
+                self.day_night = None;
+            }
+            None => tracing::warn!(
+                "Preset {} has an unknown theme {}",
+                preset.name,
+                preset.theme
+            ),
+        }
+        self.rule = preset.rule;
+        // This colors the new automata in with the theme
+        self.set_automaton(preset.automaton);
+        self.set_speed(preset.speed);
+        if preset.cell_size != self.cell_size {
+            self.set_cell_size(preset.cell_size);
+        }
+    }
+
+    // Add what's running now to the presets file under a new name, which can be
+    // changed there
+    pub(super) fn save_preset(&mut self) {
+        // The file is read again so nothing changed in it since startup is lost
+        let mut presets = presets::load();
+        let name = presets::unused_name(&presets);
+        presets.push(self.current_preset(name.clone()));
+        if let Err(e) = presets::save(&presets) {
+            tracing::warn!("Failed to save {}: {}", presets::path().display(), e);
+            return;
+        }
+        tracing::info!("Saved the current settings as {}", name);
+        self.presets = presets;
+        self.rebuild_tray_menu();
+    }
+
+    // What's running now as a preset called `name`
+    fn current_preset(&self, name: String) -> Preset {
+        Preset {
+            name,
+            automaton: self.automaton,
+            rule: self.rule.clone(),
+            theme: self
+                .themes
+                .get(self.theme)
+                .map(|theme| theme.name.clone())
+                .unwrap_or_default(),
+            speed: self.speed,
+            cell_size: self.cell_size,
+        }
+    }
+
+    pub(super) fn copy_preset(&mut self) {
+        let mut preset = self.current_preset(presets::unused_name(&self.presets));
+        // Named after the saved preset it matches, if there is one
+        if let Some(saved) = self
+            .presets
+            .iter()
+            .find(|saved| saved.same_settings(&preset))
+        {
+            preset.name = saved.name.clone();
+        }
+        let text = presets::encode(&preset);
+        match text.and_then(|text| clipboard::copy(&text).map(|_| text)) {
+            Ok(text) => tracing::info!("Copied {} to the clipboard", text),
+            Err(e) => show_warning(&format!("Couldn't copy the preset: {}", e)),
+        }
+    }
+
+    pub(super) fn import_preset_from_clipboard(&mut self) {
+        let imported = clipboard::paste().and_then(|text| self.import_preset(&text));
+        if let Err(e) = imported {
+            show_warning(&format!(
+                "Copy a preset string starting with {} first.\n\n{}",
+                presets::URL_PREFIX,
+                e
+            ));
+        }
+    }
+
+    // Add a preset string someone shared to the presets file and switch to it
+    pub(super) fn import_preset(&mut self, text: &str) -> Result<(), String> {
+        let mut preset = presets::decode(text)?;
+        let mut presets = presets::load();
+        let index = match presets
+            .iter()
+            .position(|saved| saved.same_settings(&preset))
+        {
+            Some(index) => index,
+            None => {
+                if presets.iter().any(|saved| saved.name == preset.name) {
+                    preset.name = presets::unused_name(&presets);
+                }
+                tracing::info!("Imported preset {}", preset.name);
+                presets.push(preset);
+                // It's still worth switching to when it can't be kept
+                if let Err(e) = presets::save(&presets) {
+                    tracing::warn!("Failed to save {}: {}", presets::path().display(), e);
+                }
+                presets.len() - 1
+            }
+        };
+        self.presets = presets;
+        self.rebuild_tray_menu();
+        self.apply_preset(index);
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+use std::time::{Duration, Instant};
+
+use trayicon::MenuBuilder;
+
+use crate::config::Config;
+use crate::game::automaton::AutomatonKind;
+use crate::game::brush::Brush;
+use crate::game::palette::Theme;
+use crate::game::patterns::LIBRARY;
+use crate::renderer::accent;
+use crate::renderer::autostart;
+use crate::renderer::presets::Preset;
+use crate::renderer::wallpaper;
+
+use super::{State, UserEvents};
+
+// Cell sizes in pixels offered in the tray menu
+pub(super) const CELL_SIZES: [u32; 6] = [2, 4, 6, 8, 12, 16];
+// Speeds offered in the tray menu, as percentages of ticks_per_second
+const SPEEDS: [u32; 5] = [25, 50, 100, 200, 400];
+// Opacities offered in the tray menu, as percentages
+pub(super) const OPACITIES: [u32; 5] = [100, 80, 60, 40, 20];
+// Resolutions photos can be taken at, as multiples of the screen's
+const PHOTO_SCALES: [u32; 3] = [2, 4, 8];
+// How often the tray icon's tooltip is refreshed with the latest populations
+const TOOLTIP_INTERVAL: Duration = Duration::from_secs(3);
+
+// The themes in the tray menu, followed by ones made from the Windows accent color
+// and the wallpaper
+pub(super) fn themes(config: &Config) -> Vec<Theme> {
+    let mut themes = Theme::all(&config.themes);
+    if let Some(color) = accent::accent_color() {
+        themes.push(Theme::from_accent(color));
+    }
+    themes.extend(wallpaper::wallpaper_theme());
+    themes
+}
+
+// What the tray menu has ticked, so it can be built again as things change
+pub(super) struct MenuChecks<'a> {
+    pub(super) themes: &'a [Theme],
+    pub(super) theme: Option<usize>,
+    // None while a recording is playing instead
+    pub(super) automaton: Option<AutomatonKind>,
+    pub(super) paused: bool,
+    pub(super) speed: u32,
+    pub(super) opacity: f32,
+    pub(super) cell_size: u32,
+    pub(super) brush: Brush,
+    // Whether each monitor is shown, from left to right
+    pub(super) shown: Vec<bool>,
+    // Whether the camera is drifting, or None when it's off
+    pub(super) drifting: Option<bool>,
+    pub(super) presets: &'a [Preset],
+    // Whether patterns from the library go in the middle of the world
+    pub(super) centered: bool,
+}
+
+pub(super) fn tray_menu(checks: &MenuChecks) -> MenuBuilder<UserEvents> {
+    let theme_menu =
+        checks
+            .themes
+            .iter()
+            .enumerate()
+            .fold(MenuBuilder::new(), |menu, (index, theme)| {
+                menu.checkable(
+                    &theme.name,
+                    checks.theme == Some(index),
+                    UserEvents::Theme(index),
+                )
+            });
+
+    let automaton_menu = AutomatonKind::ALL
+        .iter()
+        .fold(MenuBuilder::new(), |menu, &kind| {
+            let checked = checks.automaton == Some(kind);
+            menu.checkable(kind.name(), checked, UserEvents::Automaton(kind))
+        });
+
+    let speed_menu = SPEEDS
+        .iter()
+        .fold(MenuBuilder::new(), |menu, &speed| {
+            let name = format!("{}x", speed as f64 / 100.0);
+            let checked = !checks.paused && speed == checks.speed;
+            menu.checkable(&name, checked, UserEvents::Speed(speed))
+        })
+        .checkable("Paused", checks.paused, UserEvents::Speed(0));
+
+    let opacity_menu = OPACITIES.iter().fold(MenuBuilder::new(), |menu, &percent| {
+        let name = format!("{}%", percent);
+        let checked = percent == (checks.opacity * 100.0).round() as u32;
+        menu.checkable(&name, checked, UserEvents::Opacity(percent))
+    });
+
+    let photo_menu = PHOTO_SCALES
+        .iter()
+        .fold(MenuBuilder::new(), |menu, &scale| {
+            menu.item(&format!("{}x", scale), UserEvents::Photo(scale))
+        });
+
+    let cell_size_menu = CELL_SIZES.iter().fold(MenuBuilder::new(), |menu, &size| {
+        let name = format!("{} px", size);
+        menu.checkable(&name, size == checks.cell_size, UserEvents::CellSize(size))
+    });
+
+    let brush_menu = Brush::ALL.iter().fold(MenuBuilder::new(), |menu, &brush| {
+        menu.checkable(
+            brush.name(),
+            brush == checks.brush,
+            UserEvents::Brush(brush),
+        )
+    });
+
+    let monitor_menu =
+        checks
+            .shown
+            .iter()
+            .enumerate()
+            .fold(MenuBuilder::new(), |menu, (index, &shown)| {
+                let name = format!("Monitor {}", index + 1);
+                menu.checkable(&name, shown, UserEvents::Monitor(index))
+            });
+
+    let preset_menu = checks
+        .presets
+        .iter()
+        .enumerate()
+        .fold(MenuBuilder::new(), |menu, (index, preset)| {
+            menu.item(&preset.name, UserEvents::Preset(index))
+        })
+        .separator()
+        .item("Save current settings", UserEvents::SavePreset)
+        .item("Copy as preset string", UserEvents::CopyPreset)
+        .item("Import preset string", UserEvents::ImportPreset);
+
+    // Kinds of pattern are kept apart by separators
+    let pattern_menu = LIBRARY
+        .iter()
+        .enumerate()
+        .fold(MenuBuilder::new(), |menu, (index, pattern)| {
+            let menu = match index > 0 && LIBRARY[index - 1].kind != pattern.kind {
+                true => menu.separator(),
+                false => menu,
+            };
+            menu.item(pattern.name, UserEvents::Pattern(index))
+        })
+        .separator()
+        .checkable("In the middle", checks.centered, UserEvents::ToggleCentered);
+
+    // Only there when the camera is on to be moved
+    let camera_menu = checks.drifting.map(|drifting| {
+        MenuBuilder::new()
+            .item("Left", UserEvents::Pan(-1, 0))
+            .item("Right", UserEvents::Pan(1, 0))
+            .item("Up", UserEvents::Pan(0, 1))
+            .item("Down", UserEvents::Pan(0, -1))
+            .separator()
+            .checkable("Drift", drifting, UserEvents::ToggleDrift)
+    });
+
+    MenuBuilder::new()
+        .checkable("Pause", checks.paused, UserEvents::TogglePause)
+        .item("Reset", UserEvents::Reset)
+        .item("Step", UserEvents::Step)
+        .item("Rewind", UserEvents::Rewind)
+        .item("Export pattern", UserEvents::ExportPattern)
+        .item("Screenshot", UserEvents::Screenshot)
+        .submenu("Photo", photo_menu)
+        .item("Save world", UserEvents::SaveWorld)
+        .item("Load world", UserEvents::LoadWorld)
+        .submenu("Presets", preset_menu)
+        .submenu("Automaton", automaton_menu)
+        .submenu("Speed", speed_menu)
+        .submenu("Theme", theme_menu)
+        .submenu("Opacity", opacity_menu)
+        .submenu("Cell size", cell_size_menu)
+        .submenu("Brush", brush_menu)
+        .submenu("Patterns", pattern_menu)
+        .submenu("Monitors", monitor_menu)
+        .when(|menu| match camera_menu {
+            Some(camera_menu) => menu.submenu("Camera", camera_menu),
+            None => menu,
+        })
+        .checkable(
+            "Start with Windows",
+            autostart::is_enabled(),
+            UserEvents::ToggleAutostart,
+        )
+        .item("Open log", UserEvents::OpenLog)
+        .separator()
+        .item("Exit", UserEvents::Exit)
+}
+
+impl<'a> State<'a> {
+    // Tick or untick an item in the tray menu, when there is one
+    pub(super) fn check_menu_item(&mut self, item: UserEvents, checked: bool) {
+        if let Some(trayicon) = &mut self.trayicon {
+            let _ = trayicon.set_menu_item_checkable(item, checked);
+        }
+    }
+
+    // Build the tray menu again from scratch, for when what's in it has changed
+    pub(super) fn rebuild_tray_menu(&mut self) {
+        let menu = tray_menu(&MenuChecks {
+            themes: &self.themes,
+            theme: Some(self.theme),
+            automaton: Some(self.automaton),
+            paused: self.is_paused(),
+            speed: self.speed,
+            opacity: self.simulation_parameters_uniform.opacity,
+            cell_size: self.cell_size,
+            brush: self.brush,
+            shown: self.excluded.iter().map(|excluded| !excluded).collect(),
+            drifting: self.camera.as_ref().map(|camera| camera.drifting),
+            presets: &self.presets,
+            centered: self.centered_patterns,
+        });
+        if let Some(trayicon) = &mut self.trayicon {
+            if let Err(e) = trayicon.set_menu(&menu) {
+                tracing::warn!("Failed to update the tray menu: {:?}", e);
+            }
+        }
+    }
+
+    // Check the speed that's running in the tray menu, or Paused
+    pub(super) fn update_speed_menu(&mut self) {
+        let paused = self.is_paused();
+        for speed in SPEEDS {
+            self.check_menu_item(UserEvents::Speed(speed), !paused && speed == self.speed);
+        }
+        self.check_menu_item(UserEvents::Speed(0), paused);
+    }
+
+    // Show the tick and populations of each simulation when hovering over the tray icon
+    pub(super) fn update_tooltip(&mut self) {
+        if self.last_tooltip_update.elapsed() < TOOLTIP_INTERVAL {
+            return;
+        }
+        self.last_tooltip_update = Instant::now();
+
+        let mut tooltip = String::from("Automata");
+        for screen in &self.screens {
+            let simulation = &screen.simulation;
+            tooltip.push_str(&format!("\nTick {}", simulation.ticks()));
+            if let Some(sample) = simulation.statistics.latest() {
+                tooltip.push_str(&format!(": {}", sample.summary()));
+            }
+        }
+        if let Some(usage) = &self.usage {
+            if let (Some(cpu), Some(memory)) = (usage.cpu_percent, usage.memory_percent) {
+                tooltip.push_str(&format!("\nCPU {:.0}%, memory {:.0}%", cpu, memory));
+            }
+        }
+        if let Some(trayicon) = &mut self.trayicon {
+            let _ = trayicon.set_tooltip(&tooltip);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use automata_background::{AutomatonKind, CellType, Config, Simulation};
+
+fn simulation(kind: AutomatonKind, size: (usize, usize)) -> Simulation {
+    let config = Config::default();
+    Simulation::new(size, kind.create(&config), 1, config.cycle_window)
+}
+
+#[test]
+fn every_automaton_steps() {
+    for kind in AutomatonKind::ALL {
+        let mut simulation = simulation(kind, (64, 48));
+        for _ in 0..20 {
+            simulation.step();
+        }
+        assert_eq!(simulation.ticks(), 20, "{:?}", kind);
+        assert_eq!(simulation.world().size, (64, 48), "{:?}", kind);
+    }
+}
+
+#[test]
+fn the_first_step_seeds_the_world() {
+    let mut simulation = simulation(AutomatonKind::Life, (64, 48));
+    assert!(!simulation.world().types().contains(&CellType::Alive));
+    simulation.step();
+    assert!(simulation.world().types().contains(&CellType::Alive));
+}
+
+#[test]
+fn the_same_seed_gives_the_same_run() {
+    let (mut first, mut second) = (
+        simulation(AutomatonKind::Life, (64, 48)),
+        simulation(AutomatonKind::Life, (64, 48)),
+    );
+    for _ in 0..20 {
+        first.step();
+        second.step();
+    }
+    assert_eq!(first.world().types(), second.world().types());
+}
+
+#[test]
+fn worlds_smaller_than_a_cell_are_left_alone() {
+    for kind in AutomatonKind::ALL {
+        for size in [(0, 0), (0, 48), (64, 0)] {
+            let mut simulation = simulation(kind, size);
+            simulation.step();
+            simulation.reset_simulation();
+            assert_eq!(simulation.ticks(), 0, "{:?} {:?}", kind, size);
+            assert!(!simulation.is_extinct(), "{:?} {:?}", kind, size);
+        }
+    }
+}
+
+#[test]
+fn resizing_down_to_nothing_and_back() {
+    for kind in AutomatonKind::ALL {
+        let mut simulation = simulation(kind, (64, 48));
+        for size in [(0, 0), (1, 1), (32, 24), (0, 24), (96, 64)] {
+            simulation.resize(size);
+            for _ in 0..5 {
+                simulation.step();
+            }
+            assert_eq!(simulation.world().size, size, "{:?}", kind);
+        }
+    }
+}